@@ -13,10 +13,26 @@ use std::sync::Arc;
 
 use bigbrother::prelude::*;
 use bigbrother::error::{Error, ErrorCode};
+use bigbrother::AttributeRequest;
+use bigbrother::Chord;
+use bigbrother::clipboard::{self, ClipboardContent, ClipboardFormat, ClipboardWatcher, Selection};
+use bigbrother::lease::{InputLease, LeaseOptions, LEASE_TIMEOUT_MS};
+use bigbrother::mouse::parse_point;
+use bigbrother::{MotionProfile, MouseButton, Normalization, PacingPolicy, Scroll, ScrollUnit, TextEntryOptions, VerifyOptions};
 
 // macOS-only imports
 #[cfg(target_os = "macos")]
 use bigbrother::input;
+#[cfg(target_os = "macos")]
+use bigbrother::attributes::value_text;
+#[cfg(target_os = "macos")]
+use bigbrother::launch::parse_env_pair;
+#[cfg(target_os = "macos")]
+use bigbrother::{App, DialogRule, LaunchOptions, ObserveOptions, ScrollUntilOptions, TableOptions, TreeResult, Window};
+
+// Windows-only imports
+#[cfg(target_os = "windows")]
+use bigbrother::Role;
 
 #[derive(Parser)]
 #[command(name = "bb")]
@@ -25,6 +41,9 @@ use bigbrother::input;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// How long input commands wait for another agent to release the input, in ms
+    #[arg(long, global = true, default_value_t = LEASE_TIMEOUT_MS)]
+    lease_timeout: u64,
 }
 
 #[derive(Subcommand)]
@@ -44,6 +63,21 @@ enum Commands {
         file: String,
         #[arg(short, long, default_value = "1.0")]
         speed: f64,
+        /// Glide between recorded positions: linear, eased or bezier, e.g. bezier:300
+        #[arg(long)]
+        motion: Option<String>,
+        /// Pace for apps that drop input: slower keys, settling, at most 10 actions/s
+        #[arg(long)]
+        gentle: bool,
+        /// Pause after each typed character, in ms
+        #[arg(long)]
+        key_delay: Option<u64>,
+        /// Pause after each click, key, scroll or text, in ms
+        #[arg(long)]
+        settle: Option<u64>,
+        /// Cap on actions per second
+        #[arg(long)]
+        max_rate: Option<f64>,
     },
     /// List saved workflows
     List,
@@ -68,12 +102,78 @@ enum Commands {
     Apps,
     /// Find a browser
     Browser,
+    /// Launch an app and wait for its first window
+    Launch {
+        /// App name, bundle id, .app path or executable path
+        target: String,
+        /// Environment variable as KEY=VALUE (repeatable)
+        #[arg(long = "env")]
+        env: Vec<String>,
+        /// Return as soon as the app is running
+        #[arg(long)]
+        no_wait: bool,
+        #[arg(long, default_value = "15000")]
+        timeout: u64,
+        /// Arguments for the app, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Watch for dialogs and handle them by rule, then print what happened
+    Dialogs {
+        #[arg(long)]
+        app: Option<String>,
+        /// `<selector> => <action>`, action being click:<selector>, cancel, confirm or ignore (repeatable)
+        #[arg(long = "rule")]
+        rules: Vec<String>,
+        /// Seconds to watch
+        #[arg(long, default_value = "60")]
+        duration: u64,
+    },
+    /// Quit an app, escalating to SIGTERM and SIGKILL if it does not exit
+    Quit {
+        app: String,
+        /// Skip the quit request and SIGKILL right away
+        #[arg(long)]
+        force: bool,
+        /// How long each step gets before escalating
+        #[arg(long, default_value = "5000")]
+        grace: u64,
+    },
     /// Get accessibility tree for an app
     Tree {
         #[arg(long)]
         app: String,
         #[arg(long, default_value = "15")]
         depth: usize,
+        /// Print what changed since a snapshot saved from a previous `bb tree`
+        #[arg(long)]
+        diff_against: Option<String>,
+    },
+    /// Compact, token-budgeted view of an app for an LLM prompt
+    Observe {
+        #[arg(long)]
+        app: String,
+        /// Token budget (about 4 characters per token)
+        #[arg(long, default_value = "2000", conflicts_with = "chars")]
+        tokens: usize,
+        /// Character budget, instead of --tokens
+        #[arg(long)]
+        chars: Option<usize>,
+        #[arg(long, default_value = "15")]
+        depth: usize,
+        /// Print the rendered text instead of JSON
+        #[arg(long)]
+        text: bool,
+    },
+    /// Interactive elements with numbered marks, optionally drawn over a screenshot
+    Marks {
+        #[arg(long)]
+        app: String,
+        #[arg(long, default_value = "15")]
+        depth: usize,
+        /// Capture the main display and save it here with numbered boxes drawn on
+        #[arg(long)]
+        screenshot: Option<String>,
     },
     /// Find elements matching selector
     Find {
@@ -82,6 +182,81 @@ enum Commands {
         app: Option<String>,
         #[arg(long, default_value = "5000")]
         timeout: u64,
+        /// Include native attributes: `all` or a comma-separated list such as `AXURL,AXHelp`
+        #[arg(long)]
+        attrs: Option<String>,
+        /// Only search the app window with this title
+        #[arg(long)]
+        window: Option<String>,
+    },
+    /// List windows with title, bounds and state
+    Windows {
+        #[arg(long)]
+        app: Option<String>,
+    },
+    /// Move, resize, minimize, maximize, restore, close or raise a window
+    Window {
+        action: String,
+        #[arg(long)]
+        app: String,
+        /// Window title; the app's first window when omitted
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long)]
+        x: Option<f64>,
+        #[arg(long)]
+        y: Option<f64>,
+        #[arg(long)]
+        width: Option<f64>,
+        #[arg(long)]
+        height: Option<f64>,
+    },
+    /// Read, write or watch the clipboard: get, set, formats or watch
+    Clipboard {
+        action: String,
+        /// Content to set; read from --file when omitted
+        value: Option<String>,
+        /// text, html, uri-list or png
+        #[arg(long, default_value = "text")]
+        format: String,
+        /// File to write the content to on get, or read it from on set
+        #[arg(long)]
+        file: Option<String>,
+        /// clipboard, or primary on X11
+        #[arg(long, default_value = "clipboard")]
+        selection: String,
+        /// Seconds to watch for changes
+        #[arg(long, default_value = "10")]
+        duration: u64,
+    },
+    /// Read a table, grid or outline as rows and columns
+    Table {
+        #[arg(default_value = "role:table")]
+        selector: String,
+        #[arg(long)]
+        app: Option<String>,
+        /// Output format: json or csv
+        #[arg(long, default_value = "json")]
+        format: String,
+        /// Scroll through the table to collect rows that are not on screen
+        #[arg(long)]
+        scroll: bool,
+        #[arg(long, default_value = "10000")]
+        max_rows: usize,
+    },
+    /// Choose a menu item by path, e.g. "File > Export > PDF…"
+    Menu {
+        path: String,
+        #[arg(long)]
+        app: Option<String>,
+        /// Open this element's context menu instead of the menu bar
+        #[arg(long)]
+        selector: Option<String>,
+    },
+    /// Fill form fields by label; reads a JSON object of label -> value from stdin
+    Fill {
+        #[arg(long)]
+        app: String,
     },
     /// Click an element
     Click {
@@ -96,17 +271,52 @@ enum Commands {
         selector: Option<String>,
         #[arg(long)]
         app: Option<String>,
+        /// Read the field back and retry slower on a mismatch
+        #[arg(long, requires = "selector")]
+        verify: bool,
+        /// How read-back text is compared: default, exact or loose
+        #[arg(long, default_value = "default", requires = "verify")]
+        normalize: String,
+        /// Clear the field first, so it must match the text exactly
+        #[arg(long, requires = "verify")]
+        clear: bool,
     },
-    /// Scroll up or down
+    /// Scroll by pages, or by lines or pixels at a point or element
     Scroll {
+        /// up, down, left or right (pages only go up or down)
         #[arg(long, default_value = "down")]
         direction: String,
         #[arg(long, default_value = "1")]
         pages: u32,
+        #[arg(long, conflicts_with = "pixels")]
+        lines: Option<i32>,
+        #[arg(long)]
+        pixels: Option<i32>,
+        /// Scroll with the pointer at `x,y` (default: where it is)
+        #[arg(long, conflicts_with = "selector")]
+        at: Option<String>,
+        /// Scroll with the pointer over this element
+        #[arg(long)]
+        selector: Option<String>,
+        #[arg(long)]
+        app: Option<String>,
+    },
+    /// Scroll a container until an element inside it is visible
+    ScrollUntil {
+        target: String,
+        #[arg(long)]
+        container: String,
         #[arg(long)]
         app: Option<String>,
+        #[arg(long, default_value = "down")]
+        direction: String,
+        /// Lines per step
+        #[arg(long, default_value = "5")]
+        lines: i32,
+        #[arg(long, default_value = "50")]
+        max_steps: usize,
     },
-    /// Press a key
+    /// Press a key or chord, e.g. `escape` or `cmd+shift+t`
     Press {
         key: String,
         #[arg(long, default_value = "1")]
@@ -157,6 +367,21 @@ enum Commands {
         y: i32,
         #[arg(long, default_value = "left")]
         button: String,
+        /// Move there first: linear, eased or bezier, e.g. bezier:300
+        #[arg(long, default_value = "instant")]
+        motion: String,
+    },
+    /// Drag between two points (`x,y`) or two elements (selectors)
+    Drag {
+        from: String,
+        to: String,
+        #[arg(long)]
+        app: Option<String>,
+        /// Milliseconds from press to release
+        #[arg(long, default_value = "300")]
+        duration: u64,
+        #[arg(long, default_value = "left")]
+        button: String,
     },
     /// Send text to an app
     Send {
@@ -209,81 +434,13 @@ fn print_json<T: Serialize>(output: &T) {
     println!("{}", serde_json::to_string_pretty(output).unwrap());
 }
 
-// ── macOS key code mapping ──────────────────────────────────────────────────
-
-#[cfg(target_os = "macos")]
-fn key_name_to_code(name: &str) -> Option<u8> {
-    match name.to_lowercase().as_str() {
-        "pageup" | "page_up" => Some(input::key_codes::PAGE_UP),
-        "pagedown" | "page_down" => Some(input::key_codes::PAGE_DOWN),
-        "return" | "enter" => Some(input::key_codes::RETURN),
-        "tab" => Some(input::key_codes::TAB),
-        "escape" | "esc" => Some(input::key_codes::ESCAPE),
-        "space" => Some(input::key_codes::SPACE),
-        "delete" | "backspace" => Some(input::key_codes::DELETE),
-        "up" | "arrow_up" => Some(input::key_codes::ARROW_UP),
-        "down" | "arrow_down" => Some(input::key_codes::ARROW_DOWN),
-        "left" | "arrow_left" => Some(input::key_codes::ARROW_LEFT),
-        "right" | "arrow_right" => Some(input::key_codes::ARROW_RIGHT),
-        "home" => Some(input::key_codes::HOME),
-        "end" => Some(input::key_codes::END),
-        _ => None,
-    }
-}
-
-// ── Windows key code mapping ────────────────────────────────────────────────
-
-#[cfg(target_os = "windows")]
-fn key_name_to_vk(name: &str) -> Option<u16> {
-    match name.to_lowercase().as_str() {
-        "pageup" | "page_up" => Some(vk::PAGE_UP),
-        "pagedown" | "page_down" => Some(vk::PAGE_DOWN),
-        "return" | "enter" => Some(vk::RETURN),
-        "tab" => Some(vk::TAB),
-        "escape" | "esc" => Some(vk::ESCAPE),
-        "space" => Some(vk::SPACE),
-        "delete" | "backspace" => Some(vk::BACKSPACE),
-        "up" | "arrow_up" => Some(vk::UP),
-        "down" | "arrow_down" => Some(vk::DOWN),
-        "left" | "arrow_left" => Some(vk::LEFT),
-        "right" | "arrow_right" => Some(vk::RIGHT),
-        "home" => Some(vk::HOME),
-        "end" => Some(vk::END),
-        "f1" => Some(vk::F1),
-        "f4" => Some(0x73), // VK_F4
-        "f12" => Some(vk::F12),
-        // Single letter keys
-        k if k.len() == 1 => {
-            let c = k.chars().next().unwrap().to_ascii_uppercase();
-            if c.is_ascii_alphabetic() {
-                Some(c as u16)
-            } else if c.is_ascii_digit() {
-                Some(c as u16)
-            } else {
-                None
-            }
-        }
-        _ => None,
-    }
-}
-
-#[cfg(target_os = "windows")]
-fn modifier_name_to_vk(name: &str) -> u16 {
-    match name.trim().to_lowercase().as_str() {
-        "ctrl" | "control" => vk::CONTROL,
-        "alt" | "option" | "menu" => vk::ALT,
-        "shift" => vk::SHIFT,
-        "win" | "super" | "cmd" | "command" => vk::LWIN,
-        _ => vk::CONTROL,
-    }
-}
-
 // ── Windows element helpers ─────────────────────────────────────────────────
 
 #[cfg(target_os = "windows")]
 #[derive(Serialize)]
 struct ElementInfo {
     name: Option<String>,
+    role: Role,
     control_type: &'static str,
     control_type_id: i32,
     bounds: Option<(i32, i32, i32, i32)>,
@@ -297,6 +454,7 @@ impl ElementInfo {
     fn from_element(el: &Element) -> Self {
         Self {
             name: el.name(),
+            role: Role::from_native(el.control_type_name()),
             control_type: el.control_type_name(),
             control_type_id: el.control_type(),
             bounds: el.bounds(),
@@ -385,7 +543,9 @@ fn matches_selector(element: &Element, selector: &str) -> bool {
 
         if cond.starts_with("role:") {
             let expected_role = &cond[5..];
-            if !element.control_type_name().eq_ignore_ascii_case(expected_role) {
+            let native = element.control_type_name();
+            let canonical = Role::from_name(expected_role) == Some(Role::from_native(native));
+            if !canonical && !native.eq_ignore_ascii_case(expected_role) {
                 return false;
             }
         } else if cond.starts_with("name~:") || cond.starts_with("title~:") {
@@ -419,9 +579,24 @@ fn find_app_window(app_name: &str) -> Result<Element> {
 fn main() {
     let cli = Cli::parse();
 
+    let _lease = match hold_input(&cli.command, cli.lease_timeout) {
+        Ok(lease) => lease,
+        Err(e) => {
+            print_json(&Output::<()>::err(e.clone()));
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let result: Result<(), anyhow::Error> = match cli.command {
         Commands::Record { name, no_context, threshold } => record(&name, !no_context, threshold),
-        Commands::Replay { file, speed } => replay(&file, speed),
+        Commands::Replay { file, speed, motion, gentle, key_delay, settle, max_rate } => {
+            let mut pacing = if gentle { PacingPolicy::gentle() } else { bigbrother::recorder::replay_pacing() };
+            pacing.key_delay_ms = key_delay.unwrap_or(pacing.key_delay_ms);
+            pacing.settle_ms = settle.unwrap_or(pacing.settle_ms);
+            pacing.max_actions_per_sec = max_rate.or(pacing.max_actions_per_sec);
+            replay(&file, speed, motion.as_deref(), pacing)
+        }
         Commands::List => list(),
         Commands::Show { file, all } => show(&file, all),
         Commands::Delete { file } => delete(&file),
@@ -430,11 +605,47 @@ fn main() {
         // ── Automation (platform-dispatched) ──────────────────────────────
         Commands::Apps => run_automation(cmd_apps),
         Commands::Browser => run_automation(cmd_browser),
-        Commands::Tree { app, depth } => run_automation(move || cmd_tree(&app, depth)),
-        Commands::Find { selector, app, timeout } => run_automation(move || cmd_find(&selector, app.as_deref(), timeout)),
+        Commands::Launch { target, env, no_wait, timeout, args } => {
+            run_automation(move || cmd_launch(&target, &env, &args, !no_wait, timeout))
+        }
+        Commands::Dialogs { app, rules, duration } => run_automation(move || cmd_dialogs(app.as_deref(), &rules, duration)),
+        Commands::Quit { app, force, grace } => run_automation(move || cmd_quit(&app, force, grace)),
+        Commands::Tree { app, depth, diff_against } => run_automation(move || cmd_tree(&app, depth, diff_against.as_deref())),
+        Commands::Observe { app, tokens, chars, depth, text } => run_automation(move || cmd_observe(&app, tokens, chars, depth, text)),
+        Commands::Marks { app, depth, screenshot } => run_automation(move || cmd_marks(&app, depth, screenshot.as_deref())),
+        Commands::Find { selector, app, timeout, attrs, window } => {
+            let attrs = attrs.as_deref().map(AttributeRequest::parse);
+            run_automation(move || cmd_find(&selector, app.as_deref(), window.as_deref(), timeout, attrs.as_ref()))
+        }
+        Commands::Windows { app } => run_automation(move || cmd_windows(app.as_deref())),
+        Commands::Window { action, app, title, x, y, width, height } => {
+            run_automation(move || cmd_window(&action, &app, title.as_deref(), (x, y), (width, height)))
+        }
+        Commands::Clipboard { action, value, format, file, selection, duration } => {
+            run_automation(move || cmd_clipboard(&action, value.as_deref(), &format, file.as_deref(), &selection, duration))
+        }
+        Commands::Table { selector, app, format, scroll, max_rows } => {
+            run_automation(move || cmd_table(&selector, app.as_deref(), &format, scroll, max_rows))
+        }
+        Commands::Menu { path, app, selector } => {
+            run_automation(move || cmd_menu(&path, app.as_deref(), selector.as_deref()))
+        }
+        Commands::Fill { app } => run_automation(move || cmd_fill(&app)),
         Commands::Click { selector, app } => run_automation(move || cmd_click(&selector, app.as_deref())),
-        Commands::Type { text, selector, app } => run_automation(move || cmd_type(&text, selector.as_deref(), app.as_deref())),
-        Commands::Scroll { direction, pages, app } => run_automation(move || cmd_scroll(&direction, pages, app.as_deref())),
+        Commands::Type { text, selector, app, verify, normalize, clear } => {
+            let verify = verify_options(verify, &normalize, clear);
+            run_automation(move || cmd_type(&text, selector.as_deref(), app.as_deref(), verify?))
+        }
+        Commands::Scroll { direction, pages, lines, pixels, at, selector, app } => {
+            let precise = precise_scroll(&direction, lines, pixels);
+            run_automation(move || match precise? {
+                Some(scroll) => cmd_scroll_precise(&scroll, at.as_deref(), selector.as_deref(), app.as_deref()),
+                None => cmd_scroll(&direction, pages, app.as_deref()),
+            })
+        }
+        Commands::ScrollUntil { target, container, app, direction, lines, max_steps } => {
+            run_automation(move || cmd_scroll_until(&target, &container, app.as_deref(), &direction, lines, max_steps))
+        }
         Commands::Press { key, repeat, delay } => run_automation(move || cmd_press(&key, repeat, delay)),
         Commands::Open { url } => run_automation(move || cmd_open(&url)),
         Commands::Wait { idle, selector, app, timeout } => run_automation(move || cmd_wait(idle, selector.as_deref(), app.as_deref(), timeout)),
@@ -442,7 +653,10 @@ fn main() {
         Commands::Scrape { app, depth } => run_automation(move || cmd_scrape(&app, depth)),
         Commands::Shortcut { key, modifiers } => run_automation(move || cmd_shortcut(&key, &modifiers)),
         Commands::Activate { app } => run_automation(move || cmd_activate(&app)),
-        Commands::ClickAt { x, y, button } => run_automation(move || cmd_click_at(x, y, &button)),
+        Commands::ClickAt { x, y, button, motion } => run_automation(move || cmd_click_at(x, y, &button, &motion)),
+        Commands::Drag { from, to, app, duration, button } => {
+            run_automation(move || cmd_drag(&from, &to, app.as_deref(), duration, &button))
+        }
         Commands::Send { text, app, no_enter } => run_automation(move || cmd_send(&text, &app, no_enter)),
         Commands::Wezterm { action } => cmd_wezterm(action),
     };
@@ -453,6 +667,70 @@ fn main() {
     }
 }
 
+impl Commands {
+    /// Whether the command drives the keyboard or mouse, presses elements or
+    /// moves focus and windows under another agent's input
+    ///
+    /// Long-running commands are left out: `dialogs` takes the lease for each
+    /// action it handles, and `launch` only waits for a window.
+    fn uses_input(&self) -> bool {
+        matches!(
+            self,
+            Commands::Replay { .. }
+                | Commands::Window { .. }
+                | Commands::Table { scroll: true, .. }
+                | Commands::Menu { .. }
+                | Commands::Fill { .. }
+                | Commands::Click { .. }
+                | Commands::Type { .. }
+                | Commands::Scroll { .. }
+                | Commands::ScrollUntil { .. }
+                | Commands::Press { .. }
+                | Commands::Open { .. }
+                | Commands::Shortcut { .. }
+                | Commands::Activate { .. }
+                | Commands::ClickAt { .. }
+                | Commands::Drag { .. }
+                | Commands::Send { .. }
+        )
+    }
+}
+
+/// Input commands hold the input lease from start to finish, so agents
+/// sharing the desktop take turns, see `bigbrother::lease`
+fn hold_input(command: &Commands, timeout_ms: u64) -> Result<Option<InputLease>, Error> {
+    if !command.uses_input() {
+        return Ok(None);
+    }
+    let options = LeaseOptions {
+        timeout: std::time::Duration::from_millis(timeout_ms),
+        ..Default::default()
+    };
+    InputLease::acquire(&options).map(Some)
+}
+
+/// Options for `type --verify`, `None` without it
+fn verify_options(verify: bool, normalize: &str, clear: bool) -> Result<Option<VerifyOptions>> {
+    if !verify {
+        return Ok(None);
+    }
+    Ok(Some(VerifyOptions {
+        normalization: Normalization::parse(normalize)?,
+        clear_first: clear,
+        ..Default::default()
+    }))
+}
+
+/// A line or pixel scroll when either amount was given; pages otherwise
+fn precise_scroll(direction: &str, lines: Option<i32>, pixels: Option<i32>) -> Result<Option<Scroll>> {
+    let amount = match (lines, pixels) {
+        (Some(n), _) => (n, ScrollUnit::Lines),
+        (None, Some(n)) => (n, ScrollUnit::Pixels),
+        (None, None) => return Ok(None),
+    };
+    Ok(Some(Scroll::toward(direction, amount.0, amount.1)?))
+}
+
 fn run_automation<F>(f: F) -> Result<(), anyhow::Error>
 where F: FnOnce() -> Result<(), anyhow::Error> {
     match f() {
@@ -478,6 +756,84 @@ fn cmd_apps() -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "macos")]
+fn cmd_launch(target: &str, env: &[String], args: &[String], wait: bool, timeout: u64) -> Result<()> {
+    let mut options = LaunchOptions::default()
+        .args(args.iter().cloned())
+        .wait_for_window(wait)
+        .timeout(timeout);
+    for pair in env {
+        let (key, value) = parse_env_pair(pair)?;
+        options = options.env(key, value);
+    }
+    let desktop = Desktop::new()?;
+    let app = desktop.launch(target, options)?;
+    print_json(&Output::ok(app.info()));
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_dialogs(app: Option<&str>, rules: &[String], duration: u64) -> Result<()> {
+    let rules = rules
+        .iter()
+        .map(|r| DialogRule::parse(r))
+        .collect::<bigbrother::Result<Vec<_>>>()?;
+    let desktop = Desktop::new()?;
+    let desktop = match app {
+        Some(a) => desktop.in_app(a),
+        None => desktop,
+    };
+    let watcher = desktop.watch_dialogs(rules);
+    std::thread::sleep(std::time::Duration::from_secs(duration));
+    print_json(&Output::ok(watcher.stop()));
+    Ok(())
+}
+
+fn cmd_clipboard(action: &str, value: Option<&str>, format: &str, file: Option<&str>, selection: &str, duration: u64) -> Result<()> {
+    let selection = Selection::parse(selection)?;
+    let format = ClipboardFormat::parse(format)?;
+    match action.to_lowercase().as_str() {
+        "get" => match (clipboard::get(selection, format)?, file) {
+            (Some(content), Some(path)) => {
+                let bytes = content.to_bytes();
+                std::fs::write(path, &bytes)?;
+                print_json(&Output::ok(serde_json::json!({"format": format, "file": path, "bytes": bytes.len()})));
+            }
+            (Some(ClipboardContent::Png(_)), None) => {
+                return Err(Error::new(ErrorCode::InvalidArgument, "png content needs --file".to_string()).into());
+            }
+            (content, _) => print_json(&Output::ok(content)),
+        },
+        "set" => {
+            let bytes = match (value, file) {
+                (Some(v), _) => v.as_bytes().to_vec(),
+                (None, Some(path)) => std::fs::read(path)?,
+                (None, None) => {
+                    return Err(Error::new(ErrorCode::InvalidArgument, "set needs a value or --file".to_string()).into())
+                }
+            };
+            clipboard::set(selection, &[ClipboardContent::from_bytes(format, bytes)?])?;
+            print_json(&Output::ok(serde_json::json!({"selection": selection, "format": format})));
+        }
+        "formats" => print_json(&Output::ok(clipboard::formats(selection)?)),
+        "watch" => {
+            let watcher = ClipboardWatcher::start(vec![selection], std::time::Duration::from_millis(clipboard::WATCH_INTERVAL_MS));
+            std::thread::sleep(std::time::Duration::from_secs(duration));
+            print_json(&Output::ok(watcher.stop()));
+        }
+        _ => return Err(Error::invalid_argument("clipboard action", action, "expected get, set, formats or watch").into()),
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_quit(app: &str, force: bool, grace: u64) -> Result<()> {
+    let mut app = App::attach(app)?;
+    let exit = if force { app.kill()? } else { app.quit(grace)? };
+    print_json(&Output::ok(exit));
+    Ok(())
+}
+
 #[cfg(target_os = "macos")]
 fn cmd_browser() -> Result<()> {
     let desktop = Desktop::new()?;
@@ -487,27 +843,212 @@ fn cmd_browser() -> Result<()> {
 }
 
 #[cfg(target_os = "macos")]
-fn cmd_tree(app: &str, depth: usize) -> Result<()> {
+fn cmd_tree(app: &str, depth: usize, diff_against: Option<&str>) -> Result<()> {
     let mut desktop = Desktop::new()?;
     let tree = desktop.tree(app, depth)?;
-    print_json(&Output::ok(tree));
+    match diff_against {
+        Some(path) => {
+            let before = load_tree_snapshot(path)?;
+            print_json(&Output::ok(before.diff(&tree)));
+        }
+        None => print_json(&Output::ok(tree)),
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_observe(app: &str, tokens: usize, chars: Option<usize>, depth: usize, text: bool) -> Result<()> {
+    let mut desktop = Desktop::new()?;
+    let options = match chars {
+        Some(c) => ObserveOptions::default().chars(c),
+        None => ObserveOptions::default().tokens(tokens),
+    }
+    .depth(depth);
+    let observation = desktop.observe(app, &options)?;
+    if text {
+        println!("{}", observation.text);
+    } else {
+        print_json(&Output::ok(observation));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_marks(app: &str, depth: usize, screenshot: Option<&str>) -> Result<()> {
+    let mut desktop = Desktop::new()?;
+    let marks = desktop.set_of_marks(app, depth)?;
+    let mut data = serde_json::to_value(&marks)?;
+    if let Some(output) = screenshot {
+        let capture = std::env::temp_dir().join(format!("bb-marks-{}.png", std::process::id()));
+        let status = std::process::Command::new("screencapture")
+            .args(["-x", "-m"])
+            .arg(&capture)
+            .status()?;
+        if !status.success() { anyhow::bail!("screencapture failed"); }
+        let png = std::fs::read(&capture)?;
+        let _ = std::fs::remove_file(&capture);
+        std::fs::write(output, marks.draw(&png)?)?;
+        data["screenshot"] = serde_json::json!(output);
+    }
+    print_json(&Output::ok(data));
     Ok(())
 }
 
+/// Load a tree saved from `bb tree`, either the raw result or the `{success, data}` envelope
 #[cfg(target_os = "macos")]
-fn cmd_find(selector: &str, app: Option<&str>, timeout: u64) -> Result<()> {
+fn load_tree_snapshot(path: &str) -> Result<TreeResult> {
+    let text = std::fs::read_to_string(path)?;
+    let mut json: serde_json::Value = serde_json::from_str(&text)?;
+    if let Some(data) = json.get_mut("data") {
+        json = data.take();
+    }
+    serde_json::from_value(json).map_err(|e| {
+        Error::new(ErrorCode::Unknown, format!("Not a tree snapshot: {}: {}", path, e)).into()
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_find(selector: &str, app: Option<&str>, window: Option<&str>, timeout: u64, attrs: Option<&AttributeRequest>) -> Result<()> {
     let desktop = Desktop::new()?;
     let desktop = match app {
         Some(a) => desktop.in_app(a),
         None => desktop,
     };
+    let desktop = match window {
+        Some(w) => desktop.in_window(w),
+        None => desktop,
+    };
     let loc = desktop.locator(selector)?.timeout(timeout);
     let elements = loc.find_all()?;
-    let infos: Vec<_> = elements.iter().map(|e| e.info()).collect();
+    let infos: Vec<_> = elements
+        .iter()
+        .map(|e| match attrs {
+            Some(request) => e.info_with_attributes(request),
+            None => e.info(),
+        })
+        .collect();
+    print_json(&Output::ok(infos));
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_windows(app: Option<&str>) -> Result<()> {
+    let windows = match app {
+        Some(a) => Window::for_app(a)?,
+        None => Window::all()?,
+    };
+    let infos: Vec<_> = windows.iter().map(|w| w.info()).collect();
     print_json(&Output::ok(infos));
     Ok(())
 }
 
+#[cfg(target_os = "macos")]
+fn cmd_window(action: &str, app: &str, title: Option<&str>, pos: (Option<f64>, Option<f64>), size: (Option<f64>, Option<f64>)) -> Result<()> {
+    let window = match title {
+        Some(t) => Window::find(app, t)?,
+        None => Window::for_app(app)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::element_not_found(&format!("window of {}", app)))?,
+    };
+    match action.to_lowercase().as_str() {
+        "move" => match pos {
+            (Some(x), Some(y)) => window.move_to(x, y)?,
+            _ => return Err(Error::new(ErrorCode::Unknown, "move needs --x and --y".to_string()).into()),
+        },
+        "resize" => match size {
+            (Some(w), Some(h)) => window.resize(w, h)?,
+            _ => return Err(Error::new(ErrorCode::Unknown, "resize needs --width and --height".to_string()).into()),
+        },
+        "minimize" => window.minimize()?,
+        "maximize" => window.maximize()?,
+        "restore" => window.restore()?,
+        "close" => window.close()?,
+        "raise" | "focus" => window.raise()?,
+        _ => return Err(Error::new(ErrorCode::Unknown, format!("Unknown window action: {}", action)).into()),
+    }
+    print_json(&Output::ok(serde_json::json!({"action": action, "window": window.info()})));
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_table(selector: &str, app: Option<&str>, format: &str, scroll: bool, max_rows: usize) -> Result<()> {
+    let csv = match format.to_lowercase().as_str() {
+        "json" => false,
+        "csv" => true,
+        _ => return Err(Error::new(ErrorCode::Unknown, format!("Unknown format: {}", format)).into()),
+    };
+    let desktop = Desktop::new()?;
+    let desktop = match app {
+        Some(a) => desktop.in_app(a),
+        None => desktop,
+    };
+    let element = desktop.locator(selector)?.find()?;
+    let table = element.table(&TableOptions::default().scroll(scroll).max_rows(max_rows))?;
+    if csv {
+        print!("{}", table.to_csv());
+    } else {
+        print_json(&Output::ok(table));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_menu(path: &str, app: Option<&str>, selector: Option<&str>) -> Result<()> {
+    let desktop = Desktop::new()?;
+    let desktop = match app {
+        Some(a) => desktop.in_app(a),
+        None => desktop,
+    };
+    let result = match selector {
+        Some(sel) => desktop.locator(sel)?.find()?.context_menu(path)?,
+        None => desktop.menu(path)?,
+    };
+    print_json(&Output::ok(result));
+    Ok(())
+}
+
+/// Label -> value pairs in the order stdin lists them, which is the order
+/// fields get filled in; `serde_json::Map` would sort them
+#[cfg(target_os = "macos")]
+struct OrderedFields(Vec<(String, serde_json::Value)>);
+
+#[cfg(target_os = "macos")]
+impl<'de> serde::Deserialize<'de> for OrderedFields {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct FieldsVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for FieldsVisitor {
+            type Value = OrderedFields;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a JSON object of label -> value")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<OrderedFields, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    fields.push(entry);
+                }
+                Ok(OrderedFields(fields))
+            }
+        }
+
+        deserializer.deserialize_map(FieldsVisitor)
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_fill(app: &str) -> Result<()> {
+    let OrderedFields(fields) = serde_json::from_reader(io::stdin())
+        .map_err(|e| Error::new(ErrorCode::Unknown, format!("Expected a JSON object of label -> value on stdin: {}", e)))?;
+    let fields = fields.into_iter().map(|(label, value)| (label, value_text(&value)));
+    let desktop = Desktop::new()?;
+    let report = desktop.fill_form(app, fields)?;
+    print_json(&Output::ok(report));
+    Ok(())
+}
+
 #[cfg(target_os = "macos")]
 fn cmd_click(selector: &str, app: Option<&str>) -> Result<()> {
     let desktop = Desktop::new()?;
@@ -521,18 +1062,20 @@ fn cmd_click(selector: &str, app: Option<&str>) -> Result<()> {
 }
 
 #[cfg(target_os = "macos")]
-fn cmd_type(text: &str, selector: Option<&str>, app: Option<&str>) -> Result<()> {
+fn cmd_type(text: &str, selector: Option<&str>, app: Option<&str>, verify: Option<VerifyOptions>) -> Result<()> {
     let desktop = Desktop::new()?;
     if let Some(sel) = selector {
         let desktop = match app {
             Some(a) => desktop.in_app(a),
             None => desktop,
         };
-        let result = desktop.locator(sel)?.type_text(text)?;
-        print_json(&Output::ok(result));
+        match verify {
+            Some(options) => print_json(&Output::ok(desktop.type_verified(sel, text, &options)?)),
+            None => print_json(&Output::ok(desktop.locator(sel)?.type_text(text)?)),
+        }
     } else {
-        desktop.type_text(text)?;
-        print_json(&Output::ok(serde_json::json!({"typed": text})));
+        let report = desktop.enter_text(text, &TextEntryOptions::default())?.into_result()?;
+        print_json(&Output::ok(report));
     }
     Ok(())
 }
@@ -553,11 +1096,48 @@ fn cmd_scroll(direction: &str, pages: u32, app: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "macos")]
+fn cmd_scroll_precise(scroll: &Scroll, at: Option<&str>, selector: Option<&str>, app: Option<&str>) -> Result<()> {
+    let desktop = Desktop::new()?;
+    let desktop = match app {
+        Some(a) => desktop.in_app(a),
+        None => desktop,
+    };
+    if let Some(sel) = selector {
+        print_json(&Output::ok(desktop.locator(sel)?.find()?.scroll(scroll)?));
+        return Ok(());
+    }
+    let (x, y) = match at {
+        Some(point) => parse_point(point).ok_or_else(|| Error::selector_invalid(point, "expected x,y"))?,
+        None => input::mouse_position().map_err(Error::from)?,
+    };
+    desktop.scroll_at(x, y, scroll)?;
+    print_json(&Output::ok(serde_json::json!({"scrolled": scroll, "at": [x, y]})));
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_scroll_until(target: &str, container: &str, app: Option<&str>, direction: &str, lines: i32, max_steps: usize) -> Result<()> {
+    let desktop = Desktop::new()?;
+    let desktop = match app {
+        Some(a) => desktop.in_app(a),
+        None => desktop,
+    };
+    let options = ScrollUntilOptions {
+        step: Scroll::toward(direction, lines, ScrollUnit::Lines)?,
+        max_steps,
+        ..Default::default()
+    };
+    let found = desktop.scroll_until(container, target, &options)?;
+    print_json(&Output::ok(found.info()));
+    Ok(())
+}
+
 #[cfg(target_os = "macos")]
 fn cmd_press(key: &str, repeat: u32, delay: u64) -> Result<()> {
-    let code = key_name_to_code(key).ok_or_else(|| Error::new(ErrorCode::Unknown, format!("Unknown key: {}", key)))?;
+    let chord = Chord::parse(key)?;
     for i in 0..repeat {
-        input::press_key(code).map_err(Error::from)?;
+        input::press_chord(&chord).map_err(Error::from)?;
         if i < repeat - 1 {
             std::thread::sleep(std::time::Duration::from_millis(delay));
         }
@@ -613,15 +1193,10 @@ fn cmd_scrape(app: &str, depth: usize) -> Result<()> {
 
 #[cfg(target_os = "macos")]
 fn cmd_shortcut(key: &str, modifiers: &str) -> Result<()> {
-    let mods: Vec<&str> = modifiers.split(',').map(|m| match m.trim().to_lowercase().as_str() {
-        "cmd" | "command" => "command",
-        "ctrl" | "control" => "control",
-        "alt" | "option" => "option",
-        "shift" => "shift",
-        _ => "command",
-    }).collect();
-    input::shortcut(key, &mods).map_err(Error::from)?;
-    print_json(&Output::ok(serde_json::json!({"key": key, "modifiers": modifiers})));
+    let mods: Vec<&str> = modifiers.split(',').collect();
+    let chord = Chord::from_parts(key, &mods)?;
+    input::press_chord(&chord).map_err(Error::from)?;
+    print_json(&Output::ok(serde_json::json!({"key": key, "modifiers": modifiers, "chord": chord})));
     Ok(())
 }
 
@@ -634,12 +1209,43 @@ fn cmd_activate(app: &str) -> Result<()> {
 }
 
 #[cfg(target_os = "macos")]
-fn cmd_click_at(x: i32, y: i32, button: &str) -> Result<()> {
-    input::click_at(x, y, button).map_err(Error::from)?;
+fn cmd_click_at(x: i32, y: i32, button: &str, motion: &str) -> Result<()> {
+    let motion = MotionProfile::parse(motion)?;
+    input::click_at_with(x, y, button, &motion).map_err(Error::from)?;
     print_json(&Output::ok(serde_json::json!({"clicked": {"x": x, "y": y, "button": button}})));
     Ok(())
 }
 
+#[cfg(target_os = "macos")]
+fn cmd_drag(from: &str, to: &str, app: Option<&str>, duration: u64, button: &str) -> Result<()> {
+    let button = MouseButton::parse(button)?;
+    let duration = std::time::Duration::from_millis(duration);
+    match (parse_point(from), parse_point(to)) {
+        (Some(a), Some(b)) => {
+            input::drag(a, b, duration, button).map_err(Error::from)?;
+            print_json(&Output::ok(serde_json::json!({"dragged": {"from": a, "to": b, "button": button}})));
+        }
+        (None, None) => {
+            let desktop = Desktop::new()?;
+            let desktop = match app {
+                Some(a) => desktop.in_app(a),
+                None => desktop,
+            };
+            let source = desktop.locator(from)?.find()?;
+            let target = desktop.locator(to)?.find()?;
+            print_json(&Output::ok(source.drag_to_with(&target, duration, button)?));
+        }
+        _ => {
+            return Err(Error::selector_invalid(
+                &format!("{} -> {}", from, to),
+                "drag between two points or two selectors, not one of each",
+            )
+            .into())
+        }
+    }
+    Ok(())
+}
+
 #[cfg(target_os = "macos")]
 fn cmd_send(text: &str, app: &str, no_enter: bool) -> Result<()> {
     let desktop = Desktop::new()?;
@@ -667,6 +1273,21 @@ fn cmd_apps() -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn cmd_launch(_target: &str, _env: &[String], _args: &[String], _wait: bool, _timeout: u64) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "launch is macOS-only for now".to_string()).into())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_dialogs(_app: Option<&str>, _rules: &[String], _duration: u64) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "dialogs is macOS-only for now".to_string()).into())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_quit(_app: &str, _force: bool, _grace: u64) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "quit is macOS-only for now".to_string()).into())
+}
+
 #[cfg(target_os = "windows")]
 fn cmd_browser() -> Result<()> {
     let browsers = ["chrome", "firefox", "msedge", "brave", "opera"];
@@ -685,7 +1306,10 @@ fn cmd_browser() -> Result<()> {
 }
 
 #[cfg(target_os = "windows")]
-fn cmd_tree(app: &str, depth: usize) -> Result<()> {
+fn cmd_tree(app: &str, depth: usize, diff_against: Option<&str>) -> Result<()> {
+    if diff_against.is_some() {
+        return Err(Error::new(ErrorCode::NotImplemented, "tree --diff-against is macOS-only for now".to_string()).into());
+    }
     let automation = Automation::new()?;
     let window = find_app_window(app)?;
     let walker = automation.tree_walker()?;
@@ -698,15 +1322,28 @@ fn cmd_tree(app: &str, depth: usize) -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn cmd_observe(_app: &str, _tokens: usize, _chars: Option<usize>, _depth: usize, _text: bool) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "observe is macOS-only for now".to_string()).into())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_marks(_app: &str, _depth: usize, _screenshot: Option<&str>) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "marks is macOS-only for now".to_string()).into())
+}
+
 #[cfg(target_os = "windows")]
 fn count_nodes(node: &TreeNode) -> usize {
     1 + node.children.iter().map(count_nodes).sum::<usize>()
 }
 
 #[cfg(target_os = "windows")]
-fn cmd_find(selector: &str, app: Option<&str>, _timeout: u64) -> Result<()> {
+fn cmd_find(selector: &str, app: Option<&str>, window: Option<&str>, _timeout: u64, attrs: Option<&AttributeRequest>) -> Result<()> {
+    if attrs.is_some() {
+        return Err(Error::new(ErrorCode::NotImplemented, "--attrs is macOS-only for now".to_string()).into());
+    }
     let automation = Automation::new()?;
-    let root = if let Some(a) = app {
+    let root = if let Some(a) = window.or(app) {
         find_app_window(a)?
     } else {
         automation.root()?
@@ -718,6 +1355,31 @@ fn cmd_find(selector: &str, app: Option<&str>, _timeout: u64) -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn cmd_windows(_app: Option<&str>) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "windows is macOS-only for now".to_string()).into())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_window(_action: &str, _app: &str, _title: Option<&str>, _pos: (Option<f64>, Option<f64>), _size: (Option<f64>, Option<f64>)) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "window is macOS-only for now".to_string()).into())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_table(_selector: &str, _app: Option<&str>, _format: &str, _scroll: bool, _max_rows: usize) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "table is macOS-only for now".to_string()).into())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_menu(_path: &str, _app: Option<&str>, _selector: Option<&str>) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "menu is macOS-only for now".to_string()).into())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_fill(_app: &str) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "fill is macOS-only for now".to_string()).into())
+}
+
 #[cfg(target_os = "windows")]
 fn cmd_click(selector: &str, app: Option<&str>) -> Result<()> {
     let automation = Automation::new()?;
@@ -746,9 +1408,12 @@ fn cmd_click(selector: &str, app: Option<&str>) -> Result<()> {
 }
 
 #[cfg(target_os = "windows")]
-fn cmd_type(text: &str, _selector: Option<&str>, _app: Option<&str>) -> Result<()> {
-    type_text(text)?;
-    print_json(&Output::ok(serde_json::json!({"typed": text})));
+fn cmd_type(text: &str, _selector: Option<&str>, _app: Option<&str>, verify: Option<VerifyOptions>) -> Result<()> {
+    if verify.is_some() {
+        return Err(Error::new(ErrorCode::NotImplemented, "Verified typing is macOS-only for now".to_string()).into());
+    }
+    let report = enter_text(text, &TextEntryOptions::default()).into_result()?;
+    print_json(&Output::ok(report));
     Ok(())
 }
 
@@ -768,11 +1433,34 @@ fn cmd_scroll(direction: &str, pages: u32, app: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn cmd_scroll_precise(scroll: &Scroll, at: Option<&str>, selector: Option<&str>, app: Option<&str>) -> Result<()> {
+    if selector.is_some() {
+        return Err(Error::new(ErrorCode::NotImplemented, "Scrolling over elements is macOS-only for now; pass --at x,y".to_string()).into());
+    }
+    if let Some(a) = app {
+        cmd_activate(a)?;
+        std::thread::sleep(std::time::Duration::from_millis(300));
+    }
+    let (x, y) = match at {
+        Some(point) => parse_point(point).ok_or_else(|| Error::selector_invalid(point, "expected x,y"))?,
+        None => mouse_position()?,
+    };
+    scroll_at(x, y, scroll)?;
+    print_json(&Output::ok(serde_json::json!({"scrolled": scroll, "at": [x, y]})));
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn cmd_scroll_until(_target: &str, _container: &str, _app: Option<&str>, _direction: &str, _lines: i32, _max_steps: usize) -> Result<()> {
+    Err(Error::new(ErrorCode::NotImplemented, "scroll-until is macOS-only for now".to_string()).into())
+}
+
 #[cfg(target_os = "windows")]
 fn cmd_press(key: &str, repeat: u32, delay: u64) -> Result<()> {
-    let vk_code = key_name_to_vk(key).ok_or_else(|| Error::new(ErrorCode::Unknown, format!("Unknown key: {}", key)))?;
+    let chord = Chord::parse(key)?;
     for i in 0..repeat {
-        press_key(vk_code)?;
+        press_chord(&chord)?;
         if i < repeat - 1 {
             std::thread::sleep(std::time::Duration::from_millis(delay));
         }
@@ -860,10 +1548,10 @@ fn cmd_scrape(app: &str, depth: usize) -> Result<()> {
 
 #[cfg(target_os = "windows")]
 fn cmd_shortcut(key: &str, modifiers: &str) -> Result<()> {
-    let key_vk = key_name_to_vk(key).ok_or_else(|| Error::new(ErrorCode::Unknown, format!("Unknown key: {}", key)))?;
-    let mod_vks: Vec<u16> = modifiers.split(',').map(|m| modifier_name_to_vk(m)).collect();
-    shortcut(key_vk, &mod_vks)?;
-    print_json(&Output::ok(serde_json::json!({"key": key, "modifiers": modifiers})));
+    let mods: Vec<&str> = modifiers.split(',').collect();
+    let chord = Chord::from_parts(key, &mods)?;
+    press_chord(&chord)?;
+    print_json(&Output::ok(serde_json::json!({"key": key, "modifiers": modifiers, "chord": chord})));
     Ok(())
 }
 
@@ -917,8 +1605,8 @@ fn cmd_activate(app: &str) -> Result<()> {
 }
 
 #[cfg(target_os = "windows")]
-fn cmd_click_at(x: i32, y: i32, button: &str) -> Result<()> {
-    move_mouse(x, y)?;
+fn cmd_click_at(x: i32, y: i32, button: &str, motion: &str) -> Result<()> {
+    move_mouse_with(x, y, &MotionProfile::parse(motion)?)?;
     std::thread::sleep(std::time::Duration::from_millis(10));
     match button {
         "right" => right_click()?,
@@ -930,6 +1618,17 @@ fn cmd_click_at(x: i32, y: i32, button: &str) -> Result<()> {
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn cmd_drag(from: &str, to: &str, _app: Option<&str>, duration: u64, button: &str) -> Result<()> {
+    let button = MouseButton::parse(button)?;
+    let (Some(a), Some(b)) = (parse_point(from), parse_point(to)) else {
+        return Err(Error::new(ErrorCode::NotImplemented, "Dragging between elements is macOS-only for now; pass x,y points".to_string()).into());
+    };
+    drag(a, b, std::time::Duration::from_millis(duration), button)?;
+    print_json(&Output::ok(serde_json::json!({"dragged": {"from": a, "to": b, "button": button}})));
+    Ok(())
+}
+
 #[cfg(target_os = "windows")]
 fn cmd_send(text: &str, app: &str, no_enter: bool) -> Result<()> {
     cmd_activate(app)?;
@@ -1039,15 +1738,22 @@ fn record(name: &str, capture_context: bool, threshold: f64) -> Result<()> {
     Ok(())
 }
 
-fn replay(file: &str, speed: f64) -> Result<()> {
+fn replay(file: &str, speed: f64, motion: Option<&str>, pacing: PacingPolicy) -> Result<()> {
+    let motion = motion.map(MotionProfile::parse).transpose()?.unwrap_or_default();
     let storage = WorkflowStorage::new()?;
     let workflow = storage.load(file)?;
     println!("Replaying {} ({} events) at {}x speed...", workflow.name, workflow.events.len(), speed);
     println!("Starting in 2 seconds...");
     std::thread::sleep(std::time::Duration::from_secs(2));
-    let replayer = Replayer::new().speed(speed);
+    let replayer = Replayer::new().speed(speed).motion(motion).pacing(pacing);
     let stats = replayer.play(&workflow)?;
-    println!("Done! {} clicks, {} keys, {} chars typed", stats.clicks, stats.keys, stats.text_chars);
+    println!("Done! {} clicks, {} drags, {} keys, {} chars typed", stats.clicks, stats.drags, stats.keys, stats.text_chars);
+    if stats.skipped_keys > 0 {
+        println!("Skipped {} keys recorded on another platform with no logical name", stats.skipped_keys);
+    }
+    if stats.untyped_chars > 0 {
+        println!("Could not type {} characters on this platform", stats.untyped_chars);
+    }
     Ok(())
 }
 
@@ -1063,11 +1769,12 @@ fn show(file: &str, all: bool) -> Result<()> {
     let workflow = storage.load(file)?;
     println!("Name: {}", workflow.name);
     println!("Events: {}", workflow.events.len());
-    let (mut clicks, mut moves, mut scrolls, mut keys, mut text, mut apps, mut windows, mut pastes) = (0,0,0,0,0,0,0,0);
+    let (mut clicks, mut drags, mut moves, mut scrolls, mut keys, mut text, mut apps, mut windows, mut pastes) = (0,0,0,0,0,0,0,0,0);
     for e in &workflow.events {
         match &e.data {
             bigbrother::EventData::Click { .. } => clicks += 1,
             bigbrother::EventData::Move { .. } => moves += 1,
+            bigbrother::EventData::MouseDown { .. } => drags += 1,
             bigbrother::EventData::Scroll { .. } => scrolls += 1,
             bigbrother::EventData::Key { .. } => keys += 1,
             bigbrother::EventData::Text { .. } => text += 1,
@@ -1077,7 +1784,7 @@ fn show(file: &str, all: bool) -> Result<()> {
             _ => {}
         }
     }
-    println!("\nSummary: {} clicks, {} moves, {} scrolls, {} keys, {} text, {} apps, {} windows, {} clipboard", clicks, moves, scrolls, keys, text, apps, windows, pastes);
+    println!("\nSummary: {} clicks, {} drags, {} moves, {} scrolls, {} keys, {} text, {} apps, {} windows, {} clipboard", clicks, drags, moves, scrolls, keys, text, apps, windows, pastes);
     if all { for (i, e) in workflow.events.iter().enumerate() { println!("{}: {:?}", i, e); } }
     Ok(())
 }
@@ -1,7 +1,10 @@
 //! Accessibility API helpers for working with UI elements
 
+use crate::tree::Bounds;
 use cidre::ax;
 use cidre::arc::R;
+use cidre::cf;
+use serde_json::{json, Value};
 
 /// Get a string attribute from a UI element
 pub fn get_string_attr(element: &ax::UiElement, attr: &ax::Attr) -> Option<String> {
@@ -18,6 +21,32 @@ pub fn get_string_attr(element: &ax::UiElement, attr: &ax::Attr) -> Option<Strin
         })
 }
 
+/// Get the screen rectangle of a UI element from its position and size
+pub fn get_bounds(element: &ax::UiElement) -> Option<Bounds> {
+    let pos = get_ax_value(element, ax::attr::pos())?.cg_point()?;
+    let size = get_ax_value(element, ax::attr::size())?.cg_size()?;
+    Some(Bounds {
+        x: pos.x,
+        y: pos.y,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+fn get_ax_value(element: &ax::UiElement, attr: &ax::Attr) -> Option<R<ax::Value>> {
+    element
+        .attr_value(attr)
+        .ok()
+        .and_then(|v| {
+            if v.get_type_id() == ax::Value::type_id() {
+                let value: &ax::Value = unsafe { std::mem::transmute(&*v) };
+                Some(value.retained())
+            } else {
+                None
+            }
+        })
+}
+
 /// Extract a clean role name from an AX role
 pub fn extract_role_name(role: &R<ax::Role>) -> String {
     let debug = format!("{:?}", role);
@@ -44,6 +73,11 @@ pub fn get_description(element: &ax::UiElement) -> Option<String> {
     get_string_attr(element, ax::attr::desc())
 }
 
+/// Get the developer-assigned identifier (AXIdentifier) of an element
+pub fn get_identifier(element: &ax::UiElement) -> Option<String> {
+    get_string_attr(element, ax::attr::id())
+}
+
 /// Get the role of an element as a string
 pub fn get_role(element: &ax::UiElement) -> Option<String> {
     element.role().ok().map(|r| extract_role_name(&r))
@@ -54,6 +88,151 @@ pub fn get_role_desc(element: &ax::UiElement) -> Option<String> {
     element.role_desc().ok().map(|s| s.to_string())
 }
 
+/// Get the placeholder text of an element
+pub fn get_placeholder(element: &ax::UiElement) -> Option<String> {
+    get_string_attr(element, ax::attr::placeholder_value())
+}
+
+/// Get the elements that label an element (AXTitleUIElement, AXLabelUIElements)
+pub fn get_labelled_by(element: &ax::UiElement) -> Vec<R<ax::UiElement>> {
+    let mut labels: Vec<_> = get_element_attr(element, ax::attr::title_ui_element())
+        .into_iter()
+        .collect();
+    labels.extend(get_elements_attr(element, ax::attr::label_ui_elements()));
+    labels
+}
+
+/// Get an attribute that refers to one other element
+pub fn get_element_attr(element: &ax::UiElement, attr: &ax::Attr) -> Option<R<ax::UiElement>> {
+    let v = element.attr_value(attr).ok()?;
+    if v.get_type_id() == ax::UiElement::type_id() {
+        let other: &ax::UiElement = unsafe { std::mem::transmute(&*v) };
+        Some(other.retained())
+    } else {
+        None
+    }
+}
+
+/// Get an attribute that holds a list of elements (AXRows, AXColumns, ...)
+pub fn get_elements_attr(element: &ax::UiElement, attr: &ax::Attr) -> Vec<R<ax::UiElement>> {
+    match element.attr_value(attr) {
+        Ok(v) if v.get_type_id() == cf::Array::type_id() => {
+            let array: &cf::ArrayOf<ax::UiElement> = unsafe { std::mem::transmute(&*v) };
+            array.iter().map(|e| e.retained()).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Get a numeric attribute (AXIndex, scroll bar AXValue, ...)
+pub fn get_number_attr(element: &ax::UiElement, attr: &ax::Attr) -> Option<f64> {
+    let v = element.attr_value(attr).ok()?;
+    if v.get_type_id() == cf::Number::type_id() {
+        let n: &cf::Number = unsafe { std::mem::transmute(&*v) };
+        n.to_f64()
+    } else {
+        None
+    }
+}
+
+/// Set a point, size or range attribute (AXPosition, AXSize, ...)
+pub fn set_ax_value_attr(element: &ax::UiElement, attr: &ax::Attr, value: &ax::Value) -> bool {
+    let mut element = element.retained();
+    element.set_attr(attr, value).is_ok()
+}
+
+/// Get a boolean attribute (AXEnabled, AXSelected, ...)
+pub fn get_bool_attr(element: &ax::UiElement, attr: &ax::Attr) -> Option<bool> {
+    let v = element.attr_value(attr).ok()?;
+    if v.get_type_id() == cf::Boolean::type_id() {
+        let b: &cf::Boolean = unsafe { std::mem::transmute(&*v) };
+        Some(b.value())
+    } else {
+        None
+    }
+}
+
+/// Set a numeric attribute; false when the element refuses it
+pub fn set_number_attr(element: &ax::UiElement, attr: &ax::Attr, value: f64) -> bool {
+    let mut element = element.retained();
+    element.set_attr(attr, &cf::Number::from_f64(value)).is_ok()
+}
+
+/// Set a string attribute; false when the element refuses it
+pub fn set_string_attr(element: &ax::UiElement, attr: &ax::Attr, value: &str) -> bool {
+    let mut element = element.retained();
+    element.set_attr(attr, &cf::String::from_str(value)).is_ok()
+}
+
+/// Set a boolean attribute; false when the element refuses it
+pub fn set_bool_attr(element: &ax::UiElement, attr: &ax::Attr, value: bool) -> bool {
+    let mut element = element.retained();
+    let value = if value { cf::Boolean::value_true() } else { cf::Boolean::value_false() };
+    element.set_attr(attr, value).is_ok()
+}
+
+/// Names of every attribute an element reports
+pub fn get_attr_names(element: &ax::UiElement) -> Vec<String> {
+    element
+        .attrs()
+        .ok()
+        .map(|names| names.iter().map(|n| n.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Read an attribute by name as JSON
+///
+/// Element references (children, parent, label elements) are not data and
+/// come back as `None`, as does anything that cannot be converted.
+pub fn get_attr_json(element: &ax::UiElement, name: &str) -> Option<Value> {
+    let name = cf::String::from_str(name);
+    let v = element.attr_value(ax::Attr::with_string(&name)).ok()?;
+    cf_to_json(&v)
+}
+
+fn cf_to_json(v: &cf::Type) -> Option<Value> {
+    let type_id = v.get_type_id();
+    if type_id == cf::String::type_id() {
+        let s: &cf::String = unsafe { std::mem::transmute(v) };
+        Some(Value::String(s.to_string()))
+    } else if type_id == cf::Boolean::type_id() {
+        let b: &cf::Boolean = unsafe { std::mem::transmute(v) };
+        Some(Value::Bool(b.value()))
+    } else if type_id == cf::Number::type_id() {
+        let n: &cf::Number = unsafe { std::mem::transmute(v) };
+        if n.is_float_type() {
+            n.to_f64().map(|f| json!(f))
+        } else {
+            n.to_i64().map(|i| json!(i))
+        }
+    } else if type_id == cf::Url::type_id() {
+        let url: &cf::Url = unsafe { std::mem::transmute(v) };
+        Some(Value::String(url.cf_string().to_string()))
+    } else if type_id == ax::Value::type_id() {
+        let value: &ax::Value = unsafe { std::mem::transmute(v) };
+        if let Some(r) = value.cg_rect() {
+            Some(json!({"x": r.origin.x, "y": r.origin.y, "width": r.size.width, "height": r.size.height}))
+        } else if let Some(p) = value.cg_point() {
+            Some(json!({"x": p.x, "y": p.y}))
+        } else if let Some(s) = value.cg_size() {
+            Some(json!({"width": s.width, "height": s.height}))
+        } else {
+            value.cf_range().map(|r| json!({"location": r.loc, "length": r.len}))
+        }
+    } else if type_id == cf::Array::type_id() {
+        let array: &cf::ArrayOf<cf::Type> = unsafe { std::mem::transmute(v) };
+        let items: Vec<Value> = array.iter().filter_map(cf_to_json).collect();
+        // An array of element references converts to nothing; leave it out
+        if items.is_empty() && !array.is_empty() {
+            None
+        } else {
+            Some(Value::Array(items))
+        }
+    } else {
+        None
+    }
+}
+
 /// Get all children of an element
 pub fn get_children(element: &ax::UiElement) -> Vec<R<ax::UiElement>> {
     element
@@ -1,8 +1,10 @@
 //! Application finding and management utilities
 
+use crate::launch::{pick_app, AppNames};
 use anyhow::{Context, Result};
 use cidre::arc::R;
-use cidre::ax;
+use cidre::{ax, cf, ns};
+use std::path::Path;
 use std::process::Command;
 
 /// Common browser application names
@@ -17,24 +19,44 @@ pub const BROWSERS: &[&str] = &[
     "Vivaldi",
 ];
 
-/// Find the PID of a running application by name
+/// Every running app with its PID, from the workspace's list of running apps
+///
+/// This is the same registry the Accessibility API answers for, so an app
+/// is found under its display name even when its process is called
+/// something else (`Code` runs as `Electron`).
+pub fn running_apps() -> Vec<(AppNames, i32)> {
+    // The list only refreshes while the run loop turns; give it one pass
+    cf::RunLoop::run_in_mode(cf::RunLoopMode::default(), 0.0, false);
+    ns::Workspace::shared()
+        .running_apps()
+        .iter()
+        .filter(|app| !app.is_terminated())
+        .map(|app| {
+            let names = AppNames {
+                name: app.localized_name().map(|n| n.to_string()),
+                bundle_id: app.bundle_id().map(|id| id.to_string()),
+                executable: app
+                    .executable_url()
+                    .and_then(|url| url.path())
+                    .and_then(|path| {
+                        Path::new(&path.to_string())
+                            .file_name()
+                            .map(|f| f.to_string_lossy().into_owned())
+                    }),
+            };
+            (names, app.pid())
+        })
+        .collect()
+}
+
+/// Find the PID of a running application by display name, bundle id or
+/// executable name, see `crate::launch::pick_app`
 pub fn find_app_pid(app_name: &str) -> Result<i32> {
-    let output = Command::new("pgrep")
-        .arg("-x")
-        .arg(app_name)
-        .output()
-        .context("Failed to run pgrep")?;
-
-    if output.status.success() {
-        let pid_str = String::from_utf8_lossy(&output.stdout);
-        if let Some(first_line) = pid_str.lines().next() {
-            if let Ok(pid) = first_line.trim().parse::<i32>() {
-                return Ok(pid);
-            }
-        }
+    let (names, pids): (Vec<AppNames>, Vec<i32>) = running_apps().into_iter().unzip();
+    match pick_app(&names, app_name) {
+        Some(i) => Ok(pids[i]),
+        None => anyhow::bail!("Application '{}' not found running", app_name),
     }
-
-    anyhow::bail!("Application '{}' not found running", app_name)
 }
 
 /// Find any running application from a list of names
@@ -101,6 +123,22 @@ pub fn activate_app(app_name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Running applications with their PIDs
+///
+/// Names are resolved against one snapshot of `running_apps`. Apps whose PID
+/// cannot be resolved are left out; order follows `list_running_apps`.
+pub fn running_app_pids() -> Result<Vec<(String, i32)>> {
+    let names = list_running_apps()?;
+    let (running, pids): (Vec<AppNames>, Vec<i32>) = running_apps().into_iter().unzip();
+    Ok(names
+        .into_iter()
+        .filter_map(|name| {
+            let pid = pids[pick_app(&running, &name)?];
+            Some((name, pid))
+        })
+        .collect())
+}
+
 /// List all running applications
 pub fn list_running_apps() -> Result<Vec<String>> {
     let output = Command::new("osascript")
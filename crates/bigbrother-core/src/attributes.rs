@@ -0,0 +1,67 @@
+//! Native attributes beyond the fixed `ElementInfo` fields
+//!
+//! Every platform exposes more than role/name/value: URLs on links, row and
+//! column counts on tables, selection state, help text. Reading all of them
+//! is slow, so they are only collected when asked for.
+
+use serde_json::Value;
+
+/// Which native attributes to collect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeRequest {
+    /// Everything the element reports
+    All,
+    /// Only these names; the result map is keyed by name, in sorted order
+    Only(Vec<String>),
+}
+
+impl AttributeRequest {
+    /// `all`, or a comma-separated list of attribute names
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("all") {
+            return Self::All;
+        }
+        Self::Only(
+            s.split(',')
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+}
+
+/// Text form of an attribute value, used by `attr[...]` selectors
+///
+/// Strings compare as-is; anything else compares as its JSON text.
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_all_or_names() {
+        assert_eq!(AttributeRequest::parse("all"), AttributeRequest::All);
+        assert_eq!(AttributeRequest::parse(" ALL "), AttributeRequest::All);
+        assert_eq!(
+            AttributeRequest::parse("AXURL, AXHelp,,"),
+            AttributeRequest::Only(vec!["AXURL".to_string(), "AXHelp".to_string()])
+        );
+    }
+
+    #[test]
+    fn value_text_keeps_strings_bare() {
+        assert_eq!(value_text(&json!("https://github.com")), "https://github.com");
+        assert_eq!(value_text(&json!(true)), "true");
+        assert_eq!(value_text(&json!(3)), "3");
+        assert_eq!(value_text(&json!({"x": 1.0})), "{\"x\":1.0}");
+    }
+}
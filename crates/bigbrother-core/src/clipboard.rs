@@ -0,0 +1,355 @@
+//! Clipboard read/write and change notifications
+//!
+//! Contents come in four formats: plain text, HTML, URI lists and PNG
+//! images. One write can offer several at once, e.g. HTML with a plain text
+//! fallback, and readers take whichever they understand. Windows only does
+//! plain text so far.
+//!
+//! X11 has two selections an agent cares about: CLIPBOARD, written by
+//! copy/cut, and PRIMARY, which holds whatever was last highlighted. Other
+//! platforms only have the clipboard. On X11 the content a client writes is
+//! served by that client on request, so `set` leaves a small detached process
+//! behind to serve it until something else takes the selection over.
+//!
+//! Changes are noticed by polling the platform's change marker, see
+//! `ClipboardWatcher`.
+
+use crate::error::{Error, ErrorCode, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_os = "linux")]
+use crate::platform::linux::clipboard as backend;
+#[cfg(target_os = "macos")]
+use crate::platform::macos::clipboard as backend;
+#[cfg(target_os = "windows")]
+use crate::platform::windows::clipboard as backend;
+
+/// How often a watcher looks for changes by default
+pub const WATCH_INTERVAL_MS: u64 = 250;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    /// Written by copy and cut
+    #[default]
+    Clipboard,
+    /// The last highlighted text (X11 only)
+    Primary,
+}
+
+impl Selection {
+    /// `clipboard` or `primary`
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "clipboard" => Ok(Selection::Clipboard),
+            "primary" => Ok(Selection::Primary),
+            _ => Err(Error::invalid_argument("selection", s, "expected clipboard or primary")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardFormat {
+    Text,
+    Html,
+    UriList,
+    Png,
+}
+
+impl ClipboardFormat {
+    pub const ALL: [ClipboardFormat; 4] = [
+        ClipboardFormat::Text,
+        ClipboardFormat::Html,
+        ClipboardFormat::UriList,
+        ClipboardFormat::Png,
+    ];
+
+    /// `text`, `html`, `uri-list` or `png`
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "text" | "txt" => Ok(ClipboardFormat::Text),
+            "html" => Ok(ClipboardFormat::Html),
+            "uri-list" | "uri_list" | "uris" | "urls" => Ok(ClipboardFormat::UriList),
+            "png" | "image" => Ok(ClipboardFormat::Png),
+            _ => Err(Error::invalid_argument("clipboard format", s, "expected text, html, uri-list or png")),
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            ClipboardFormat::Text => "text/plain;charset=utf-8",
+            ClipboardFormat::Html => "text/html",
+            ClipboardFormat::UriList => "text/uri-list",
+            ClipboardFormat::Png => "image/png",
+        }
+    }
+
+    /// Format of a MIME type or X11 target name
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime.to_lowercase().as_str() {
+            "utf8_string" | "string" | "text" | "text/plain" | "text/plain;charset=utf-8" => Some(ClipboardFormat::Text),
+            "text/html" => Some(ClipboardFormat::Html),
+            "text/uri-list" => Some(ClipboardFormat::UriList),
+            "image/png" => Some(ClipboardFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ClipboardFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardFormat::Text => write!(f, "text"),
+            ClipboardFormat::Html => write!(f, "html"),
+            ClipboardFormat::UriList => write!(f, "uri-list"),
+            ClipboardFormat::Png => write!(f, "png"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "format", content = "data", rename_all = "snake_case")]
+pub enum ClipboardContent {
+    Text(String),
+    Html(String),
+    UriList(Vec<String>),
+    /// Encoded PNG file
+    Png(Vec<u8>),
+}
+
+impl ClipboardContent {
+    pub fn format(&self) -> ClipboardFormat {
+        match self {
+            ClipboardContent::Text(_) => ClipboardFormat::Text,
+            ClipboardContent::Html(_) => ClipboardFormat::Html,
+            ClipboardContent::UriList(_) => ClipboardFormat::UriList,
+            ClipboardContent::Png(_) => ClipboardFormat::Png,
+        }
+    }
+
+    /// The content as it travels under its MIME type
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            ClipboardContent::Text(s) | ClipboardContent::Html(s) => s.as_bytes().to_vec(),
+            ClipboardContent::UriList(uris) => format_uri_list(uris).into_bytes(),
+            ClipboardContent::Png(png) => png.clone(),
+        }
+    }
+
+    pub fn from_bytes(format: ClipboardFormat, bytes: Vec<u8>) -> Result<Self> {
+        let text = |bytes: Vec<u8>| String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+        match format {
+            ClipboardFormat::Text => Ok(ClipboardContent::Text(text(bytes))),
+            ClipboardFormat::Html => Ok(ClipboardContent::Html(text(bytes))),
+            ClipboardFormat::UriList => Ok(ClipboardContent::UriList(parse_uri_list(&text(bytes)))),
+            ClipboardFormat::Png if bytes.starts_with(PNG_SIGNATURE) => Ok(ClipboardContent::Png(bytes)),
+            ClipboardFormat::Png => Err(Error::new(
+                ErrorCode::ActionFailed,
+                "Clipboard image is not a PNG file".to_string(),
+            )),
+        }
+    }
+}
+
+/// URIs in a `text/uri-list`, skipping comments and blank lines
+pub fn parse_uri_list(s: &str) -> Vec<String> {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// A `text/uri-list`, one URI per CRLF-terminated line
+pub fn format_uri_list(uris: &[String]) -> String {
+    uris.iter().map(|uri| format!("{}\r\n", uri)).collect()
+}
+
+/// Formats the selection currently offers
+pub fn formats(selection: Selection) -> Result<Vec<ClipboardFormat>> {
+    backend::formats(selection)
+}
+
+/// The selection's content in `format`, `None` when it isn't offered
+pub fn get(selection: Selection, format: ClipboardFormat) -> Result<Option<ClipboardContent>> {
+    backend::get(selection, format)
+}
+
+/// Everything the selection offers, one entry per format
+pub fn get_all(selection: Selection) -> Result<Vec<ClipboardContent>> {
+    let mut contents = Vec::new();
+    for format in formats(selection)? {
+        contents.extend(get(selection, format)?);
+    }
+    Ok(contents)
+}
+
+/// Replace the selection's content, offering each entry as its format
+///
+/// The content outlives the calling process; on X11 a forked server keeps
+/// answering for it until another client takes the selection.
+pub fn set(selection: Selection, contents: &[ClipboardContent]) -> Result<()> {
+    if contents.is_empty() {
+        return Err(Error::new(
+            ErrorCode::ActionFailed,
+            "Nothing to put on the clipboard".to_string(),
+        ));
+    }
+    backend::set(selection, contents)
+}
+
+/// Plain text on the clipboard
+pub fn get_text() -> Result<Option<String>> {
+    Ok(match get(Selection::Clipboard, ClipboardFormat::Text)? {
+        Some(ClipboardContent::Text(text)) => Some(text),
+        _ => None,
+    })
+}
+
+pub fn set_text(text: &str) -> Result<()> {
+    set(Selection::Clipboard, &[ClipboardContent::Text(text.to_string())])
+}
+
+/// Marker that changes whenever the selection's owner writes new content
+pub(crate) fn change_marker(selection: Selection) -> Result<u64> {
+    backend::change_marker(selection)
+}
+
+/// A write to a watched selection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClipboardChange {
+    pub timestamp_ms: u64,
+    pub selection: Selection,
+    /// Formats offered right after the change
+    pub formats: Vec<ClipboardFormat>,
+}
+
+/// Handle to a running clipboard watcher; dropping it stops the watcher
+pub struct ClipboardWatcher {
+    changes: Arc<Mutex<Vec<ClipboardChange>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ClipboardWatcher {
+    /// Watch `selections`, checking every `interval`
+    pub fn start(selections: Vec<Selection>, interval: Duration) -> Self {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (changes, stop) = (changes.clone(), stop.clone());
+            thread::spawn(move || {
+                let mut markers: Vec<Option<u64>> = selections.iter().map(|&s| change_marker(s).ok()).collect();
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(interval);
+                    for (selection, marker) in selections.iter().zip(markers.iter_mut()) {
+                        let current = change_marker(*selection).ok();
+                        if current.is_none() || current == *marker {
+                            continue;
+                        }
+                        *marker = current;
+                        changes.lock().unwrap().push(ClipboardChange {
+                            timestamp_ms: SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map(|d| d.as_millis() as u64)
+                                .unwrap_or_default(),
+                            selection: *selection,
+                            formats: formats(*selection).unwrap_or_default(),
+                        });
+                    }
+                }
+            })
+        };
+        Self {
+            changes,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Every change seen so far
+    pub fn changes(&self) -> Vec<ClipboardChange> {
+        self.changes.lock().unwrap().clone()
+    }
+
+    /// Changes seen since the last drain
+    pub fn drain(&self) -> Vec<ClipboardChange> {
+        std::mem::take(&mut *self.changes.lock().unwrap())
+    }
+
+    /// Stop watching and return the changes not yet drained
+    pub fn stop(mut self) -> Vec<ClipboardChange> {
+        self.halt();
+        self.drain()
+    }
+
+    fn halt(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ClipboardWatcher {
+    fn drop(&mut self) {
+        self.halt();
+    }
+}
+
+/// Watch the clipboard with the default interval
+pub fn watch() -> ClipboardWatcher {
+    ClipboardWatcher::start(vec![Selection::Clipboard], Duration::from_millis(WATCH_INTERVAL_MS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_lists_round_trip() {
+        let list = "# copied from Files\r\nfile:///home/neo/a.txt\r\n\r\nhttps://example.com/b\r\n";
+        let uris = parse_uri_list(list);
+        assert_eq!(uris, vec!["file:///home/neo/a.txt", "https://example.com/b"]);
+        assert_eq!(format_uri_list(&uris), "file:///home/neo/a.txt\r\nhttps://example.com/b\r\n");
+    }
+
+    #[test]
+    fn contents_from_bytes() {
+        let html = ClipboardContent::from_bytes(ClipboardFormat::Html, b"<b>hi</b>".to_vec()).unwrap();
+        assert_eq!(html, ClipboardContent::Html("<b>hi</b>".into()));
+        assert_eq!(html.to_bytes(), b"<b>hi</b>");
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(b"IHDR");
+        assert_eq!(ClipboardContent::from_bytes(ClipboardFormat::Png, png.clone()).unwrap().format(), ClipboardFormat::Png);
+        let err = ClipboardContent::from_bytes(ClipboardFormat::Png, b"GIF89a".to_vec()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ActionFailed);
+    }
+
+    #[test]
+    fn formats_by_name_and_mime() {
+        assert_eq!(ClipboardFormat::parse("URI-List").unwrap(), ClipboardFormat::UriList);
+        assert!(ClipboardFormat::parse("rtf").is_err());
+        assert_eq!(ClipboardFormat::from_mime("UTF8_STRING"), Some(ClipboardFormat::Text));
+        assert_eq!(ClipboardFormat::from_mime("image/png"), Some(ClipboardFormat::Png));
+        assert_eq!(ClipboardFormat::from_mime("TARGETS"), None);
+        for format in ClipboardFormat::ALL {
+            assert_eq!(ClipboardFormat::parse(&format.to_string()).unwrap(), format);
+        }
+        assert_eq!(Selection::parse("PRIMARY").unwrap(), Selection::Primary);
+    }
+
+    #[test]
+    fn contents_serialize_tagged() {
+        let json = serde_json::to_value(ClipboardContent::UriList(vec!["file:///tmp/a".into()])).unwrap();
+        assert_eq!(json, serde_json::json!({"format": "uri_list", "data": ["file:///tmp/a"]}));
+    }
+}
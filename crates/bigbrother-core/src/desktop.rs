@@ -1,17 +1,40 @@
 //! Desktop - main entry point for automation
 
 use crate::apps;
-use crate::element::UIElement;
-use crate::error::{Error, Result};
+use crate::dialog::DialogRule;
+use crate::accessibility::get_element_attr;
+use crate::element::{choose_menu_path, ActionResult, UIElement};
+use crate::error::{Error, ErrorCode, Result};
+use crate::form::{parse_checked, FieldKind, FieldReport, FieldStatus, FormFields, FormReport};
 use crate::input;
+use crate::keys::Chord;
+use crate::launch::LaunchOptions;
+use crate::lease::{InputLease, LeaseOptions};
 use crate::locator::Locator;
+use crate::marks::SetOfMarks;
+use crate::menu::parse_menu_path;
+use crate::observe::{Observation, ObserveOptions};
+use crate::pacing::{Pacer, PacingPolicy};
+use crate::platform::macos::{App, DialogWatcher, Window};
+use crate::scroll::{Scroll, ScrollUntilOptions};
 use crate::selector::Selector;
+use crate::text_entry::{TextEntryOptions, TextEntryReport};
+use crate::traversal::{Traversal, TraversalPolicy, Truncation};
+use crate::tree::{element_id, identity_label, Bounds, SiblingPaths};
+use crate::verify::{VerifiedTyping, VerifyOptions};
+pub use crate::tree::{TreeNode, TreeResult};
+use cidre::arc::R;
+use cidre::{ax, cg};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 pub struct Desktop {
     app_filter: Option<String>,
+    window_filter: Option<String>,
     tree_cache: Vec<UIElement>,
+    traversal: TraversalPolicy,
+    lease: LeaseOptions,
+    pacer: Pacer,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,31 +43,19 @@ pub struct AppInfo {
     pub pid: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TreeNode {
-    pub index: usize,
-    pub role: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub title: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub value: Option<String>,
-    pub depth: usize,
-    pub children_count: usize,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TreeResult {
-    pub app: String,
-    pub element_count: usize,
-    pub nodes: Vec<TreeNode>,
+/// State threaded through one tree walk
+struct TreeWalk<'a> {
+    traversal: Traversal<'a>,
+    focused: Option<R<ax::UiElement>>,
+    nodes: Vec<TreeNode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrapeResult {
     pub app: String,
     pub items: Vec<ScrapeItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated: Vec<Truncation>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,7 +72,11 @@ impl Desktop {
         crate::ensure_accessibility()?;
         Ok(Self {
             app_filter: None,
+            window_filter: None,
             tree_cache: Vec::new(),
+            traversal: TraversalPolicy::default(),
+            lease: LeaseOptions::default(),
+            pacer: Pacer::default(),
         })
     }
 
@@ -70,19 +85,60 @@ impl Desktop {
         self
     }
 
-    // Discovery
+    /// Scope locators to the app window with this title (needs `in_app`)
+    ///
+    /// An exact title wins, then a case-insensitive substring.
+    pub fn in_window(mut self, title: &str) -> Self {
+        self.window_filter = Some(title.to_string());
+        self
+    }
 
-    pub fn apps(&self) -> Result<Vec<AppInfo>> {
-        let names = apps::list_running_apps().map_err(|e| Error::from(e))?;
-        let mut result = Vec::new();
+    /// Budget applied to tree, scrape and locator walks
+    pub fn traversal(mut self, policy: TraversalPolicy) -> Self {
+        self.traversal = policy;
+        self
+    }
 
-        for name in names {
-            if let Ok(pid) = apps::find_app_pid(&name) {
-                result.push(AppInfo { name, pid });
-            }
-        }
+    /// How input actions wait for the input lease, see `crate::lease`
+    pub fn input_lease(mut self, options: LeaseOptions) -> Self {
+        self.lease = options;
+        self
+    }
+
+    /// How fast input actions go out, see `crate::pacing`
+    pub fn pacing(mut self, policy: PacingPolicy) -> Self {
+        self.pacer = Pacer::new(policy);
+        self
+    }
+
+    /// The pacer input actions go through; report verifications to it so
+    /// adaptive pacing can slow down
+    pub fn pacer(&self) -> &Pacer {
+        &self.pacer
+    }
 
-        Ok(result)
+    /// Hold the input lease across several actions, e.g. find, click and type
+    ///
+    /// Input actions take the lease on their own; holding it here keeps
+    /// other agents out between them too.
+    pub fn hold_input(&self) -> Result<InputLease> {
+        InputLease::acquire(&self.lease)
+    }
+
+    /// Run one input action under the lease and the pacing policy
+    fn input<T>(&self, action: impl FnOnce() -> Result<T>) -> Result<T> {
+        let _lease = self.hold_input()?;
+        self.pacer.action(action)
+    }
+
+    // Discovery
+
+    pub fn apps(&self) -> Result<Vec<AppInfo>> {
+        let apps = apps::running_app_pids().map_err(|e| Error::from(e))?;
+        Ok(apps
+            .into_iter()
+            .map(|(name, pid)| AppInfo { name, pid })
+            .collect())
     }
 
     pub fn find_app(&self, name: &str) -> Result<AppInfo> {
@@ -93,6 +149,13 @@ impl Desktop {
         })
     }
 
+    /// Start an app and, unless told otherwise, wait for its first window
+    ///
+    /// `target` is an app name, bundle id, `.app` path or executable path.
+    pub fn launch(&self, target: &str, options: LaunchOptions) -> Result<App> {
+        App::launch(target, &options)
+    }
+
     pub fn browser(&self) -> Result<AppInfo> {
         let (name, pid) = apps::find_browser().map_err(|e| Error::from(e))?;
         Ok(AppInfo { name, pid })
@@ -101,24 +164,66 @@ impl Desktop {
     // Element finding
 
     pub fn locator(&self, selector: &str) -> Result<Locator> {
-        let mut loc = Locator::parse(selector)?;
-        if let Some(ref app) = self.app_filter {
-            let root = self.app_root(app)?;
-            loc = loc.with_root(root);
+        let mut loc = Locator::parse(selector)?
+            .traversal(self.traversal.clone())
+            .input_lease(self.lease.clone());
+        match self.app_filter {
+            Some(ref app) => loc = loc.with_root(self.scope_root(app)?),
+            None => loc = loc.all_apps(),
         }
         Ok(loc)
     }
 
     pub fn locator_selector(&self, selector: Selector) -> Locator {
-        let mut loc = Locator::new(selector);
-        if let Some(ref app) = self.app_filter {
-            if let Ok(root) = self.app_root(app) {
-                loc = loc.with_root(root);
+        let mut loc = Locator::new(selector)
+            .traversal(self.traversal.clone())
+            .input_lease(self.lease.clone());
+        match self.app_filter {
+            Some(ref app) => {
+                if let Ok(root) = self.scope_root(app) {
+                    loc = loc.with_root(root);
+                }
             }
+            None => loc = loc.all_apps(),
         }
         loc
     }
 
+    /// The app's windows
+    pub fn windows(&self, app: &str) -> Result<Vec<Window>> {
+        Window::for_app(app)
+    }
+
+    /// The app's window with this title
+    pub fn window(&self, app: &str, title: &str) -> Result<Window> {
+        Window::find(app, title)
+    }
+
+    /// Watch for dialogs in the background and handle them by rule
+    ///
+    /// Scoped to the `in_app` app if set, else every app. Rules are tried in
+    /// order; the first match wins. Each dialog seen is logged on the
+    /// returned watcher, handled or not. Actions hold the input lease; a
+    /// dialog whose action failed is tried again at the next poll.
+    pub fn watch_dialogs(&self, rules: Vec<DialogRule>) -> DialogWatcher {
+        DialogWatcher::start(
+            self.app_filter.clone(),
+            rules,
+            Duration::from_millis(DIALOG_POLL_MS),
+            self.lease.clone(),
+        )
+    }
+
+    /// Locator root: the window from `in_window` if set, else the whole app
+    ///
+    /// Ids are the same either way; the locator places a window root in its app.
+    fn scope_root(&self, app: &str) -> Result<UIElement> {
+        match &self.window_filter {
+            Some(title) => Ok(Window::find(app, title)?.element().clone()),
+            None => self.app_root(app),
+        }
+    }
+
     fn app_root(&self, app: &str) -> Result<UIElement> {
         let element = apps::get_app_by_name(app).map_err(|_| Error::app_not_running(app))?;
         Ok(UIElement::new(element))
@@ -128,37 +233,62 @@ impl Desktop {
 
     pub fn tree(&mut self, app: &str, max_depth: usize) -> Result<TreeResult> {
         let root = self.app_root(app)?;
-        let mut nodes = Vec::new();
-        let mut index = 0;
+        let policy = self.traversal.clone();
+        let mut walk = TreeWalk {
+            traversal: self.start_traversal(&policy, max_depth),
+            focused: root.raw().focused_ui_element().ok(),
+            nodes: Vec::new(),
+        };
 
         self.tree_cache.clear();
-        self.build_tree(&root, 0, max_depth, &mut nodes, &mut index);
+        let mut siblings = SiblingPaths::new();
+        self.build_tree(&root, None, &mut siblings, 0, &mut walk);
 
         Ok(TreeResult {
             app: app.to_string(),
-            element_count: nodes.len(),
-            nodes,
+            element_count: walk.nodes.len(),
+            nodes: walk.nodes,
+            truncated: walk.traversal.finish(),
         })
     }
 
     fn build_tree(
         &mut self,
         element: &UIElement,
+        parent_path: Option<&str>,
+        siblings: &mut SiblingPaths,
         depth: usize,
-        max_depth: usize,
-        nodes: &mut Vec<TreeNode>,
-        index: &mut usize,
+        walk: &mut TreeWalk,
     ) {
-        if depth > max_depth {
+        if !walk.traversal.visit() {
+            return;
+        }
+
+        // Skipped siblings still count, so a path doesn't depend on what is on screen
+        let role = element.role().unwrap_or_else(|| "Unknown".to_string());
+        let title = element.title();
+        let identifier = element.identifier();
+        let description = element.description();
+        let path = siblings.child(
+            parent_path,
+            &role,
+            identity_label(title.as_deref(), identifier.as_deref(), description.as_deref()),
+        );
+        let bounds = element.bounds();
+        if walk.traversal.is_offscreen(bounds.as_ref()) {
             return;
         }
+        let id = element_id(&path);
+        let index = walk.nodes.len();
 
         let children = element.children();
+        let walk_children = walk.traversal.children_to_walk(depth, &role, children.len());
         let node = TreeNode {
-            index: *index,
-            role: element.role().unwrap_or_else(|| "Unknown".to_string()),
+            index,
+            id: id.clone(),
+            role,
             name: element.name(),
-            title: element.title(),
+            title,
             value: element.value().map(|v| {
                 if v.len() > 100 {
                     format!("{}...", &v[..100])
@@ -166,16 +296,60 @@ impl Desktop {
                     v
                 }
             }),
+            identifier,
+            description,
+            focused: walk
+                .focused
+                .as_ref()
+                .is_some_and(|f| f.equal(element.raw())),
+            bounds,
             depth,
             children_count: children.len(),
         };
 
-        nodes.push(node);
-        self.tree_cache.push(element.clone().with_index(*index));
-        *index += 1;
+        walk.nodes.push(node);
+        self.tree_cache.push(element.clone().with_index(index).with_id(id));
+
+        let mut child_siblings = SiblingPaths::new();
+        for child in children.into_iter().take(walk_children) {
+            self.build_tree(&child, Some(&path), &mut child_siblings, depth + 1, walk);
+        }
+    }
+
+    /// Compact text rendering of the app's tree, sized for an LLM context window
+    pub fn observe(&mut self, app: &str, options: &ObserveOptions) -> Result<Observation> {
+        let tree = self.tree(app, options.max_depth)?;
+        Ok(tree.observe(options))
+    }
 
-        for child in children {
-            self.build_tree(&child, depth + 1, max_depth, nodes, index);
+    /// Interactive elements of the app that are on the main display, numbered for a screenshot overlay
+    pub fn set_of_marks(&mut self, app: &str, max_depth: usize) -> Result<SetOfMarks> {
+        let tree = self.tree(app, max_depth)?;
+        Ok(tree.marks(&Self::screen_bounds()))
+    }
+
+    /// Main display bounds in points
+    pub fn screen_bounds() -> Bounds {
+        display_bounds(cg::DirectDisplayId::main())
+    }
+
+    /// Bounds of every active display in points
+    pub fn screens() -> Vec<Bounds> {
+        let mut ids = [cg::DirectDisplayId::NULL; 16];
+        let mut count = 0u32;
+        let err = unsafe { CGGetActiveDisplayList(ids.len() as u32, ids.as_mut_ptr(), &mut count) };
+        if err != 0 {
+            return vec![Self::screen_bounds()];
+        }
+        ids[..count as usize].iter().map(|id| display_bounds(*id)).collect()
+    }
+
+    fn start_traversal<'a>(&self, policy: &'a TraversalPolicy, max_depth: usize) -> Traversal<'a> {
+        let traversal = Traversal::new(policy, max_depth);
+        if policy.skip_offscreen {
+            traversal.with_screens(Self::screens())
+        } else {
+            traversal
         }
     }
 
@@ -186,30 +360,40 @@ impl Desktop {
             .ok_or_else(|| Error::element_not_found(&format!("index:{}", index)))
     }
 
+    /// Resolve a stable id against the live tree of the filtered app
+    pub fn element_by_id(&self, id: &str) -> Result<UIElement> {
+        self.locator_selector(Selector::id(id)).find()
+    }
+
     // Scraping
 
     pub fn scrape(&self, app: &str, max_depth: usize) -> Result<ScrapeResult> {
         let root = self.app_root(app)?;
         let mut items = Vec::new();
         let mut seen = std::collections::HashSet::new();
+        let mut traversal = self.start_traversal(&self.traversal, max_depth);
 
-        self.scrape_recursive(&root, max_depth, 0, &mut items, &mut seen);
+        self.scrape_recursive(&root, 0, &mut traversal, &mut items, &mut seen);
 
         Ok(ScrapeResult {
             app: app.to_string(),
             items,
+            truncated: traversal.finish(),
         })
     }
 
     fn scrape_recursive(
         &self,
         element: &UIElement,
-        max_depth: usize,
         depth: usize,
+        traversal: &mut Traversal,
         items: &mut Vec<ScrapeItem>,
         seen: &mut std::collections::HashSet<String>,
     ) {
-        if depth > max_depth {
+        if !traversal.visit() {
+            return;
+        }
+        if traversal.checks_offscreen() && traversal.is_offscreen(element.bounds().as_ref()) {
             return;
         }
 
@@ -220,18 +404,78 @@ impl Desktop {
                     index: items.len(),
                     role: element.role().unwrap_or_else(|| "Unknown".to_string()),
                     text,
-                    context: element.name(),
+                    context: element.role_description(),
                 });
             }
         }
 
-        for child in element.children() {
-            self.scrape_recursive(&child, max_depth, depth + 1, items, seen);
+        let children = element.children();
+        let role = element.role().unwrap_or_default();
+        let walk_children = traversal.children_to_walk(depth, &role, children.len());
+        for child in children.into_iter().take(walk_children) {
+            self.scrape_recursive(&child, depth + 1, traversal, items, seen);
         }
     }
 
     // Actions
 
+    /// Fill form fields by their labels, e.g. `[("Email", "me@example.com"), ("Remember me", "true")]`
+    ///
+    /// Fields are looked up in the app's focused window and filled in the
+    /// given order; see `crate::form` for how labels are paired with fields.
+    /// A missing or failing field does not stop the rest, the report says
+    /// what happened to each.
+    pub fn fill_form<I, K, V>(&self, app: &str, fields: I) -> Result<FormReport>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let _lease = self.hold_input()?;
+        let app_root = self.app_root(app)?;
+        let root = get_element_attr(app_root.raw(), ax::attr::focused_window())
+            .map(UIElement::new)
+            .unwrap_or(app_root);
+        let form = FormFields::collect(&root, FORM_DEPTH);
+        let reports = fields
+            .into_iter()
+            .map(|(label, value)| {
+                let report = self.pacer.action(|| fill_field(&form, label.into(), value.into()));
+                match report.status {
+                    FieldStatus::Filled | FieldStatus::Unchanged => self.pacer.verified(true),
+                    FieldStatus::Failed => self.pacer.verified(false),
+                    FieldStatus::NotFound => {}
+                }
+                report
+            })
+            .collect();
+        Ok(FormReport::new(reports))
+    }
+
+    /// Choose a menu bar item by path, e.g. `"File > Export > PDF…"`
+    ///
+    /// Uses the app from `in_app` (activating it first) or else the frontmost
+    /// app. When a level has no matching item the error lists what it offers.
+    pub fn menu(&self, path: &str) -> Result<ActionResult> {
+        let path = parse_menu_path(path)?;
+        let _lease = self.hold_input()?;
+        let app = match &self.app_filter {
+            Some(name) => {
+                let root = self.app_root(name)?;
+                self.activate(name)?;
+                root
+            }
+            None => ax::UiElement::sys_wide()
+                .focused_app()
+                .map(UIElement::new)
+                .map_err(|_| Error::app_not_running("frontmost app"))?,
+        };
+        let bar = get_element_attr(app.raw(), ax::attr::menu_bar())
+            .map(UIElement::new)
+            .ok_or_else(|| Error::element_not_found("AXMenuBar"))?;
+        choose_menu_path(&bar, "menu bar", &path)
+    }
+
     pub fn open_url(&self, url: &str) -> Result<()> {
         apps::open_url(url).map_err(|e| Error::from(e))
     }
@@ -246,23 +490,75 @@ impl Desktop {
     }
 
     pub fn scroll_up(&self, pages: u32) -> Result<()> {
-        input::scroll_up(pages).map_err(|e| Error::from(e))
+        self.input(|| input::scroll_up(pages).map_err(|e| Error::from(e)))
     }
 
     pub fn scroll_down(&self, pages: u32) -> Result<()> {
-        input::scroll_down(pages).map_err(|e| Error::from(e))
+        self.input(|| input::scroll_down(pages).map_err(|e| Error::from(e)))
+    }
+
+    /// Scroll by lines or pixels with the pointer at a screen point
+    pub fn scroll_at(&self, x: i32, y: i32, scroll: &Scroll) -> Result<()> {
+        self.input(|| input::scroll_at(x, y, scroll).map_err(Error::from))
+    }
+
+    /// Scroll the `container` match until a `target` inside it is visible,
+    /// see `UIElement::scroll_until`
+    pub fn scroll_until(&self, container: &str, target: &str, options: &ScrollUntilOptions) -> Result<UIElement> {
+        let _lease = self.hold_input()?;
+        let container = self.locator(container)?.find()?;
+        let target = Locator::parse(target)?
+            .traversal(self.traversal.clone())
+            .with_root(container.clone());
+        container.scroll_until(&target, options)
+    }
+
+    /// Click at screen coordinates with `left`, `right` or `middle`
+    pub fn click_at(&self, x: i32, y: i32, button: &str) -> Result<()> {
+        self.input(|| input::click_at(x, y, button).map_err(Error::from))
     }
 
     pub fn press_key(&self, key_code: u8) -> Result<()> {
-        input::press_key(key_code).map_err(|e| Error::from(e))
+        self.input(|| input::press_key(key_code).map_err(|e| Error::from(e)))
+    }
+
+    /// Press a key chord such as `cmd+shift+t` or `escape`
+    pub fn press(&self, chord: &str) -> Result<()> {
+        let chord = Chord::parse(chord)?;
+        self.input(|| input::press_chord(&chord).map_err(Error::from))
     }
 
     pub fn type_text(&self, text: &str) -> Result<()> {
-        input::type_text(text).map_err(|e| Error::from(e))
+        self.enter_text(text, &TextEntryOptions::default())?.into_result()?;
+        Ok(())
+    }
+
+    /// Type text and report which characters went which way, see
+    /// `crate::text_entry`; the key delay comes from the pacing policy
+    pub fn enter_text(&self, text: &str, options: &TextEntryOptions) -> Result<TextEntryReport> {
+        let options = self.pacer.text_entry(options);
+        self.input(|| Ok(input::enter_text(text, &options)))
+    }
+
+    /// Type into the field `selector` matches and read it back, see
+    /// `crate::verify`; outcomes feed adaptive pacing
+    pub fn type_verified(&self, selector: &str, text: &str, options: &VerifyOptions) -> Result<VerifiedTyping> {
+        let locator = self.locator(selector)?;
+        let options = VerifyOptions {
+            entry: self.pacer.text_entry(&options.entry),
+            ..options.clone()
+        };
+        let result = self.input(|| locator.type_text_verified(text, &options));
+        match &result {
+            Ok(report) => report.attempts.iter().for_each(|a| self.pacer.verified(a.matched)),
+            Err(e) if e.code == ErrorCode::ValueMismatch => self.pacer.verified(false),
+            Err(_) => {}
+        }
+        result
     }
 
     pub fn cmd(&self, key: &str) -> Result<()> {
-        input::cmd(key).map_err(|e| Error::from(e))
+        self.input(|| input::cmd(key).map_err(|e| Error::from(e)))
     }
 }
 
@@ -270,7 +566,70 @@ impl Default for Desktop {
     fn default() -> Self {
         Self {
             app_filter: None,
+            window_filter: None,
             tree_cache: Vec::new(),
+            traversal: TraversalPolicy::default(),
+            lease: LeaseOptions::default(),
+            pacer: Pacer::default(),
+        }
+    }
+}
+
+/// How deep `fill_form` looks for fields below the window
+const FORM_DEPTH: usize = 30;
+
+/// How often the dialog watcher looks for new dialogs
+const DIALOG_POLL_MS: u64 = 500;
+
+fn fill_field(form: &FormFields<UIElement>, label: String, value: String) -> FieldReport {
+    let mut report = FieldReport::not_found(label, value);
+    let Some(field) = form.resolve(&report.label) else {
+        return report;
+    };
+    report.kind = Some(field.kind);
+    report.matched_by = Some(field.matched_by);
+
+    // A label over a radio group takes the title of the option to choose
+    let (index, value) = if field.kind == FieldKind::RadioButton && parse_checked(&report.value).is_none() {
+        match form.radio_option(field.index, &report.value) {
+            Some(option) => (option, "true"),
+            None => {
+                report.status = FieldStatus::Failed;
+                report.error = Some(format!("No option '{}' in this radio group", report.value));
+                return report;
+            }
         }
+    } else {
+        (field.index, report.value.as_str())
+    };
+
+    match form.node(index).fill(field.kind, value) {
+        Ok(true) => report.status = FieldStatus::Filled,
+        Ok(false) => report.status = FieldStatus::Unchanged,
+        Err(e) => {
+            report.status = FieldStatus::Failed;
+            report.error = Some(e.message);
+        }
+    }
+    report
+}
+
+fn display_bounds(id: cg::DirectDisplayId) -> Bounds {
+    let rect = id.bounds();
+    Bounds {
+        x: rect.origin.x,
+        y: rect.origin.y,
+        width: rect.size.width,
+        height: rect.size.height,
     }
 }
+
+// Raw FFI for the display list (not exposed by cidre)
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGGetActiveDisplayList(
+        max_displays: u32,
+        displays: *mut cg::DirectDisplayId,
+        display_count: *mut u32,
+    ) -> i32;
+}
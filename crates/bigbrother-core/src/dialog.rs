@@ -0,0 +1,267 @@
+//! Dialog watching with handling rules
+//!
+//! Save prompts, permission alerts and crash reporters pop up in the middle
+//! of long workflows. The watcher polls for dialog windows and sheets, checks
+//! each new one against the rules in the order they were registered, applies
+//! the first rule that matches and logs a `DialogEvent` either way, so the
+//! agent can read back what happened behind its back.
+
+use crate::error::{Error, Result};
+use crate::role::Role;
+use crate::selector::Selector;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// What to do with a dialog a rule matched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogAction {
+    /// Click the element matching this selector inside the dialog
+    Click(String),
+    /// Press Escape with the dialog's app in front
+    Cancel,
+    /// Press Return with the dialog's app in front
+    Confirm,
+    /// Leave the dialog up; the event is still logged
+    Ignore,
+}
+
+impl DialogAction {
+    /// `click:<selector>`, `cancel`, `confirm` or `ignore`
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(target) = s.strip_prefix("click:") {
+            Selector::parse(target)?;
+            return Ok(DialogAction::Click(target.trim().to_string()));
+        }
+        match s.to_lowercase().as_str() {
+            "cancel" => Ok(DialogAction::Cancel),
+            "confirm" => Ok(DialogAction::Confirm),
+            "ignore" => Ok(DialogAction::Ignore),
+            _ => Err(Error::selector_invalid(
+                s,
+                "expected click:<selector>, cancel, confirm or ignore",
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for DialogAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DialogAction::Click(selector) => write!(f, "click:{}", selector),
+            DialogAction::Cancel => write!(f, "cancel"),
+            DialogAction::Confirm => write!(f, "confirm"),
+            DialogAction::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DialogRule {
+    pub name: String,
+    /// Matches the dialog itself or anything inside it, e.g. its message
+    pub when: Selector,
+    pub action: DialogAction,
+    /// Only dialogs of this app
+    pub app: Option<String>,
+}
+
+impl DialogRule {
+    pub fn new(name: &str, when: &str, action: DialogAction) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            when: Selector::parse(when)?,
+            action,
+            app: None,
+        })
+    }
+
+    /// Click `target` in dialogs matching `when`
+    pub fn click(name: &str, when: &str, target: &str) -> Result<Self> {
+        Selector::parse(target)?;
+        Self::new(name, when, DialogAction::Click(target.to_string()))
+    }
+
+    /// Parse `<when> => <action>`, as given to `bb dialogs --rule`
+    ///
+    /// The rule is named after its `when` part.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let Some((when, action)) = spec.split_once("=>") else {
+            return Err(Error::selector_invalid(spec, "expected '<selector> => <action>'")
+                .with_suggestions(vec![
+                    "e.g. \"name~:Do you want to save => click:name:Don't Save\"".to_string(),
+                ]));
+        };
+        Self::new(when.trim(), when, DialogAction::parse(action)?)
+    }
+
+    pub fn in_app(mut self, app: &str) -> Self {
+        self.app = Some(app.to_string());
+        self
+    }
+
+    pub fn applies_to_app(&self, app: &str) -> bool {
+        self.app.as_deref().is_none_or(|a| a.eq_ignore_ascii_case(app))
+    }
+}
+
+/// Whether a native role/subrole pair is a dialog rather than a document window
+pub fn is_dialog_role(role: &str, subrole: Option<&str>) -> bool {
+    Role::from_native(role) == Role::Dialog
+        || matches!(subrole, Some("AXDialog" | "AXSystemDialog"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Intervention {
+    /// A rule matched and its action went through
+    Handled,
+    /// A rule matched and said to leave the dialog alone
+    Ignored,
+    /// No rule matched; the dialog is still up
+    Unmatched,
+    /// A rule matched but its action failed
+    Failed,
+}
+
+/// One dialog the watcher saw, and what it did about it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DialogEvent {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    pub app: String,
+    pub pid: i32,
+    pub title: String,
+    pub role: String,
+    /// Text shown in the dialog, to decide on unmatched ones
+    pub text: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    pub outcome: Intervention,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+}
+
+/// Events shared between the watcher thread and its handle
+#[derive(Debug, Clone, Default)]
+pub struct DialogLog {
+    events: Arc<Mutex<Vec<DialogEvent>>>,
+}
+
+impl DialogLog {
+    pub fn push(&self, event: DialogEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    /// Everything logged so far
+    pub fn events(&self) -> Vec<DialogEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Everything logged since the last drain
+    pub fn drain(&self) -> Vec<DialogEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+/// Times a dialog is handed to the rules again after its rule failed
+pub const DIALOG_RETRIES: usize = 3;
+
+/// Dialogs on screen at the last poll
+///
+/// A dialog counts as new once; if it closes and opens again it is new again.
+#[derive(Debug, Default)]
+pub struct SeenDialogs {
+    keys: HashSet<String>,
+    failures: HashMap<String, usize>,
+}
+
+impl SeenDialogs {
+    /// Indices into `current` of dialogs that were not up at the last poll
+    pub fn update(&mut self, current: &[String]) -> Vec<usize> {
+        let fresh = current
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| !self.keys.contains(*key))
+            .map(|(i, _)| i)
+            .collect();
+        self.keys = current.iter().cloned().collect();
+        self.failures.retain(|key, _| self.keys.contains(key));
+        fresh
+    }
+
+    /// Count the dialog as new again at the next poll, since its rule
+    /// failed; after `DIALOG_RETRIES` failures it is left alone
+    pub fn failed(&mut self, key: &str) {
+        let failures = self.failures.entry(key.to_string()).or_insert(0);
+        *failures += 1;
+        if *failures <= DIALOG_RETRIES {
+            self.keys.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        let rule = DialogRule::parse("name~:Do you want to save => click:name:Don't Save").unwrap();
+        assert_eq!(rule.name, "name~:Do you want to save");
+        assert_eq!(rule.action, DialogAction::Click("name:Don't Save".into()));
+        assert_eq!(DialogRule::parse("role:dialog=>Cancel").unwrap().action, DialogAction::Cancel);
+        assert!(DialogRule::parse("role:dialog").is_err());
+        assert!(DialogRule::parse("role:dialog => close").is_err());
+        assert!(DialogRule::parse("role:dialog => click:").is_err());
+    }
+
+    #[test]
+    fn rules_scoped_to_app() {
+        let rule = DialogRule::new("crash", "title~:quit unexpectedly", DialogAction::Ignore).unwrap();
+        assert!(rule.applies_to_app("Safari"));
+        let rule = rule.in_app("safari");
+        assert!(rule.applies_to_app("Safari"));
+        assert!(!rule.applies_to_app("Mail"));
+    }
+
+    #[test]
+    fn dialog_roles() {
+        assert!(is_dialog_role("AXSheet", None));
+        assert!(is_dialog_role("AXWindow", Some("AXDialog")));
+        assert!(is_dialog_role("alert", None));
+        assert!(!is_dialog_role("AXWindow", Some("AXStandardWindow")));
+    }
+
+    #[test]
+    fn dialogs_are_new_once_per_appearance() {
+        let mut seen = SeenDialogs::default();
+        let keys = |items: &[&str]| items.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        assert_eq!(seen.update(&keys(&["a", "b"])), vec![0, 1]);
+        assert_eq!(seen.update(&keys(&["b", "c"])), vec![1]);
+        assert_eq!(seen.update(&keys(&["c"])), Vec::<usize>::new());
+        assert_eq!(seen.update(&keys(&["b", "c"])), vec![0]);
+    }
+
+    #[test]
+    fn failed_dialogs_are_retried_a_few_times() {
+        let mut seen = SeenDialogs::default();
+        let keys = vec!["a".to_string()];
+        let mut handled = seen.update(&keys).len();
+        for _ in 0..10 {
+            seen.failed("a");
+            handled += seen.update(&keys).len();
+        }
+        assert_eq!(handled, 1 + DIALOG_RETRIES);
+
+        // Closing the dialog resets its count
+        seen.update(&[]);
+        assert_eq!(seen.update(&keys), vec![0]);
+        seen.failed("a");
+        assert_eq!(seen.update(&keys), vec![0]);
+    }
+}
@@ -0,0 +1,278 @@
+//! Structural diff between two accessibility tree snapshots
+//!
+//! Nodes are matched by identity, not by their DFS index: the chain of
+//! role/label segments from the root (see `TreeResult::paths`). A node whose
+//! value changes keeps its identity and shows up as changed; a dialog that
+//! appears shows up as one added subtree.
+
+use crate::tree::{element_id, TreeNode, TreeResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeDiff {
+    pub added: Vec<DiffNode>,
+    pub removed: Vec<DiffNode>,
+    pub changed: Vec<ChangedNode>,
+    pub unchanged: usize,
+}
+
+/// Root of an added or removed subtree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffNode {
+    pub path: String,
+    pub id: String,
+    pub index: usize,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Number of nodes in the subtree, including this one
+    pub subtree_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedNode {
+    pub path: String,
+    pub id: String,
+    pub before_index: usize,
+    pub after_index: usize,
+    pub role: String,
+    pub changes: Vec<AttrChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttrChange {
+    pub attr: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+impl TreeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl TreeResult {
+    /// Diff this snapshot (before) against a later one (after)
+    pub fn diff(&self, after: &TreeResult) -> TreeDiff {
+        diff(self, after)
+    }
+}
+
+/// Compute the structural diff between two snapshots
+pub fn diff(before: &TreeResult, after: &TreeResult) -> TreeDiff {
+    let before_paths = before.paths();
+    let after_paths = after.paths();
+
+    let before_by_path: HashMap<&str, usize> = before_paths
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.as_str(), i))
+        .collect();
+    let after_by_path: HashMap<&str, usize> = after_paths
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.as_str(), i))
+        .collect();
+
+    let mut changed = Vec::new();
+    let mut unchanged = 0;
+
+    for (ai, path) in after_paths.iter().enumerate() {
+        if let Some(&bi) = before_by_path.get(path.as_str()) {
+            let changes = attr_changes(&before.nodes[bi], &after.nodes[ai]);
+            if changes.is_empty() {
+                unchanged += 1;
+            } else {
+                changed.push(ChangedNode {
+                    path: path.clone(),
+                    id: element_id(path),
+                    before_index: before.nodes[bi].index,
+                    after_index: after.nodes[ai].index,
+                    role: after.nodes[ai].role.clone(),
+                    changes,
+                });
+            }
+        }
+    }
+
+    TreeDiff {
+        added: subtree_roots(after, &after_paths, |p| !before_by_path.contains_key(p)),
+        removed: subtree_roots(before, &before_paths, |p| !after_by_path.contains_key(p)),
+        changed,
+        unchanged,
+    }
+}
+
+fn attr_changes(before: &TreeNode, after: &TreeNode) -> Vec<AttrChange> {
+    let pairs = [
+        ("name", &before.name, &after.name),
+        ("title", &before.title, &after.title),
+        ("value", &before.value, &after.value),
+    ];
+
+    pairs
+        .into_iter()
+        .filter(|(_, b, a)| b != a)
+        .map(|(attr, b, a)| AttrChange {
+            attr: attr.to_string(),
+            before: b.clone(),
+            after: a.clone(),
+        })
+        .collect()
+}
+
+/// Collapse unmatched nodes into the roots of unmatched subtrees
+fn subtree_roots<F>(tree: &TreeResult, paths: &[String], unmatched: F) -> Vec<DiffNode>
+where
+    F: Fn(&str) -> bool,
+{
+    let parents = tree.parents();
+    let mut roots: Vec<DiffNode> = Vec::new();
+    // Position in `roots` of the subtree each unmatched node belongs to
+    let mut owner: Vec<Option<usize>> = vec![None; tree.nodes.len()];
+
+    for (i, node) in tree.nodes.iter().enumerate() {
+        if !unmatched(&paths[i]) {
+            continue;
+        }
+
+        if let Some(root) = parents[i].and_then(|p| owner[p]) {
+            roots[root].subtree_size += 1;
+            owner[i] = Some(root);
+            continue;
+        }
+
+        owner[i] = Some(roots.len());
+        roots.push(DiffNode {
+            path: paths[i].clone(),
+            id: element_id(&paths[i]),
+            index: node.index,
+            role: node.role.clone(),
+            name: node.name.clone(),
+            title: node.title.clone(),
+            value: node.value.clone(),
+            subtree_size: 1,
+        });
+    }
+
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(mut nodes: Vec<TreeNode>) -> TreeResult {
+        for (i, n) in nodes.iter_mut().enumerate() {
+            n.index = i;
+        }
+        TreeResult {
+            app: "Test".to_string(),
+            element_count: nodes.len(),
+            nodes,
+            truncated: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn identical_trees_have_no_diff() {
+        let t = tree(vec![
+            TreeNode::test(0, "AXApplication").with_title("Test"),
+            TreeNode::test(1, "AXWindow").with_title("Main"),
+            TreeNode::test(2, "AXButton").with_title("OK"),
+        ]);
+        let d = t.diff(&t.clone());
+        assert!(d.is_empty());
+        assert_eq!(d.unchanged, 3);
+    }
+
+    #[test]
+    fn value_update_is_a_change_not_a_replacement() {
+        let before = tree(vec![
+            TreeNode::test(0, "AXWindow").with_title("Main"),
+            TreeNode::test(1, "AXTextField").with_value("hello"),
+        ]);
+        let after = tree(vec![
+            TreeNode::test(0, "AXWindow").with_title("Main"),
+            TreeNode::test(1, "AXTextField").with_value("hello world"),
+        ]);
+        let d = before.diff(&after);
+        assert!(d.added.is_empty() && d.removed.is_empty());
+        assert_eq!(d.changed.len(), 1);
+        assert_eq!(
+            d.changed[0].changes,
+            vec![AttrChange {
+                attr: "value".to_string(),
+                before: Some("hello".to_string()),
+                after: Some("hello world".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn new_dialog_is_one_added_subtree() {
+        let before = tree(vec![
+            TreeNode::test(0, "AXApplication").with_title("Test"),
+            TreeNode::test(1, "AXWindow").with_title("Main"),
+        ]);
+        let after = tree(vec![
+            TreeNode::test(0, "AXApplication").with_title("Test"),
+            TreeNode::test(1, "AXWindow").with_title("Main"),
+            TreeNode::test(1, "AXSheet"),
+            TreeNode::test(2, "AXButton").with_title("Save"),
+            TreeNode::test(2, "AXButton").with_title("Don't Save"),
+        ]);
+        let d = before.diff(&after);
+        assert_eq!(d.added.len(), 1);
+        assert_eq!(d.added[0].role, "AXSheet");
+        assert_eq!(d.added[0].subtree_size, 3);
+        assert_eq!(d.added[0].index, 2);
+        assert!(d.removed.is_empty());
+    }
+
+    #[test]
+    fn inserted_row_does_not_renumber_siblings() {
+        let before = tree(vec![
+            TreeNode::test(0, "AXTable"),
+            TreeNode::test(1, "AXRow").with_value("a"),
+            TreeNode::test(1, "AXRow").with_value("b"),
+        ]);
+        let after = tree(vec![
+            TreeNode::test(0, "AXTable"),
+            TreeNode::test(1, "AXRow").with_value("a"),
+            TreeNode::test(1, "AXRow").with_value("b"),
+            TreeNode::test(1, "AXRow").with_value("c"),
+        ]);
+        let d = before.diff(&after);
+        assert_eq!(d.added.len(), 1);
+        assert_eq!(d.added[0].path, "AXTable/AXRow[2]");
+        assert!(d.changed.is_empty());
+    }
+
+    #[test]
+    fn removed_subtree_reported_once() {
+        let before = tree(vec![
+            TreeNode::test(0, "AXWindow").with_title("Main"),
+            TreeNode::test(1, "AXGroup").with_title("Sidebar"),
+            TreeNode::test(2, "AXStaticText").with_value("Inbox"),
+            TreeNode::test(1, "AXButton").with_title("OK"),
+        ]);
+        let after = tree(vec![
+            TreeNode::test(0, "AXWindow").with_title("Main"),
+            TreeNode::test(1, "AXButton").with_title("OK"),
+        ]);
+        let d = before.diff(&after);
+        assert_eq!(d.removed.len(), 1);
+        assert_eq!(d.removed[0].path, "AXWindow(Main)/AXGroup(Sidebar)");
+        assert_eq!(d.removed[0].subtree_size, 2);
+        assert_eq!(d.unchanged, 2);
+    }
+}
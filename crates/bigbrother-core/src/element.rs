@@ -1,23 +1,45 @@
 //! UI Element representation with structured output
 
 use crate::accessibility::*;
-use crate::error::{Error, Result};
+use crate::attributes::AttributeRequest;
+use crate::error::{Error, ErrorCode, Result};
+use crate::form::{parse_checked, FieldKind};
 use crate::input;
+use crate::keys::{Chord, Key};
+use crate::lease::{InputLease, LeaseOptions};
+use crate::locator::Locator;
+use crate::menu::{menu_divergence, parse_menu_path, pick_menu_item};
+use crate::mouse::{MouseButton, DRAG_DURATION_MS};
+use crate::name::accessible_name;
+use crate::node::AccessibleNode;
+use crate::role::Role;
+use crate::scroll::{self, Scroll, ScrollUntilOptions};
+use crate::table::{is_table_role, Table, TableOptions, TableRows};
+use crate::text_entry::TextEntryOptions;
+use crate::tree::identity_label;
+use crate::verify::{self, TypingTarget, VerifiedTyping, VerifyOptions};
 use cidre::arc::R;
 use cidre::ax;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::Duration;
 
 #[derive(Clone)]
 pub struct UIElement {
     inner: R<ax::UiElement>,
     pub index: Option<usize>,
+    pub id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ElementInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub index: Option<usize>,
-    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub role: Role,
+    /// Platform role name, e.g. `AXButton`
+    pub native_role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -27,16 +49,15 @@ pub struct ElementInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bounds: Option<Bounds>,
+    /// Extra native attributes, only present when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Map<String, Value>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Bounds {
-    pub x: f64,
-    pub y: f64,
-    pub width: f64,
-    pub height: f64,
-}
+pub use crate::tree::Bounds;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionResult {
@@ -49,7 +70,7 @@ pub struct ActionResult {
 
 impl UIElement {
     pub fn new(inner: R<ax::UiElement>) -> Self {
-        Self { inner, index: None }
+        Self { inner, index: None, id: None }
     }
 
     pub fn with_index(mut self, index: usize) -> Self {
@@ -57,15 +78,31 @@ impl UIElement {
         self
     }
 
+    pub fn with_id(mut self, id: String) -> Self {
+        self.id = Some(id);
+        self
+    }
+
     pub fn raw(&self) -> &ax::UiElement {
         &self.inner
     }
 
+    /// Native role name, e.g. `AXButton`
     pub fn role(&self) -> Option<String> {
         get_role(&self.inner)
     }
 
+    pub fn canonical_role(&self) -> Role {
+        self.role().map_or(Role::Unknown, |r| Role::from_native(&r))
+    }
+
+    /// Accessible name, see `crate::name` for the precedence
     pub fn name(&self) -> Option<String> {
+        accessible_name(self)
+    }
+
+    /// Localized role text such as "button"; never used as the name
+    pub fn role_description(&self) -> Option<String> {
         get_role_desc(&self.inner)
     }
 
@@ -81,6 +118,17 @@ impl UIElement {
         get_description(&self.inner)
     }
 
+    /// Developer-assigned identifier, stable across localizations
+    pub fn identifier(&self) -> Option<String> {
+        get_identifier(&self.inner)
+    }
+
+    /// Label that tells this element apart from same-role siblings in its id
+    pub fn identity_label(&self) -> Option<String> {
+        let (title, identifier, description) = (self.title(), self.identifier(), self.description());
+        identity_label(title.as_deref(), identifier.as_deref(), description.as_deref()).map(String::from)
+    }
+
     pub fn text(&self) -> Option<String> {
         self.value()
             .or_else(|| self.title())
@@ -89,20 +137,181 @@ impl UIElement {
     }
 
     pub fn bounds(&self) -> Option<Bounds> {
-        // TODO: implement bounds extraction from AX API
-        None
+        get_bounds(&self.inner)
     }
 
     pub fn info(&self) -> ElementInfo {
+        let native_role = self.role().unwrap_or_else(|| "Unknown".to_string());
         ElementInfo {
             index: self.index,
-            role: self.role().unwrap_or_else(|| "Unknown".to_string()),
+            id: self.id.clone(),
+            role: Role::from_native(&native_role),
+            native_role,
             name: self.name(),
             title: self.title(),
             value: self.value(),
             description: self.description(),
+            role_description: self.role_description(),
             bounds: self.bounds(),
+            attributes: None,
+        }
+    }
+
+    /// `info()` plus the requested native attributes
+    pub fn info_with_attributes(&self, request: &AttributeRequest) -> ElementInfo {
+        let mut info = self.info();
+        info.attributes = Some(self.attributes(request));
+        info
+    }
+
+    /// Any native attribute by name, e.g. `AXURL`
+    pub fn attribute(&self, name: &str) -> Option<Value> {
+        get_attr_json(&self.inner, name)
+    }
+
+    /// Native attributes as JSON; ones that are missing or hold element references are left out
+    pub fn attributes(&self, request: &AttributeRequest) -> Map<String, Value> {
+        let names = match request {
+            AttributeRequest::All => get_attr_names(&self.inner),
+            AttributeRequest::Only(names) => names.clone(),
+        };
+        names
+            .into_iter()
+            .filter_map(|name| self.attribute(&name).map(|v| (name, v)))
+            .collect()
+    }
+
+    /// Read this table, grid or outline, or the first one inside this element
+    pub fn table(&self, options: &TableOptions) -> Result<Table> {
+        let table = self.find_table(TABLE_SEARCH_DEPTH).ok_or_else(|| {
+            Error::element_not_found("role:table").with_suggestions(vec![
+                "Select the table itself or a scroll area that contains it".to_string(),
+                "Use `bb tree` to check the role; tables, grids and outlines are supported".to_string(),
+            ])
+        })?;
+
+        // Scrolling through the rows is input too
+        let _lease = options.scroll.then(hold_input).transpose()?;
+        let headers = table_headers(&table.inner);
+        let mut rows = TableRows::new();
+        // Start from the first row, wherever the table was left scrolled
+        if options.scroll && scroll_table_to_top(&table.inner) {
+            std::thread::sleep(std::time::Duration::from_millis(options.settle_ms));
+        }
+        loop {
+            // When scrolling, read what is on screen; otherwise read every row
+            let attr = if options.scroll { ax::attr::visible_rows() } else { ax::attr::rows() };
+            let mut added = 0;
+            for row in get_elements_attr(&table.inner, attr) {
+                if rows.len() >= options.max_rows {
+                    break;
+                }
+                let index = get_number_attr(&row, ax::attr::index()).map(|i| i as usize);
+                let cells = UIElement::new(row).children().iter().map(cell_text).collect();
+                if rows.insert(index, cells) {
+                    added += 1;
+                }
+            }
+
+            if !options.scroll || added == 0 || rows.len() >= options.max_rows {
+                break;
+            }
+            if !scroll_table_page(&table.inner) {
+                break;
+            }
+            rows.scrolled();
+            std::thread::sleep(std::time::Duration::from_millis(options.settle_ms));
+        }
+
+        Ok(rows.into_table(headers))
+    }
+
+    /// Put a form value into this field the way its kind takes it
+    ///
+    /// Returns false when the field already held the value. Checkboxes and
+    /// radio buttons take `true`/`false`; pop-ups take the title of an item.
+    pub fn fill(&self, kind: FieldKind, value: &str) -> Result<bool> {
+        let _lease = hold_input()?;
+        match kind {
+            FieldKind::Text => self.fill_text(value),
+            FieldKind::CheckBox | FieldKind::RadioButton => {
+                let want = parse_checked(value).ok_or_else(|| {
+                    Error::action_failed("fill", &format!("expected true or false, got '{}'", value))
+                })?;
+                let checked = get_number_attr(&self.inner, ax::attr::value()).is_some_and(|v| v != 0.0);
+                if checked == want {
+                    return Ok(false);
+                }
+                if kind == FieldKind::RadioButton && !want {
+                    return Err(Error::action_failed(
+                        "fill",
+                        "a radio button is cleared by choosing another option",
+                    ));
+                }
+                self.click()?;
+                Ok(true)
+            }
+            FieldKind::PopUp => {
+                let current = self.value().or_else(|| self.title()).unwrap_or_default();
+                if pick_menu_item(value, &[current]).is_some() {
+                    return Ok(false);
+                }
+                let menu = open_submenu(self).ok_or_else(|| {
+                    Error::new(
+                        ErrorCode::Timeout,
+                        format!("Pop-up menu did not open within {}ms", MENU_TIMEOUT_MS),
+                    )
+                })?;
+                choose_menu_path(&menu, "pop-up menu", &[value.to_string()])?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn fill_text(&self, value: &str) -> Result<bool> {
+        if self.value().as_deref() == Some(value) {
+            return Ok(false);
+        }
+        set_bool_attr(&self.inner, ax::attr::focused(), true);
+        let set = set_string_attr(&self.inner, ax::attr::value(), value);
+        if !set || self.value().as_deref() != Some(value) {
+            // Web views and custom controls often ignore AXValue; type instead
+            input::cmd("a").map_err(Error::from)?;
+            input::type_text(value).map_err(Error::from)?;
+        }
+        Ok(true)
+    }
+
+    /// Open this element's context menu and follow a path such as `Copy Link`
+    pub fn context_menu(&self, path: &str) -> Result<ActionResult> {
+        let path = parse_menu_path(path)?;
+        let _lease = hold_input()?;
+        if let Err(e) = self.inner.perform_action(ax::action::show_menu()) {
+            return Err(Error::action_failed("context_menu", &format!("{:?}", e)));
         }
+
+        // The menu shows up under the element or, more often, under the app
+        let app = self.inner.pid().ok().map(|pid| UIElement::new(ax::UiElement::with_app_pid(pid)));
+        let menu = wait_for(MENU_TIMEOUT_MS, || {
+            open_menu_of(self).or_else(|| app.as_ref().and_then(open_menu_of))
+        })
+        .ok_or_else(|| {
+            Error::new(
+                ErrorCode::Timeout,
+                format!("Context menu did not open within {}ms", MENU_TIMEOUT_MS),
+            )
+        })?;
+        choose_menu_path(&menu, "context menu", &path)
+    }
+
+    fn find_table(&self, depth: usize) -> Option<UIElement> {
+        if self.role().is_some_and(|r| is_table_role(&r)) {
+            return Some(self.clone());
+        }
+        if depth == 0 {
+            return None;
+        }
+        self.children().iter().find_map(|c| c.find_table(depth - 1))
     }
 
     pub fn children(&self) -> Vec<UIElement> {
@@ -113,6 +322,7 @@ impl UIElement {
     }
 
     pub fn click(&self) -> Result<ActionResult> {
+        let _lease = hold_input()?;
         let start = std::time::Instant::now();
 
         // Try to perform AX press action
@@ -128,7 +338,124 @@ impl UIElement {
         })
     }
 
+    /// Drag from the center of this element to the center of `target`
+    pub fn drag_to(&self, target: &UIElement) -> Result<ActionResult> {
+        self.drag_to_with(target, Duration::from_millis(DRAG_DURATION_MS), MouseButton::Left)
+    }
+
+    pub fn drag_to_with(&self, target: &UIElement, duration: Duration, button: MouseButton) -> Result<ActionResult> {
+        let _lease = hold_input()?;
+        let start = std::time::Instant::now();
+        let from = self.drag_point("source")?;
+        let to = target.drag_point("target")?;
+        input::drag(from, to, duration, button).map_err(|e| Error::action_failed("drag", &e.to_string()))?;
+
+        Ok(ActionResult {
+            success: true,
+            action: "drag".to_string(),
+            element: Some(self.info()),
+            timing_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Screen point at the element's center, for pointer actions
+    fn drag_point(&self, which: &str) -> Result<(i32, i32)> {
+        self.center_point()
+            .ok_or_else(|| Error::action_failed("drag", &format!("drag {} has no on-screen bounds", which)))
+    }
+
+    fn center_point(&self) -> Option<(i32, i32)> {
+        let (x, y) = self.bounds().filter(|b| !b.is_empty())?.center();
+        Some((x.round() as i32, y.round() as i32))
+    }
+
+    /// Scroll with the pointer over the element's center
+    pub fn scroll(&self, scroll: &Scroll) -> Result<ActionResult> {
+        let _lease = hold_input()?;
+        let start = std::time::Instant::now();
+        let (x, y) = self
+            .center_point()
+            .ok_or_else(|| Error::action_failed("scroll", "element has no on-screen bounds"))?;
+        input::scroll_at(x, y, scroll).map_err(|e| Error::action_failed("scroll", &e.to_string()))?;
+
+        Ok(ActionResult {
+            success: true,
+            action: "scroll".to_string(),
+            element: Some(self.info()),
+            timing_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Scroll this container until `target` has its center inside it
+    ///
+    /// Fails with `ElementNotFound` when the end of the content comes first,
+    /// the report in the error's context.
+    pub fn scroll_until(&self, target: &Locator, options: &ScrollUntilOptions) -> Result<UIElement> {
+        let _lease = hold_input()?;
+        let container = self
+            .bounds()
+            .filter(|b| !b.is_empty())
+            .ok_or_else(|| Error::action_failed("scroll", "container has no on-screen bounds"))?;
+        let (x, y) = container.center();
+        let (found, report) = scroll::scroll_until(
+            options,
+            || {
+                target.find_all().ok()?.into_iter().find(|e| {
+                    e.bounds()
+                        .is_some_and(|b| !b.is_empty() && container.contains(b.center()))
+                })
+            },
+            || self.scroll_state(),
+            |step| input::scroll_at(x.round() as i32, y.round() as i32, step).map_err(Error::from),
+        )?;
+        found.ok_or_else(|| {
+            let suggestion = if report.end_reached {
+                "The container cannot scroll further; check the selector or scroll the other way"
+            } else {
+                "Raise max_steps or scroll further per step"
+            };
+            Error::element_not_found(&target.selector().to_string())
+                .with_suggestions(vec![suggestion.to_string()])
+                .with_context(serde_json::to_value(&report).unwrap_or_default())
+        })
+    }
+
+    /// What changes when the container scrolls: scroll bar positions and
+    /// where its content sits. Tables and outlines keep their bars on the
+    /// enclosing scroll area.
+    fn scroll_state(&self) -> (Vec<Option<f64>>, Vec<Option<(i64, i64)>>) {
+        let area = get_element_attr(&self.inner, ax::attr::parent());
+        let bars = [ax::attr::vertical_scroll_bar(), ax::attr::horizontal_scroll_bar()]
+            .into_iter()
+            .map(|attr| {
+                get_element_attr(&self.inner, attr)
+                    .or_else(|| area.as_ref().and_then(|area| get_element_attr(area, attr)))
+                    .and_then(|bar| get_number_attr(&bar, ax::attr::value()))
+            })
+            .collect();
+        let content = self
+            .children()
+            .into_iter()
+            .flat_map(|child| {
+                let grandchildren = child.children().into_iter().take(SCROLL_STATE_ELEMENTS);
+                std::iter::once(child.clone()).chain(grandchildren)
+            })
+            .take(SCROLL_STATE_ELEMENTS)
+            .map(|e| e.bounds().map(|b| (b.x.round() as i64, b.y.round() as i64)))
+            .collect();
+        (bars, content)
+    }
+
+    /// Type into this field and read it back, retrying slower on a mismatch,
+    /// see `crate::verify`
+    pub fn type_verified(&self, text: &str, options: &VerifyOptions) -> Result<VerifiedTyping> {
+        let _lease = hold_input()?;
+        set_bool_attr(&self.inner, ax::attr::focused(), true);
+        verify::type_verified(&mut FieldInput(self), text, options)
+    }
+
     pub fn set_value(&self, text: &str) -> Result<ActionResult> {
+        let _lease = hold_input()?;
         let start = std::time::Instant::now();
 
         // Try to set value via AX API
@@ -146,6 +473,212 @@ impl UIElement {
     }
 }
 
+/// A focused field typed into through the keyboard
+struct FieldInput<'a>(&'a UIElement);
+
+impl TypingTarget for FieldInput<'_> {
+    fn type_text(&mut self, text: &str, options: &TextEntryOptions) -> Result<()> {
+        // Characters that couldn't be typed show up in the read-back
+        input::enter_text(text, options);
+        Ok(())
+    }
+
+    fn read(&mut self) -> Option<String> {
+        self.0.value()
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        set_bool_attr(&self.0.inner, ax::attr::focused(), true);
+        input::cmd("a").map_err(Error::from)?;
+        input::press_chord(&Chord::new(Key::Backspace)).map_err(Error::from)
+    }
+}
+
+/// The input lease for an element action: the one the caller holds, else
+/// the default one, see `crate::lease`
+pub(crate) fn hold_input() -> Result<InputLease> {
+    InputLease::join_or_acquire(&LeaseOptions::default())
+}
+
+/// Content elements compared to tell whether a scroll step moved anything
+const SCROLL_STATE_ELEMENTS: usize = 24;
+
+/// How long to wait for each menu level to open
+pub(crate) const MENU_TIMEOUT_MS: u64 = 2000;
+
+/// Follow `path` from a menu bar or open menu and press the last item
+///
+/// Each level is opened and waited for before the next title is looked up.
+/// On failure the menus opened so far are closed again.
+pub(crate) fn choose_menu_path(root: &UIElement, root_label: &str, path: &[String]) -> Result<ActionResult> {
+    let _lease = hold_input()?;
+    let start = std::time::Instant::now();
+    let mut items = menu_items(root);
+    let mut opened: Option<UIElement> = None;
+
+    for (level, wanted) in path.iter().enumerate() {
+        let titles: Vec<String> = items.iter().map(|i| i.title().unwrap_or_default()).collect();
+        let Some(i) = pick_menu_item(wanted, &titles) else {
+            close_menu(opened.as_ref());
+            return Err(menu_divergence(root_label, path, level, &titles));
+        };
+        let item = &items[i];
+        if get_bool_attr(&item.inner, ax::attr::enabled()) == Some(false) {
+            close_menu(opened.as_ref());
+            return Err(Error::action_failed(
+                "menu",
+                &format!("'{}' is disabled", path[..=level].join(" > ")),
+            ));
+        }
+
+        if level + 1 == path.len() {
+            if let Err(e) = item.inner.perform_action(ax::action::press()) {
+                close_menu(opened.as_ref());
+                return Err(Error::action_failed("menu", &format!("{:?}", e)));
+            }
+            return Ok(ActionResult {
+                success: true,
+                action: "menu".to_string(),
+                element: Some(item.info()),
+                timing_ms: start.elapsed().as_millis() as u64,
+            });
+        }
+
+        let Some(submenu) = open_submenu(item) else {
+            close_menu(opened.as_ref());
+            return Err(Error::new(
+                ErrorCode::Timeout,
+                format!(
+                    "Menu '{}' did not open within {}ms",
+                    path[..=level].join(" > "),
+                    MENU_TIMEOUT_MS
+                ),
+            ));
+        };
+        items = menu_items(&submenu);
+        opened.get_or_insert(submenu);
+    }
+    unreachable!("menu paths have at least one level")
+}
+
+/// Titled items of a menu bar or menu, separators left out
+fn menu_items(menu: &UIElement) -> Vec<UIElement> {
+    menu.children()
+        .into_iter()
+        .filter(|i| i.title().is_some_and(|t| !t.is_empty()))
+        .collect()
+}
+
+/// The menu under an item, opening it if it is not there yet
+fn open_submenu(item: &UIElement) -> Option<UIElement> {
+    // Menu bar items only populate once open; nested items usually already do
+    if item.role().as_deref() != Some("AXMenuBarItem") {
+        if let Some(menu) = open_menu_of(item) {
+            return Some(menu);
+        }
+    }
+    item.inner.perform_action(ax::action::press()).ok()?;
+    wait_for(MENU_TIMEOUT_MS, || open_menu_of(item))
+}
+
+/// A child menu that has items
+fn open_menu_of(element: &UIElement) -> Option<UIElement> {
+    element
+        .children()
+        .into_iter()
+        .find(|c| c.role().as_deref() == Some("AXMenu") && !c.children().is_empty())
+}
+
+fn close_menu(menu: Option<&UIElement>) {
+    if let Some(menu) = menu {
+        let _ = menu.inner.perform_action(ax::action::cancel());
+    }
+}
+
+/// Poll until `f` returns something or the timeout passes
+fn wait_for<T>(timeout_ms: u64, f: impl Fn() -> Option<T>) -> Option<T> {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(timeout_ms);
+    loop {
+        if let Some(found) = f() {
+            return Some(found);
+        }
+        if std::time::Instant::now() >= deadline {
+            return None;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
+/// How deep `UIElement::table` looks for a table inside the given element
+const TABLE_SEARCH_DEPTH: usize = 4;
+
+/// Column titles from AXColumns headers, falling back to the table's header row
+fn table_headers(table: &ax::UiElement) -> Vec<String> {
+    let columns = get_elements_attr(table, ax::attr::columns());
+    let from_columns: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            get_element_attr(column, ax::attr::header())
+                .and_then(|h| UIElement::new(h).name())
+                .or_else(|| get_title(column))
+                .unwrap_or_default()
+        })
+        .collect();
+    if from_columns.iter().any(|h| !h.is_empty()) {
+        return from_columns;
+    }
+
+    get_element_attr(table, ax::attr::header())
+        .map(|header| UIElement::new(header).children().iter().map(cell_text).collect())
+        .unwrap_or_default()
+}
+
+/// Text shown in a cell: a field's value, otherwise its accessible name
+fn cell_text(cell: &UIElement) -> String {
+    cell.value().or_else(|| cell.name()).unwrap_or_default()
+}
+
+/// Vertical scroll bar of the scroll area around a table
+fn table_scroll_bar(table: &ax::UiElement) -> Option<R<ax::UiElement>> {
+    get_element_attr(table, ax::attr::parent())
+        .and_then(|area| get_element_attr(&area, ax::attr::vertical_scroll_bar()))
+}
+
+/// Scroll a table back to its first row; false when it already was there
+fn scroll_table_to_top(table: &ax::UiElement) -> bool {
+    let Some(bar) = table_scroll_bar(table) else {
+        return false;
+    };
+    match get_number_attr(&bar, ax::attr::value()) {
+        Some(position) if position > 0.0 => set_number_attr(&bar, ax::attr::value(), 0.0),
+        _ => false,
+    }
+}
+
+/// Move the enclosing scroll area down by about one screen of rows
+///
+/// Returns false at the bottom or when the table cannot be scrolled.
+fn scroll_table_page(table: &ax::UiElement) -> bool {
+    let Some(bar) = table_scroll_bar(table) else {
+        return false;
+    };
+    let Some(position) = get_number_attr(&bar, ax::attr::value()) else {
+        return false;
+    };
+    if position >= 1.0 {
+        return false;
+    }
+
+    let visible = get_elements_attr(table, ax::attr::visible_rows()).len();
+    let total = get_elements_attr(table, ax::attr::rows()).len();
+    if visible == 0 || total <= visible {
+        return false;
+    }
+    // Keep one row of overlap so nothing falls between two pages
+    let step = visible.saturating_sub(1).max(1) as f64 / (total - visible) as f64;
+    set_number_attr(&bar, ax::attr::value(), (position + step).min(1.0))
+}
+
 impl std::fmt::Debug for UIElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("UIElement")
@@ -155,3 +688,44 @@ impl std::fmt::Debug for UIElement {
             .finish()
     }
 }
+
+impl AccessibleNode for UIElement {
+    fn role(&self) -> Option<String> {
+        get_role(&self.inner)
+    }
+
+    fn title(&self) -> Option<String> {
+        get_title(&self.inner)
+    }
+
+    fn description(&self) -> Option<String> {
+        get_description(&self.inner)
+    }
+
+    fn placeholder(&self) -> Option<String> {
+        get_placeholder(&self.inner)
+    }
+
+    fn value(&self) -> Option<String> {
+        get_value(&self.inner)
+    }
+
+    fn role_description(&self) -> Option<String> {
+        get_role_desc(&self.inner)
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        get_bounds(&self.inner)
+    }
+
+    fn labelled_by(&self) -> Vec<Self> {
+        get_labelled_by(&self.inner)
+            .into_iter()
+            .map(UIElement::new)
+            .collect()
+    }
+
+    fn children(&self) -> Vec<Self> {
+        UIElement::children(self)
+    }
+}
@@ -24,8 +24,16 @@ pub enum ErrorCode {
     AppNotRunning,
     ActionFailed,
     SelectorInvalid,
+    /// An option or argument that isn't a selector has a value it can't take
+    InvalidArgument,
     MultipleMatches,
     NotImplemented,
+    /// Another agent holds the input lease, see `crate::lease`
+    InputBusy,
+    /// A field read back something other than what was typed, see `crate::verify`
+    ValueMismatch,
+    /// A field has no value to read back, such as a password field
+    ValueUnreadable,
     Unknown,
 }
 
@@ -85,6 +93,13 @@ impl Error {
         )
     }
 
+    pub fn invalid_argument(name: &str, value: &str, reason: &str) -> Self {
+        Self::new(
+            ErrorCode::InvalidArgument,
+            format!("Invalid {} '{}': {}", name, value, reason),
+        )
+    }
+
     pub fn multiple_matches(selector: &str, count: usize) -> Self {
         Self::new(
             ErrorCode::MultipleMatches,
@@ -0,0 +1,153 @@
+//! In-memory accessibility tree for unit tests
+//!
+//! Nodes live in a shared arena so relations such as labels can point
+//! anywhere in the tree, the same way they do in a live API.
+
+use crate::node::AccessibleNode;
+use crate::tree::Bounds;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+
+#[derive(Debug, Default, Clone)]
+pub struct FakeData {
+    pub role: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub placeholder: Option<String>,
+    pub value: Option<String>,
+    pub role_description: Option<String>,
+    pub bounds: Option<Bounds>,
+    pub labelled_by: Vec<usize>,
+    pub children: Vec<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct FakeTree {
+    nodes: RefCell<Vec<FakeData>>,
+}
+
+/// Handle to one node of a `FakeTree`
+#[derive(Debug, Clone)]
+pub struct FakeNode {
+    tree: Rc<FakeTree>,
+    id: usize,
+}
+
+impl FakeTree {
+    /// Start a new tree and return its root
+    pub fn root(role: &str) -> FakeNode {
+        let tree = Rc::new(FakeTree::default());
+        tree.nodes.borrow_mut().push(FakeData {
+            role: role.to_string(),
+            ..Default::default()
+        });
+        FakeNode { tree, id: 0 }
+    }
+}
+
+impl FakeNode {
+    /// Append a child and return it
+    pub fn add(&self, role: &str) -> FakeNode {
+        let mut nodes = self.tree.nodes.borrow_mut();
+        let id = nodes.len();
+        nodes.push(FakeData {
+            role: role.to_string(),
+            ..Default::default()
+        });
+        nodes[self.id].children.push(id);
+        FakeNode {
+            tree: self.tree.clone(),
+            id,
+        }
+    }
+
+    pub fn data(&self) -> Ref<'_, FakeData> {
+        Ref::map(self.tree.nodes.borrow(), |nodes| &nodes[self.id])
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut FakeData)) {
+        f(&mut self.tree.nodes.borrow_mut()[self.id]);
+    }
+
+    pub fn with_title(self, title: &str) -> Self {
+        self.update(|d| d.title = Some(title.to_string()));
+        self
+    }
+
+    pub fn with_description(self, description: &str) -> Self {
+        self.update(|d| d.description = Some(description.to_string()));
+        self
+    }
+
+    pub fn with_placeholder(self, placeholder: &str) -> Self {
+        self.update(|d| d.placeholder = Some(placeholder.to_string()));
+        self
+    }
+
+    pub fn with_value(self, value: &str) -> Self {
+        self.update(|d| d.value = Some(value.to_string()));
+        self
+    }
+
+    pub fn with_role_description(self, role_description: &str) -> Self {
+        self.update(|d| d.role_description = Some(role_description.to_string()));
+        self
+    }
+
+    pub fn with_bounds(self, x: f64, y: f64, width: f64, height: f64) -> Self {
+        self.update(|d| d.bounds = Some(Bounds { x, y, width, height }));
+        self
+    }
+
+    pub fn labelled_by_node(self, label: &FakeNode) -> Self {
+        self.update(|d| d.labelled_by.push(label.id));
+        self
+    }
+
+    fn node(&self, id: usize) -> FakeNode {
+        FakeNode {
+            tree: self.tree.clone(),
+            id,
+        }
+    }
+}
+
+impl AccessibleNode for FakeNode {
+    fn role(&self) -> Option<String> {
+        Some(self.data().role.clone())
+    }
+
+    fn title(&self) -> Option<String> {
+        self.data().title.clone()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.data().description.clone()
+    }
+
+    fn placeholder(&self) -> Option<String> {
+        self.data().placeholder.clone()
+    }
+
+    fn value(&self) -> Option<String> {
+        self.data().value.clone()
+    }
+
+    fn role_description(&self) -> Option<String> {
+        self.data().role_description.clone()
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.data().bounds
+    }
+
+    fn labelled_by(&self) -> Vec<Self> {
+        let ids = self.data().labelled_by.clone();
+        ids.into_iter().map(|id| self.node(id)).collect()
+    }
+
+    fn children(&self) -> Vec<Self> {
+        let ids = self.data().children.clone();
+        ids.into_iter().map(|id| self.node(id)).collect()
+    }
+}
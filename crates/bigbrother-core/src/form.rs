@@ -0,0 +1,386 @@
+//! Form filling by field labels
+//!
+//! A label is resolved to its input in this order:
+//!
+//! 1. label relation: the input names the label element (AXTitleUIElement,
+//!    LabeledBy), or its own accessible name is the label, as for checkboxes
+//! 2. spatial proximity: the nearest input right of or below a static text
+//!    with the label
+//! 3. shared parent: the input next to that static text under the same parent
+//!
+//! Labels compare loosely: case, extra whitespace and a trailing `:` or `*`
+//! are ignored.
+
+use crate::name::{accessible_name, is_static_text};
+use crate::node::AccessibleNode;
+use crate::role::Role;
+use crate::tree::Bounds;
+use serde::{Deserialize, Serialize};
+
+/// How far an input may sit from its label, in points
+const MAX_LABEL_DISTANCE: f64 = 250.0;
+
+/// How the value is applied to a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    Text,
+    CheckBox,
+    RadioButton,
+    PopUp,
+}
+
+impl FieldKind {
+    pub fn from_role(role: Role) -> Option<Self> {
+        match role {
+            Role::TextField | Role::TextArea | Role::ComboBox => Some(Self::Text),
+            Role::CheckBox => Some(Self::CheckBox),
+            Role::RadioButton => Some(Self::RadioButton),
+            Role::PopUpButton => Some(Self::PopUp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedBy {
+    LabelRelation,
+    Name,
+    Proximity,
+    SharedParent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldStatus {
+    Filled,
+    /// The field already held the value
+    Unchanged,
+    NotFound,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldReport {
+    pub label: String,
+    pub value: String,
+    pub status: FieldStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<FieldKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_by: Option<MatchedBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FieldReport {
+    /// Report for a label no field was found for; resolution fills in the rest
+    pub fn not_found(label: String, value: String) -> Self {
+        Self {
+            label,
+            value,
+            status: FieldStatus::NotFound,
+            kind: None,
+            matched_by: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormReport {
+    pub filled: usize,
+    pub failed: usize,
+    pub fields: Vec<FieldReport>,
+}
+
+impl FormReport {
+    pub fn new(fields: Vec<FieldReport>) -> Self {
+        let filled = fields
+            .iter()
+            .filter(|f| matches!(f.status, FieldStatus::Filled | FieldStatus::Unchanged))
+            .count();
+        Self {
+            filled,
+            failed: fields.len() - filled,
+            fields,
+        }
+    }
+}
+
+/// A label resolved to an input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedField {
+    pub index: usize,
+    pub kind: FieldKind,
+    pub matched_by: MatchedBy,
+}
+
+struct Entry<N> {
+    node: N,
+    parent: Option<usize>,
+    role: Role,
+    kind: Option<FieldKind>,
+    bounds: Option<Bounds>,
+    /// Accessible name, for inputs and static text only
+    name: Option<String>,
+}
+
+/// Inputs and label texts of one window, read once
+pub struct FormFields<N> {
+    entries: Vec<Entry<N>>,
+}
+
+impl<N: AccessibleNode + Clone> FormFields<N> {
+    pub fn collect(root: &N, max_depth: usize) -> Self {
+        let mut fields = Self { entries: Vec::new() };
+        fields.walk(root, None, 0, max_depth);
+        fields
+    }
+
+    fn walk(&mut self, node: &N, parent: Option<usize>, depth: usize, max_depth: usize) {
+        let native = node.role().unwrap_or_default();
+        let role = Role::from_native(&native);
+        let kind = FieldKind::from_role(role);
+        let name = if kind.is_some() || is_static_text(&native) {
+            accessible_name(node)
+        } else {
+            None
+        };
+        let index = self.entries.len();
+        self.entries.push(Entry {
+            node: node.clone(),
+            parent,
+            role,
+            kind,
+            bounds: node.bounds(),
+            name,
+        });
+        if depth < max_depth {
+            for child in node.children() {
+                self.walk(&child, Some(index), depth + 1, max_depth);
+            }
+        }
+    }
+
+    pub fn node(&self, index: usize) -> &N {
+        &self.entries[index].node
+    }
+
+    /// Find the input for a label, see the module docs for the order
+    pub fn resolve(&self, label: &str) -> Option<ResolvedField> {
+        let inputs = || self.entries.iter().enumerate().filter(|(_, e)| e.kind.is_some());
+        let found = |index: usize, matched_by| ResolvedField {
+            index,
+            kind: self.entries[index].kind.unwrap(),
+            matched_by,
+        };
+
+        let by_relation = inputs().find(|(_, e)| {
+            e.node
+                .labelled_by()
+                .iter()
+                .any(|l| accessible_name(l).is_some_and(|t| label_matches(label, &t)))
+        });
+        if let Some((i, _)) = by_relation {
+            return Some(found(i, MatchedBy::LabelRelation));
+        }
+        if let Some((i, _)) = inputs().find(|(_, e)| e.name.as_deref().is_some_and(|n| label_matches(label, n))) {
+            return Some(found(i, MatchedBy::Name));
+        }
+
+        let labels: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| {
+                e.role == Role::StaticText && e.name.as_deref().is_some_and(|n| label_matches(label, n))
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        let nearest = labels
+            .iter()
+            .filter_map(|&l| self.entries[l].bounds)
+            .flat_map(|lb| {
+                inputs().filter_map(move |(i, e)| Some((i, label_distance(&lb, e.bounds.as_ref()?)?)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, _)) = nearest {
+            return Some(found(i, MatchedBy::Proximity));
+        }
+
+        labels
+            .iter()
+            .find_map(|&l| self.sibling_input(l))
+            .map(|i| found(i, MatchedBy::SharedParent))
+    }
+
+    /// The input after a label under the same parent, else the last one before it
+    ///
+    /// Siblings that are containers count through their first input, so a
+    /// label next to a radio group finds the group's first radio.
+    fn sibling_input(&self, label: usize) -> Option<usize> {
+        let parent = self.entries[label].parent?;
+        let siblings: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| i != label && self.entries[i].parent == Some(parent))
+            .filter_map(|i| self.first_input(i))
+            .collect();
+        siblings
+            .iter()
+            .find(|&&i| i > label)
+            .or_else(|| siblings.iter().rev().find(|&&i| i < label))
+            .copied()
+    }
+
+    /// The entry itself if it is an input, else its first input descendant
+    fn first_input(&self, index: usize) -> Option<usize> {
+        // Entries are in tree order, so descendants directly follow their ancestor
+        (index..self.entries.len())
+            .take_while(|&i| self.is_within(i, index))
+            .find(|&i| self.entries[i].kind.is_some())
+    }
+
+    fn is_within(&self, mut index: usize, ancestor: usize) -> bool {
+        loop {
+            if index == ancestor {
+                return true;
+            }
+            match self.entries[index].parent {
+                Some(parent) => index = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// For a radio button, the radio in its group whose name is `option`
+    pub fn radio_option(&self, index: usize, option: &str) -> Option<usize> {
+        let parent = self.entries[index].parent;
+        (0..self.entries.len()).find(|&i| {
+            let e = &self.entries[i];
+            e.parent == parent
+                && e.kind == Some(FieldKind::RadioButton)
+                && e.name.as_deref().is_some_and(|n| label_matches(option, n))
+        })
+    }
+}
+
+/// Whether a label text names the wanted field
+pub fn label_matches(wanted: &str, text: &str) -> bool {
+    normalize_label(wanted) == normalize_label(text)
+}
+
+fn normalize_label(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    text.trim_end_matches([':', '*', ' ']).to_lowercase()
+}
+
+/// Distance from a label to an input right of it or below it
+fn label_distance(label: &Bounds, input: &Bounds) -> Option<f64> {
+    let label_mid_y = label.y + label.height / 2.0;
+    let beside = input.x >= label.x + label.width - 4.0
+        && label_mid_y >= input.y
+        && label_mid_y <= input.y + input.height;
+    let below = input.y >= label.y + label.height - 4.0 && (input.x - label.x).abs() <= 40.0;
+
+    let distance = if beside {
+        input.x - (label.x + label.width)
+    } else if below {
+        input.y - (label.y + label.height) + (input.x - label.x).abs()
+    } else {
+        return None;
+    };
+    (distance <= MAX_LABEL_DISTANCE).then_some(distance.max(0.0))
+}
+
+/// Read a checkbox or radio value such as `true`, `on`, `yes` or `1`
+pub fn parse_checked(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" | "checked" | "x" => Some(true),
+        "false" | "off" | "no" | "0" | "unchecked" | "" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeTree;
+
+    #[test]
+    fn label_relation_comes_first() {
+        let form = FakeTree::root("AXGroup");
+        let label = form.add("AXStaticText").with_value("Email:");
+        form.add("AXTextField").with_placeholder("Name");
+        form.add("AXTextField").labelled_by_node(&label);
+
+        let fields = FormFields::collect(&form, 10);
+        let field = fields.resolve("email").unwrap();
+        assert_eq!(field.index, 3);
+        assert_eq!(field.kind, FieldKind::Text);
+        assert_eq!(field.matched_by, MatchedBy::LabelRelation);
+    }
+
+    #[test]
+    fn checkboxes_match_their_own_title() {
+        let form = FakeTree::root("AXGroup");
+        form.add("AXCheckBox").with_title("Remember me");
+        let fields = FormFields::collect(&form, 10);
+        let field = fields.resolve("Remember me").unwrap();
+        assert_eq!((field.index, field.kind, field.matched_by), (1, FieldKind::CheckBox, MatchedBy::Name));
+    }
+
+    #[test]
+    fn proximity_pairs_labels_and_fields_in_separate_columns() {
+        let form = FakeTree::root("AXGroup");
+        let labels = form.add("AXGroup");
+        labels.add("AXStaticText").with_value("First name").with_bounds(0.0, 0.0, 80.0, 20.0);
+        labels.add("AXStaticText").with_value("Last name").with_bounds(0.0, 40.0, 80.0, 20.0);
+        let inputs = form.add("AXGroup");
+        inputs.add("AXTextField").with_bounds(100.0, 0.0, 200.0, 22.0);
+        inputs.add("AXTextField").with_bounds(100.0, 38.0, 200.0, 22.0);
+
+        let fields = FormFields::collect(&form, 10);
+        let last = fields.resolve("Last name").unwrap();
+        assert_eq!(last.index, 6);
+        assert_eq!(last.matched_by, MatchedBy::Proximity);
+        assert_eq!(fields.resolve("First name").unwrap().index, 5);
+    }
+
+    #[test]
+    fn shared_parent_without_bounds() {
+        let form = FakeTree::root("AXGroup");
+        let row = form.add("AXGroup");
+        row.add("AXStaticText").with_value("Country *");
+        row.add("AXPopUpButton");
+        form.add("AXTextField");
+
+        let fields = FormFields::collect(&form, 10);
+        let field = fields.resolve("Country").unwrap();
+        assert_eq!((field.index, field.kind), (3, FieldKind::PopUp));
+        assert_eq!(field.matched_by, MatchedBy::SharedParent);
+        assert!(fields.resolve("Phone").is_none());
+    }
+
+    #[test]
+    fn radio_options_are_found_in_the_group() {
+        let form = FakeTree::root("AXGroup");
+        form.add("AXStaticText").with_value("Size");
+        let group = form.add("AXRadioGroup");
+        group.add("AXRadioButton").with_title("Small");
+        group.add("AXRadioButton").with_title("Large");
+
+        let fields = FormFields::collect(&form, 10);
+        let field = fields.resolve("Size").unwrap();
+        assert_eq!(field.kind, FieldKind::RadioButton);
+        assert_eq!(fields.radio_option(field.index, "large"), Some(4));
+    }
+
+    #[test]
+    fn checked_values() {
+        assert_eq!(parse_checked("Yes"), Some(true));
+        assert_eq!(parse_checked("off"), Some(false));
+        assert_eq!(parse_checked("maybe"), None);
+    }
+}
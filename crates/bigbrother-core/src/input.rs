@@ -1,6 +1,13 @@
 //! Keyboard and mouse input simulation
 
+use crate::keys::{Chord, Key, Modifier};
+use crate::mouse::{drag_path, MotionProfile, MouseButton, DRAG_STEP_MS};
+use crate::scroll::{Scroll, ScrollUnit};
+use crate::text_entry::{
+    enter_with, EntryStrategy, RunError, TextEntryBackend, TextEntryOptions, TextEntryReport, PASTE_SETTLE_MS,
+};
 use anyhow::{Context, Result};
+use cidre::cg;
 use std::process::Command;
 use std::thread;
 use std::time::Duration;
@@ -80,45 +87,136 @@ pub fn press_key_repeat(key_code: u8, times: u32, delay_ms: u64) -> Result<()> {
     Ok(())
 }
 
-/// Type text using keystroke
+/// Type text with Unicode key events, pasting what they can't carry
+///
+/// Fails when any character could not be typed; `enter_text` reports them
+/// instead.
 pub fn type_text(text: &str) -> Result<()> {
-    let escaped = text.replace("\\", "\\\\").replace("\"", "\\\"");
-    let script = format!(
-        r#"tell application "System Events" to keystroke "{}""#,
-        escaped
-    );
+    enter_text(text, &TextEntryOptions::default()).into_result()?;
+    Ok(())
+}
 
-    Command::new("osascript")
-        .arg("-e")
-        .arg(&script)
-        .output()
-        .context("Failed to type text")?;
+/// Type text through the strategy stack, see `crate::text_entry`
+pub fn enter_text(text: &str, options: &TextEntryOptions) -> TextEntryReport {
+    let mut backend = MacTextEntry {
+        key_delay: Duration::from_millis(options.key_delay_ms),
+    };
+    enter_with(&mut backend, text, options)
+}
+
+struct MacTextEntry {
+    key_delay: Duration,
+}
 
+impl TextEntryBackend for MacTextEntry {
+    fn strategies(&self) -> Vec<EntryStrategy> {
+        vec![EntryStrategy::Unicode, EntryStrategy::Paste]
+    }
+
+    fn can_type(&self, strategy: EntryStrategy, c: char) -> bool {
+        match strategy {
+            EntryStrategy::Unicode => Key::from_control_char(c).is_some() || !c.is_control(),
+            EntryStrategy::Paste => !c.is_control() || c == '\n' || c == '\t',
+            EntryStrategy::Keysym => false,
+        }
+    }
+
+    fn type_run(&mut self, strategy: EntryStrategy, text: &str) -> std::result::Result<(), RunError> {
+        match strategy {
+            EntryStrategy::Unicode => post_chars(text, self.key_delay),
+            EntryStrategy::Paste => Ok(paste(text)?),
+            EntryStrategy::Keysym => Err(crate::error::Error::new(
+                crate::error::ErrorCode::NotImplemented,
+                "Keysym entry only exists on X11".to_string(),
+            )
+            .into()),
+        }
+    }
+}
+
+fn post_chars(text: &str, key_delay: Duration) -> std::result::Result<(), RunError> {
+    for (typed, c) in text.chars().enumerate() {
+        post_char(c).map_err(|e| RunError {
+            typed,
+            error: e.into(),
+        })?;
+        thread::sleep(key_delay);
+    }
     Ok(())
 }
 
-/// Press a keyboard shortcut (e.g., Cmd+C)
-pub fn shortcut(key: &str, modifiers: &[&str]) -> Result<()> {
-    let modifier_str = modifiers
-        .iter()
-        .map(|m| format!("{} down", m))
-        .collect::<Vec<_>>()
-        .join(", ");
+fn post_char(c: char) -> Result<()> {
+    if let Some(key) = Key::from_control_char(c) {
+        for down in [true, false] {
+            let event = cg::Event::keyboard(None, key.to_mac(), down).context("Failed to create key event")?;
+            post(&event);
+        }
+        return Ok(());
+    }
+    let mut units = [0u16; 2];
+    let units = c.encode_utf16(&mut units);
+    for down in [true, false] {
+        // The keycode is ignored once the event carries a string
+        let event = cg::Event::keyboard(None, 0, down).context("Failed to create key event")?;
+        unsafe {
+            CGEventKeyboardSetUnicodeString(
+                &*event as *const cg::Event as *const std::ffi::c_void,
+                units.len(),
+                units.as_ptr(),
+            );
+        }
+        post(&event);
+    }
+    Ok(())
+}
 
-    let script = format!(
-        r#"tell application "System Events" to keystroke "{}" using {{{}}}"#,
-        key, modifier_str
-    );
+/// Paste through the clipboard, putting back what was there
+fn paste(text: &str) -> crate::error::Result<()> {
+    crate::platform::macos::clipboard::paste(text, PASTE_SETTLE_MS)
+}
+
+/// Press a key chord such as `cmd+shift+t`, see `crate::keys`
+pub fn press_chord(chord: &Chord) -> Result<()> {
+    let using: Vec<&str> = chord
+        .modifiers
+        .iter()
+        .map(|m| match m {
+            Modifier::Control => "control down",
+            Modifier::Alt => "option down",
+            Modifier::Shift => "shift down",
+            Modifier::Meta => "command down",
+        })
+        .collect();
+    // Keys that type a character go by the character, so the user's layout
+    // picks the key: cmd+a stays cmd+a on AZERTY. The rest go by keycode.
+    let key = match chord.key.glyph() {
+        Some(c) => format!(r#"keystroke "{}""#, c.to_string().replace('\\', "\\\\").replace('"', "\\\"")),
+        None => format!("key code {}", chord.key.to_mac()),
+    };
+    let script = if using.is_empty() {
+        format!(r#"tell application "System Events" to {}"#, key)
+    } else {
+        format!(
+            r#"tell application "System Events" to {} using {{{}}}"#,
+            key,
+            using.join(", ")
+        )
+    };
 
     Command::new("osascript")
         .arg("-e")
         .arg(&script)
         .output()
-        .context("Failed to execute shortcut")?;
+        .context("Failed to press key chord")?;
 
     Ok(())
 }
 
+/// Press a keyboard shortcut (e.g., Cmd+C)
+pub fn shortcut(key: &str, modifiers: &[&str]) -> Result<()> {
+    press_chord(&Chord::from_parts(key, modifiers)?)
+}
+
 /// Press Cmd+key shortcut
 pub fn cmd(key: &str) -> Result<()> {
     shortcut(key, &["command"])
@@ -134,6 +232,21 @@ pub fn scroll_down(pages: u32) -> Result<()> {
     press_key_repeat(key_codes::PAGE_DOWN, pages, 300)
 }
 
+/// Scroll with the wheel at a screen point, see `crate::scroll`
+pub fn scroll_at(x: i32, y: i32, scroll: &Scroll) -> Result<()> {
+    post_mouse(cg::EventType::MOUSE_MOVED, (x, y), MouseButton::Left)?;
+    thread::sleep(Duration::from_millis(10));
+    let unit = match scroll.unit {
+        ScrollUnit::Lines => cg::ScrollEventUnit::Line,
+        ScrollUnit::Pixels => cg::ScrollEventUnit::Pixel,
+    };
+    // Wheel deltas count up and left as positive, and are signed despite the u32
+    let event = cg::Event::wheel_2(None, unit, scroll.dy.wrapping_neg() as u32, scroll.dx.wrapping_neg() as u32)
+        .context("Failed to create scroll event")?;
+    post(&event);
+    Ok(())
+}
+
 /// Scroll up in a specific application
 pub fn scroll_up_in_app(app_name: &str, pages: u32, delay_ms: u64) -> Result<()> {
     let script = format!(
@@ -189,6 +302,31 @@ pub fn click_at(x: i32, y: i32, button: &str) -> Result<()> {
     Ok(())
 }
 
+/// Click at screen coordinates after moving there along `profile`
+pub fn click_at_with(x: i32, y: i32, button: &str, profile: &MotionProfile) -> Result<()> {
+    move_mouse_with(x, y, profile)?;
+    click_at(x, y, button)
+}
+
+/// Move the mouse to screen coordinates along `profile`
+pub fn move_mouse_with(x: i32, y: i32, profile: &MotionProfile) -> Result<()> {
+    if profile.is_instant() {
+        return move_mouse(x, y);
+    }
+    for point in profile.path(mouse_position()?, (x, y)) {
+        post_mouse(cg::EventType::MOUSE_MOVED, point, MouseButton::Left)?;
+        thread::sleep(Duration::from_millis(profile.step_ms));
+    }
+    Ok(())
+}
+
+/// Current pointer position in screen coordinates
+pub fn mouse_position() -> Result<(i32, i32)> {
+    let event = cg::Event::with_src(None).context("Failed to read mouse position")?;
+    let loc = event.location();
+    Ok((loc.x.round() as i32, loc.y.round() as i32))
+}
+
 /// Move mouse to screen coordinates
 pub fn move_mouse(x: i32, y: i32) -> Result<()> {
     let script = format!(
@@ -206,3 +344,66 @@ pub fn move_mouse(x: i32, y: i32) -> Result<()> {
 
     Ok(())
 }
+
+// Raw FFI for CGEventPost (not exposed by cidre)
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventPost(tap: u32, event: *const std::ffi::c_void);
+    fn CGEventKeyboardSetUnicodeString(event: *const std::ffi::c_void, length: usize, string: *const u16);
+}
+
+const HID_EVENT_TAP: u32 = 0;
+
+/// Press `button` at `from`, move to `to` over `duration` and release there
+pub fn drag(from: (i32, i32), to: (i32, i32), duration: Duration, button: MouseButton) -> Result<()> {
+    let (down, dragged, up) = match button {
+        MouseButton::Left => (
+            cg::EventType::LEFT_MOUSE_DOWN,
+            cg::EventType::LEFT_MOUSE_DRAGGED,
+            cg::EventType::LEFT_MOUSE_UP,
+        ),
+        MouseButton::Right => (
+            cg::EventType::RIGHT_MOUSE_DOWN,
+            cg::EventType::RIGHT_MOUSE_DRAGGED,
+            cg::EventType::RIGHT_MOUSE_UP,
+        ),
+        MouseButton::Middle => (
+            cg::EventType::OHTER_MOUSE_DOWN,
+            cg::EventType::OTHER_MOUSE_DRAGGED,
+            cg::EventType::OHTER_MOUSE_UP,
+        ),
+    };
+
+    post_mouse(cg::EventType::MOUSE_MOVED, from, button)?;
+    thread::sleep(Duration::from_millis(10));
+    post_mouse(down, from, button)?;
+
+    let moved = drag_path(from, to, duration).into_iter().try_for_each(|point| {
+        thread::sleep(Duration::from_millis(DRAG_STEP_MS));
+        post_mouse(dragged, point, button)
+    });
+    // Release even when a move failed, or the button stays stuck down
+    let released = post_mouse(up, to, button);
+    moved.and(released)
+}
+
+fn post_mouse(kind: cg::EventType, at: (i32, i32), button: MouseButton) -> Result<()> {
+    let pos = cg::Point {
+        x: at.0 as f64,
+        y: at.1 as f64,
+    };
+    let cg_button = match button {
+        MouseButton::Left => cg::MouseButton::Left,
+        MouseButton::Right => cg::MouseButton::Right,
+        MouseButton::Middle => cg::MouseButton::Center,
+    };
+    let event = cg::Event::mouse(None, kind, pos, cg_button).context("Failed to create mouse event")?;
+    post(&event);
+    Ok(())
+}
+
+fn post(event: &cg::Event) {
+    unsafe {
+        CGEventPost(HID_EVENT_TAP, event as *const cg::Event as *const std::ffi::c_void);
+    }
+}
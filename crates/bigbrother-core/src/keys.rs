@@ -0,0 +1,560 @@
+//! Platform-neutral keys and chords
+//!
+//! `Key` names a physical key by its US-layout meaning. One table maps it
+//! both ways to macOS virtual keycodes, Windows virtual-key codes and X11
+//! keysyms, so `press`, `shortcut`, the recorder and replay all agree on
+//! what `escape` or `cmd+shift+t` means.
+//!
+//! Chord syntax: modifiers and a key joined by `+`, any case, e.g.
+//! `ctrl+shift+t`, `cmd+alt+Escape`, `shift+F5`.
+
+use crate::error::{Error, ErrorCode, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[rustfmt::skip]
+pub enum Key {
+    A, B, C, D, E, F, G, H, I, J, K, L, M,
+    N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Return,
+    Tab,
+    Space,
+    Backspace,
+    ForwardDelete,
+    Escape,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Up,
+    Down,
+    Minus,
+    Equal,
+    LeftBracket,
+    RightBracket,
+    Backslash,
+    Semicolon,
+    Quote,
+    Comma,
+    Period,
+    Slash,
+    Grave,
+    CapsLock,
+    Shift,
+    Control,
+    Alt,
+    /// Command on macOS, the Windows key, Super on X11
+    Meta,
+}
+
+/// Key, canonical name, macOS keycode, Windows VK, X11 keysym
+///
+/// `Insert` sits where macOS keyboards have Help (114).
+#[rustfmt::skip]
+const KEYS: &[(Key, &str, u16, u16, u32)] = &[
+    (Key::A, "a", 0, 0x41, 0x61),
+    (Key::B, "b", 11, 0x42, 0x62),
+    (Key::C, "c", 8, 0x43, 0x63),
+    (Key::D, "d", 2, 0x44, 0x64),
+    (Key::E, "e", 14, 0x45, 0x65),
+    (Key::F, "f", 3, 0x46, 0x66),
+    (Key::G, "g", 5, 0x47, 0x67),
+    (Key::H, "h", 4, 0x48, 0x68),
+    (Key::I, "i", 34, 0x49, 0x69),
+    (Key::J, "j", 38, 0x4A, 0x6a),
+    (Key::K, "k", 40, 0x4B, 0x6b),
+    (Key::L, "l", 37, 0x4C, 0x6c),
+    (Key::M, "m", 46, 0x4D, 0x6d),
+    (Key::N, "n", 45, 0x4E, 0x6e),
+    (Key::O, "o", 31, 0x4F, 0x6f),
+    (Key::P, "p", 35, 0x50, 0x70),
+    (Key::Q, "q", 12, 0x51, 0x71),
+    (Key::R, "r", 15, 0x52, 0x72),
+    (Key::S, "s", 1, 0x53, 0x73),
+    (Key::T, "t", 17, 0x54, 0x74),
+    (Key::U, "u", 32, 0x55, 0x75),
+    (Key::V, "v", 9, 0x56, 0x76),
+    (Key::W, "w", 13, 0x57, 0x77),
+    (Key::X, "x", 7, 0x58, 0x78),
+    (Key::Y, "y", 16, 0x59, 0x79),
+    (Key::Z, "z", 6, 0x5A, 0x7a),
+    (Key::Num0, "0", 29, 0x30, 0x30),
+    (Key::Num1, "1", 18, 0x31, 0x31),
+    (Key::Num2, "2", 19, 0x32, 0x32),
+    (Key::Num3, "3", 20, 0x33, 0x33),
+    (Key::Num4, "4", 21, 0x34, 0x34),
+    (Key::Num5, "5", 23, 0x35, 0x35),
+    (Key::Num6, "6", 22, 0x36, 0x36),
+    (Key::Num7, "7", 26, 0x37, 0x37),
+    (Key::Num8, "8", 28, 0x38, 0x38),
+    (Key::Num9, "9", 25, 0x39, 0x39),
+    (Key::F1, "f1", 122, 0x70, 0xffbe),
+    (Key::F2, "f2", 120, 0x71, 0xffbf),
+    (Key::F3, "f3", 99, 0x72, 0xffc0),
+    (Key::F4, "f4", 118, 0x73, 0xffc1),
+    (Key::F5, "f5", 96, 0x74, 0xffc2),
+    (Key::F6, "f6", 97, 0x75, 0xffc3),
+    (Key::F7, "f7", 98, 0x76, 0xffc4),
+    (Key::F8, "f8", 100, 0x77, 0xffc5),
+    (Key::F9, "f9", 101, 0x78, 0xffc6),
+    (Key::F10, "f10", 109, 0x79, 0xffc7),
+    (Key::F11, "f11", 103, 0x7A, 0xffc8),
+    (Key::F12, "f12", 111, 0x7B, 0xffc9),
+    (Key::Return, "return", 36, 0x0D, 0xff0d),
+    (Key::Tab, "tab", 48, 0x09, 0xff09),
+    (Key::Space, "space", 49, 0x20, 0x20),
+    (Key::Backspace, "backspace", 51, 0x08, 0xff08),
+    (Key::ForwardDelete, "forward_delete", 117, 0x2E, 0xffff),
+    (Key::Escape, "escape", 53, 0x1B, 0xff1b),
+    (Key::Insert, "insert", 114, 0x2D, 0xff63),
+    (Key::Home, "home", 115, 0x24, 0xff50),
+    (Key::End, "end", 119, 0x23, 0xff57),
+    (Key::PageUp, "page_up", 116, 0x21, 0xff55),
+    (Key::PageDown, "page_down", 121, 0x22, 0xff56),
+    (Key::Left, "left", 123, 0x25, 0xff51),
+    (Key::Right, "right", 124, 0x27, 0xff53),
+    (Key::Up, "up", 126, 0x26, 0xff52),
+    (Key::Down, "down", 125, 0x28, 0xff54),
+    (Key::Minus, "minus", 27, 0xBD, 0x2d),
+    (Key::Equal, "equal", 24, 0xBB, 0x3d),
+    (Key::LeftBracket, "left_bracket", 33, 0xDB, 0x5b),
+    (Key::RightBracket, "right_bracket", 30, 0xDD, 0x5d),
+    (Key::Backslash, "backslash", 42, 0xDC, 0x5c),
+    (Key::Semicolon, "semicolon", 41, 0xBA, 0x3b),
+    (Key::Quote, "quote", 39, 0xDE, 0x27),
+    (Key::Comma, "comma", 43, 0xBC, 0x2c),
+    (Key::Period, "period", 47, 0xBE, 0x2e),
+    (Key::Slash, "slash", 44, 0xBF, 0x2f),
+    (Key::Grave, "grave", 50, 0xC0, 0x60),
+    (Key::CapsLock, "caps_lock", 57, 0x14, 0xffe5),
+    (Key::Shift, "shift", 56, 0x10, 0xffe1),
+    (Key::Control, "control", 59, 0x11, 0xffe3),
+    (Key::Alt, "alt", 58, 0x12, 0xffe9),
+    (Key::Meta, "meta", 55, 0x5B, 0xffeb),
+];
+
+/// Right-hand and left/right-specific codes that read as the same key
+#[rustfmt::skip]
+const MAC_ALIASES: &[(u16, Key)] = &[
+    (54, Key::Meta), (60, Key::Shift), (61, Key::Alt), (62, Key::Control),
+];
+
+#[rustfmt::skip]
+const VK_ALIASES: &[(u16, Key)] = &[
+    (0x5C, Key::Meta),
+    (0xA0, Key::Shift), (0xA1, Key::Shift),
+    (0xA2, Key::Control), (0xA3, Key::Control),
+    (0xA4, Key::Alt), (0xA5, Key::Alt),
+];
+
+#[rustfmt::skip]
+const X11_ALIASES: &[(u32, Key)] = &[
+    (0xffe2, Key::Shift), (0xffe4, Key::Control), (0xffea, Key::Alt),
+    (0xffe7, Key::Meta), (0xffe8, Key::Meta), (0xffec, Key::Meta),
+    (0xff8d, Key::Return),
+];
+
+/// Other names accepted by `Key::parse`, compared after normalizing
+#[rustfmt::skip]
+const NAME_ALIASES: &[(&str, Key)] = &[
+    ("enter", Key::Return), ("esc", Key::Escape),
+    ("delete", Key::Backspace), ("del", Key::ForwardDelete), ("ins", Key::Insert),
+    ("pgup", Key::PageUp), ("pgdn", Key::PageDown),
+    ("arrowup", Key::Up), ("arrowdown", Key::Down),
+    ("arrowleft", Key::Left), ("arrowright", Key::Right),
+    ("spacebar", Key::Space), ("capslock", Key::CapsLock),
+    ("ctrl", Key::Control), ("option", Key::Alt), ("opt", Key::Alt), ("menu", Key::Alt),
+    ("cmd", Key::Meta), ("command", Key::Meta), ("super", Key::Meta), ("win", Key::Meta),
+    ("backtick", Key::Grave), ("apostrophe", Key::Quote), ("dot", Key::Period),
+];
+
+/// Characters a key types on a US layout: key, unshifted, shifted
+#[rustfmt::skip]
+const CHARS: &[(Key, char, char)] = &[
+    (Key::Num1, '1', '!'), (Key::Num2, '2', '@'), (Key::Num3, '3', '#'),
+    (Key::Num4, '4', '$'), (Key::Num5, '5', '%'), (Key::Num6, '6', '^'),
+    (Key::Num7, '7', '&'), (Key::Num8, '8', '*'), (Key::Num9, '9', '('),
+    (Key::Num0, '0', ')'),
+    (Key::Minus, '-', '_'), (Key::Equal, '=', '+'),
+    (Key::LeftBracket, '[', '{'), (Key::RightBracket, ']', '}'),
+    (Key::Backslash, '\\', '|'), (Key::Semicolon, ';', ':'),
+    (Key::Quote, '\'', '"'), (Key::Comma, ',', '<'), (Key::Period, '.', '>'),
+    (Key::Slash, '/', '?'), (Key::Grave, '`', '~'),
+    (Key::Space, ' ', ' '), (Key::Return, '\n', '\n'), (Key::Tab, '\t', '\t'),
+    (Key::Backspace, '\x08', '\x08'),
+];
+
+fn row(key: Key) -> &'static (Key, &'static str, u16, u16, u32) {
+    KEYS.iter()
+        .find(|r| r.0 == key)
+        .expect("every key has a row in KEYS")
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl Key {
+    /// Parse a key name (`escape`, `PageUp`, `f5`, `cmd`) or a single
+    /// character (`t`, `/`, `1`)
+    pub fn parse(name: &str) -> Result<Key> {
+        let name = name.trim();
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if let Some((key, _)) = Key::from_char(c.to_ascii_lowercase()) {
+                return Ok(key);
+            }
+        }
+        let wanted = normalize(name);
+        KEYS.iter()
+            .find(|r| normalize(r.1) == wanted)
+            .map(|r| r.0)
+            .or_else(|| NAME_ALIASES.iter().find(|(a, _)| *a == wanted).map(|(_, k)| *k))
+            .ok_or_else(|| unknown_key(name))
+    }
+
+    /// Canonical lowercase name, what `parse` and serde round-trip through
+    pub fn name(self) -> &'static str {
+        row(self).1
+    }
+
+    pub fn to_mac(self) -> u16 {
+        row(self).2
+    }
+
+    pub fn to_vk(self) -> u16 {
+        row(self).3
+    }
+
+    pub fn to_x11_keysym(self) -> u32 {
+        row(self).4
+    }
+
+    pub fn from_mac(code: u16) -> Option<Key> {
+        KEYS.iter()
+            .find(|r| r.2 == code)
+            .map(|r| r.0)
+            .or_else(|| MAC_ALIASES.iter().find(|(c, _)| *c == code).map(|(_, k)| *k))
+    }
+
+    pub fn from_vk(code: u16) -> Option<Key> {
+        KEYS.iter()
+            .find(|r| r.3 == code)
+            .map(|r| r.0)
+            .or_else(|| VK_ALIASES.iter().find(|(c, _)| *c == code).map(|(_, k)| *k))
+    }
+
+    /// Upper-case letter keysyms map to the same key as lower-case ones
+    pub fn from_x11_keysym(keysym: u32) -> Option<Key> {
+        let keysym = match keysym {
+            0x41..=0x5a => keysym + 0x20,
+            _ => keysym,
+        };
+        KEYS.iter()
+            .find(|r| r.4 == keysym)
+            .map(|r| r.0)
+            .or_else(|| X11_ALIASES.iter().find(|(c, _)| *c == keysym).map(|(_, k)| *k))
+    }
+
+    /// Character the key types on a US layout
+    pub fn to_char(self, shift: bool) -> Option<char> {
+        let name = self.name();
+        if name.len() == 1 && name.as_bytes()[0].is_ascii_lowercase() {
+            let c = name.as_bytes()[0] as char;
+            return Some(if shift { c.to_ascii_uppercase() } else { c });
+        }
+        CHARS
+            .iter()
+            .find(|(k, _, _)| *k == self)
+            .map(|&(_, plain, shifted)| if shift { shifted } else { plain })
+    }
+
+    /// Visible character the key types, for keys that type one
+    ///
+    /// Whitespace and editing keys (space, return, tab, backspace) are not
+    /// characters here.
+    pub fn glyph(self) -> Option<char> {
+        self.to_char(false).filter(|c| c.is_ascii_graphic())
+    }
+
+    /// Key and shift state that type `c` on a US layout
+    pub fn from_char(c: char) -> Option<(Key, bool)> {
+        if c.is_ascii_alphabetic() {
+            let lower = c.to_ascii_lowercase();
+            let key = KEYS.iter().find(|r| r.1.len() == 1 && r.1.starts_with(lower))?.0;
+            return Some((key, c.is_ascii_uppercase()));
+        }
+        CHARS.iter().find_map(|&(key, plain, shifted)| {
+            if c == plain {
+                Some((key, false))
+            } else if c == shifted {
+                Some((key, true))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Editing key a control character in typed text stands for
+    ///
+    /// Apps ignore these when they arrive as Unicode input, so text entry
+    /// presses the key instead.
+    pub fn from_control_char(c: char) -> Option<Key> {
+        match c {
+            '\n' | '\r' => Some(Key::Return),
+            '\t' => Some(Key::Tab),
+            '\x08' => Some(Key::Backspace),
+            _ => None,
+        }
+    }
+
+    pub fn is_modifier(self) -> bool {
+        Modifier::from_key(self).is_some()
+    }
+}
+
+fn unknown_key(name: &str) -> Error {
+    Error::new(ErrorCode::SelectorInvalid, format!("Unknown key: '{}'", name)).with_suggestions(vec![
+        "Use a character (a, 1, /) or a name such as return, escape, tab, space, backspace, up, page_down, f5".to_string(),
+    ])
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Key::parse(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Modifier keys, in the order chords print them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Modifier {
+    Control,
+    Alt,
+    Shift,
+    /// Command on macOS, the Windows key, Super on X11
+    Meta,
+}
+
+impl Modifier {
+    pub fn from_key(key: Key) -> Option<Modifier> {
+        match key {
+            Key::Control => Some(Modifier::Control),
+            Key::Alt => Some(Modifier::Alt),
+            Key::Shift => Some(Modifier::Shift),
+            Key::Meta => Some(Modifier::Meta),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> Key {
+        match self {
+            Modifier::Control => Key::Control,
+            Modifier::Alt => Key::Alt,
+            Modifier::Shift => Key::Shift,
+            Modifier::Meta => Key::Meta,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::Control => "ctrl",
+            Modifier::Alt => "alt",
+            Modifier::Shift => "shift",
+            Modifier::Meta => "cmd",
+        }
+    }
+}
+
+/// A key pressed while holding modifiers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    /// Sorted and without duplicates
+    pub modifiers: Vec<Modifier>,
+    pub key: Key,
+}
+
+impl Chord {
+    pub fn new(key: Key) -> Self {
+        Self {
+            modifiers: Vec::new(),
+            key,
+        }
+    }
+
+    pub fn with(mut self, modifier: Modifier) -> Self {
+        if !self.modifiers.contains(&modifier) {
+            self.modifiers.push(modifier);
+            self.modifiers.sort();
+        }
+        self
+    }
+
+    /// Parse `ctrl+shift+t`; a lone modifier (`shift`) is a plain key press
+    pub fn parse(s: &str) -> Result<Chord> {
+        let parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        // `cmd++` and `+` name the key that types '+'
+        let (mods, key) = match parts.as_slice() {
+            [rest @ .., "", ""] => (rest, Key::Equal),
+            [rest @ .., last] if !last.is_empty() => (rest, Key::parse(last)?),
+            _ => return Err(invalid_chord(s)),
+        };
+        let mut chord = Chord::new(key);
+        for part in mods {
+            let modifier = Key::parse(part)
+                .ok()
+                .and_then(Modifier::from_key)
+                .ok_or_else(|| invalid_chord(s))?;
+            chord = chord.with(modifier);
+        }
+        if parts.as_slice().ends_with(&["", ""]) {
+            chord = chord.with(Modifier::Shift);
+        }
+        Ok(chord)
+    }
+
+    /// Build from a key and separate modifier names, as `bb shortcut` takes them
+    pub fn from_parts(key: &str, modifiers: &[&str]) -> Result<Chord> {
+        let mut spec: Vec<&str> = modifiers.iter().map(|m| m.trim()).filter(|m| !m.is_empty()).collect();
+        spec.push(key);
+        Chord::parse(&spec.join("+"))
+    }
+
+    pub fn has(&self, modifier: Modifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+}
+
+fn invalid_chord(s: &str) -> Error {
+    Error::new(ErrorCode::SelectorInvalid, format!("Invalid key chord: '{}'", s)).with_suggestions(vec![
+        "Join modifiers (ctrl, alt/option, shift, cmd/win/super) and one key with '+', e.g. ctrl+shift+t".to_string(),
+    ])
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier.name())?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl Serialize for Chord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Chord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Chord::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_names_and_aliases() {
+        assert_eq!(Key::parse("Escape").unwrap(), Key::Escape);
+        assert_eq!(Key::parse("esc").unwrap(), Key::Escape);
+        assert_eq!(Key::parse("PageUp").unwrap(), Key::PageUp);
+        assert_eq!(Key::parse("page_down").unwrap(), Key::PageDown);
+        assert_eq!(Key::parse("arrow_up").unwrap(), Key::Up);
+        assert_eq!(Key::parse("T").unwrap(), Key::T);
+        assert_eq!(Key::parse("/").unwrap(), Key::Slash);
+        assert_eq!(Key::parse("F12").unwrap(), Key::F12);
+        assert_eq!(Key::parse("delete").unwrap(), Key::Backspace);
+        assert_eq!(Key::parse("menu").unwrap(), Key::Alt);
+        assert!(Key::parse("hyper").is_err());
+    }
+
+    #[test]
+    fn every_key_round_trips() {
+        for &(key, name, mac, vk, keysym) in KEYS {
+            assert_eq!(Key::parse(name).unwrap(), key, "{}", name);
+            assert_eq!(Key::from_mac(mac), Some(key), "mac {}", mac);
+            assert_eq!(Key::from_vk(vk), Some(key), "vk {:#x}", vk);
+            assert_eq!(Key::from_x11_keysym(keysym), Some(key), "keysym {:#x}", keysym);
+        }
+    }
+
+    #[test]
+    fn side_specific_codes() {
+        assert_eq!(Key::from_mac(54), Some(Key::Meta));
+        assert_eq!(Key::from_vk(0xA3), Some(Key::Control));
+        assert_eq!(Key::from_x11_keysym(0x54), Some(Key::T));
+        assert_eq!(Key::from_mac(200), None);
+    }
+
+    #[test]
+    fn chars() {
+        assert_eq!(Key::from_char('T'), Some((Key::T, true)));
+        assert_eq!(Key::from_char('?'), Some((Key::Slash, true)));
+        assert_eq!(Key::from_char('5'), Some((Key::Num5, false)));
+        assert_eq!(Key::from_char('é'), None);
+        assert_eq!(Key::from_control_char('\r'), Some(Key::Return));
+        assert_eq!(Key::from_control_char('\x08'), Some(Key::Backspace));
+        assert_eq!(Key::from_control_char('\x07'), None);
+        assert_eq!(Key::Num6.to_char(true), Some('^'));
+        assert_eq!(Key::Q.to_char(false), Some('q'));
+        assert_eq!(Key::Escape.to_char(false), None);
+        assert_eq!(Key::A.glyph(), Some('a'));
+        assert_eq!(Key::Backslash.glyph(), Some('\\'));
+        assert_eq!(Key::Space.glyph(), None);
+        assert_eq!(Key::Return.glyph(), None);
+    }
+
+    #[test]
+    fn parse_chords() {
+        let chord = Chord::parse("ctrl+shift+t").unwrap();
+        assert_eq!(chord.modifiers, vec![Modifier::Control, Modifier::Shift]);
+        assert_eq!(chord.key, Key::T);
+
+        let chord = Chord::parse("cmd+alt+Escape").unwrap();
+        assert_eq!(chord.to_string(), "alt+cmd+escape");
+        assert_eq!(Chord::parse(&chord.to_string()).unwrap(), chord);
+
+        assert_eq!(Chord::parse("shift").unwrap(), Chord::new(Key::Shift));
+        assert_eq!(Chord::parse("cmd++").unwrap().to_string(), "shift+cmd+equal");
+        assert_eq!(
+            Chord::from_parts("c", &["command", "shift"]).unwrap(),
+            Chord::parse("shift+cmd+c").unwrap()
+        );
+        assert!(Chord::parse("ctrl+").is_err());
+        assert!(Chord::parse("t+ctrl+x").is_err());
+        assert!(Chord::parse("").is_err());
+    }
+
+    #[test]
+    fn serde_uses_names() {
+        let json = serde_json::to_string(&Chord::parse("ctrl+page_up").unwrap()).unwrap();
+        assert_eq!(json, "\"ctrl+page_up\"");
+        let key: Key = serde_json::from_str("\"Enter\"").unwrap();
+        assert_eq!(key, Key::Return);
+    }
+}
@@ -0,0 +1,404 @@
+//! Launching apps and ending them
+//!
+//! `App` itself is per platform, since finding the launched app and its
+//! windows goes through the platform's own registry of running apps. This
+//! module holds what they share: launch targets and options, the exit
+//! report, and the process side of quitting, which escalates from a polite
+//! quit request to SIGTERM to SIGKILL.
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::window::WindowInfo;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::time::Duration;
+
+/// What a launch string refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchTarget {
+    /// Reverse-DNS app id such as `com.apple.Safari` or `org.gnome.Calculator`
+    AppId(String),
+    /// An app bundle (`.app`) or desktop entry (`.desktop`)
+    AppPath(String),
+    /// Display name such as `Safari`; a command name on Linux
+    AppName(String),
+    /// Path to an executable, run as is
+    Command(String),
+}
+
+impl LaunchTarget {
+    pub fn parse(target: &str) -> Self {
+        let target = target.trim();
+        let bare = target.trim_end_matches('/');
+        if bare.ends_with(".app") || bare.ends_with(".desktop") {
+            LaunchTarget::AppPath(bare.to_string())
+        } else if target.contains('/') {
+            LaunchTarget::Command(target.to_string())
+        } else if is_app_id(target) {
+            LaunchTarget::AppId(target.to_string())
+        } else {
+            LaunchTarget::AppName(target.to_string())
+        }
+    }
+}
+
+/// `com.example.App`: three or more dot-separated identifier parts
+fn is_app_id(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('.').collect();
+    parts.len() >= 3
+        && parts[0].starts_with(|c: char| c.is_ascii_alphabetic())
+        && parts.iter().all(|p| {
+            !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    /// Return only once the app shows its first window
+    pub wait_for_window: bool,
+    /// How long to wait for the app, and its first window, to appear
+    pub timeout_ms: u64,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            args: Vec::new(),
+            env: Vec::new(),
+            wait_for_window: true,
+            timeout_ms: 15_000,
+        }
+    }
+}
+
+impl LaunchOptions {
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn wait_for_window(mut self, wait: bool) -> Self {
+        self.wait_for_window = wait;
+        self
+    }
+
+    pub fn timeout(mut self, ms: u64) -> Self {
+        self.timeout_ms = ms;
+        self
+    }
+}
+
+/// Parse `KEY=VALUE`, as given to `bb launch --env`
+pub fn parse_env_pair(pair: &str) -> Result<(String, String)> {
+    match pair.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(Error::new(
+            ErrorCode::Unknown,
+            format!("Invalid environment variable '{}', expected KEY=VALUE", pair),
+        )),
+    }
+}
+
+/// The names a running app answers to
+#[derive(Debug, Clone, Default)]
+pub struct AppNames {
+    pub name: Option<String>,
+    pub bundle_id: Option<String>,
+    /// File name of the executable, what `pgrep` would see
+    pub executable: Option<String>,
+}
+
+/// Index of the running app `wanted` refers to
+///
+/// Display name first, then bundle id, then executable name, each exact;
+/// then the display name ignoring case.
+pub fn pick_app(apps: &[AppNames], wanted: &str) -> Option<usize> {
+    let exact = |field: fn(&AppNames) -> &Option<String>| {
+        apps.iter().position(|a| field(a).as_deref() == Some(wanted))
+    };
+    exact(|a| &a.name)
+        .or_else(|| exact(|a| &a.bundle_id))
+        .or_else(|| exact(|a| &a.executable))
+        .or_else(|| {
+            apps.iter()
+                .position(|a| a.name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(wanted)))
+        })
+}
+
+/// Snapshot of a launched or attached app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppSnapshot {
+    pub pid: i32,
+    pub name: String,
+    pub windows: Vec<WindowInfo>,
+}
+
+/// What ended the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndedBy {
+    /// It exited on its own, or was already gone
+    Exited,
+    /// It honoured the quit request
+    Quit,
+    /// SIGTERM after the quit request went unanswered
+    Terminated,
+    /// SIGKILL
+    Killed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppExit {
+    pub pid: i32,
+    pub ended_by: EndedBy,
+    /// Exit code, known only for processes this handle started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+}
+
+/// How long SIGKILL gets before the process counts as stuck
+#[cfg(unix)]
+const KILL_WAIT: Duration = Duration::from_secs(2);
+
+#[cfg(unix)]
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// A process an `App` handle owns (spawned) or follows (adopted)
+///
+/// Spawned children are reaped here, so their exit code is known; adopted
+/// processes are only watched through `kill(pid, 0)`.
+#[cfg(unix)]
+pub(crate) struct Process {
+    pid: i32,
+    child: Option<std::process::Child>,
+}
+
+#[cfg(unix)]
+type Status = (Option<i32>, Option<i32>);
+
+#[cfg(unix)]
+impl Process {
+    pub(crate) fn spawned(child: std::process::Child) -> Self {
+        Self {
+            pid: child.id() as i32,
+            child: Some(child),
+        }
+    }
+
+    pub(crate) fn adopted(pid: i32) -> Self {
+        Self { pid, child: None }
+    }
+
+    pub(crate) fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// Exit code and signal once the process has ended, `None` while it runs
+    pub(crate) fn poll(&mut self) -> Option<Status> {
+        use std::os::unix::process::ExitStatusExt;
+
+        match &mut self.child {
+            Some(child) => match child.try_wait() {
+                Ok(Some(status)) => Some((status.code(), status.signal())),
+                Ok(None) => None,
+                Err(_) => Some((None, None)),
+            },
+            None => {
+                let alive = unsafe { libc::kill(self.pid, 0) } == 0
+                    || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+                (!alive).then_some((None, None))
+            }
+        }
+    }
+
+    pub(crate) fn is_running(&mut self) -> bool {
+        self.poll().is_none()
+    }
+
+    /// Poll until the process ends; `None` if it outlives `timeout`
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) -> Option<Status> {
+        let start = std::time::Instant::now();
+        loop {
+            if let Some(status) = self.poll() {
+                return Some(status);
+            }
+            if timeout.is_some_and(|t| start.elapsed() >= t) {
+                return None;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    pub(crate) fn wait_exit(&mut self, timeout_ms: Option<u64>) -> Result<AppExit> {
+        match self.wait(timeout_ms.map(Duration::from_millis)) {
+            Some(status) => Ok(self.exit(EndedBy::Exited, status)),
+            None => Err(Error::new(
+                ErrorCode::Timeout,
+                format!(
+                    "Timeout after {}ms waiting for process {} to exit",
+                    timeout_ms.unwrap_or_default(),
+                    self.pid
+                ),
+            )),
+        }
+    }
+
+    /// Ask nicely, then SIGTERM, then SIGKILL, giving each step `grace`
+    ///
+    /// `request_quit` sends the platform's quit request and returns false
+    /// when there was nothing to send it to; escalation then starts at once.
+    pub(crate) fn end(&mut self, request_quit: impl FnOnce() -> bool, grace: Duration) -> Result<AppExit> {
+        if let Some(status) = self.poll() {
+            return Ok(self.exit(EndedBy::Exited, status));
+        }
+        if request_quit() {
+            if let Some(status) = self.wait(Some(grace)) {
+                return Ok(self.exit(EndedBy::Quit, status));
+            }
+        }
+        if self.signal(libc::SIGTERM) {
+            if let Some(status) = self.wait(Some(grace)) {
+                return Ok(self.exit(EndedBy::Terminated, status));
+            }
+        }
+        self.kill()
+    }
+
+    pub(crate) fn kill(&mut self) -> Result<AppExit> {
+        if let Some(status) = self.poll() {
+            return Ok(self.exit(EndedBy::Exited, status));
+        }
+        self.signal(libc::SIGKILL);
+        match self.wait(Some(KILL_WAIT)) {
+            Some(status) => Ok(self.exit(EndedBy::Killed, status)),
+            None => Err(Error::action_failed(
+                "kill",
+                &format!("process {} survived SIGKILL", self.pid),
+            )),
+        }
+    }
+
+    fn signal(&self, signal: i32) -> bool {
+        unsafe { libc::kill(self.pid, signal) == 0 }
+    }
+
+    fn exit(&self, ended_by: EndedBy, (code, signal): Status) -> AppExit {
+        AppExit {
+            pid: self.pid,
+            ended_by,
+            code,
+            signal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_targets() {
+        assert_eq!(
+            LaunchTarget::parse("com.apple.Safari"),
+            LaunchTarget::AppId("com.apple.Safari".into())
+        );
+        assert_eq!(
+            LaunchTarget::parse("/Applications/Safari.app/"),
+            LaunchTarget::AppPath("/Applications/Safari.app".into())
+        );
+        assert_eq!(
+            LaunchTarget::parse("/usr/bin/xterm"),
+            LaunchTarget::Command("/usr/bin/xterm".into())
+        );
+        assert_eq!(LaunchTarget::parse("Safari"), LaunchTarget::AppName("Safari".into()));
+        assert_eq!(LaunchTarget::parse("Notes 2.0"), LaunchTarget::AppName("Notes 2.0".into()));
+    }
+
+    #[test]
+    fn pick_app_by_any_name() {
+        let app = |name: &str, bundle_id: &str, executable: &str| AppNames {
+            name: Some(name.into()),
+            bundle_id: Some(bundle_id.into()),
+            executable: Some(executable.into()),
+        };
+        let apps = [
+            app("Code", "com.microsoft.VSCode", "Electron"),
+            app("Visual Studio Code", "com.microsoft.VSCode2", "Code"),
+        ];
+        assert_eq!(pick_app(&apps, "Code"), Some(0));
+        assert_eq!(pick_app(&apps, "com.microsoft.VSCode2"), Some(1));
+        assert_eq!(pick_app(&apps, "Electron"), Some(0));
+        assert_eq!(pick_app(&apps, "visual studio code"), Some(1));
+        assert_eq!(pick_app(&apps, "Xcode"), None);
+    }
+
+    #[test]
+    fn env_pairs() {
+        assert_eq!(parse_env_pair("LANG=C=x").unwrap(), ("LANG".into(), "C=x".into()));
+        assert!(parse_env_pair("LANG").is_err());
+        assert!(parse_env_pair("=C").is_err());
+    }
+
+    #[cfg(unix)]
+    fn spawn(script: &str) -> Process {
+        Process::spawned(std::process::Command::new("sh").arg("-c").arg(script).spawn().unwrap())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escalates_to_sigterm() {
+        let mut process = spawn("exec sleep 30");
+        let exit = process.end(|| false, Duration::from_millis(200)).unwrap();
+        assert_eq!(exit.ended_by, EndedBy::Terminated);
+        assert_eq!(exit.signal, Some(libc::SIGTERM));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escalates_to_sigkill() {
+        use std::io::BufRead;
+
+        // Read a line first so SIGTERM cannot arrive before the trap is set
+        let mut child = std::process::Command::new("sh")
+            .arg("-c")
+            .arg("trap '' TERM; echo ready; exec sleep 30")
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        std::io::BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        let mut process = Process::spawned(child);
+        let exit = process.end(|| false, Duration::from_millis(200)).unwrap();
+        assert_eq!(exit.ended_by, EndedBy::Killed);
+        assert_eq!(exit.signal, Some(libc::SIGKILL));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wait_exit_reports_code() {
+        let mut process = spawn("exit 3");
+        let exit = process.wait_exit(Some(5_000)).unwrap();
+        assert_eq!((exit.ended_by, exit.code), (EndedBy::Exited, Some(3)));
+
+        let mut process = spawn("exec sleep 30");
+        assert_eq!(process.wait_exit(Some(50)).unwrap_err().code, ErrorCode::Timeout);
+        process.kill().unwrap();
+    }
+}
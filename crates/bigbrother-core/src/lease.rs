@@ -0,0 +1,320 @@
+//! Exclusive use of keyboard and mouse across processes
+//!
+//! Agents sharing a desktop take turns: input actions hold a lease, an
+//! advisory lock on a file in the temp directory, so one agent's typing
+//! never lands in the middle of another's clicks. The lock goes away with
+//! the process holding it, so a crashed agent can't wedge the others. Next
+//! to the lock a small note says who holds it and since when; that is what
+//! a waiting agent reports when it gives up.
+//!
+//! Leases nest per thread: an action taken on a thread that already holds
+//! the lease joins it instead of waiting on itself. Other threads of the
+//! same process wait their turn like other agents do, so a background
+//! thread such as the dialog watcher never clicks into the middle of the
+//! main thread's typing.
+
+use crate::error::{Error, ErrorCode, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long an action waits for another agent to let go of the input
+pub const LEASE_TIMEOUT_MS: u64 = 10_000;
+
+const LEASE_POLL_MS: u64 = 25;
+
+const LEASE_FILE: &str = "bigbrother-input.lock";
+
+/// Who holds the lease
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaseHolder {
+    pub pid: u32,
+    pub name: String,
+    /// When the lease was taken, in ms since the Unix epoch
+    pub since_ms: u64,
+}
+
+impl LeaseHolder {
+    /// How long the lease has been held
+    pub fn held_ms(&self) -> u64 {
+        now_ms().saturating_sub(self.since_ms)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaseOptions {
+    /// How long to wait for the current holder
+    pub timeout: Duration,
+    /// What other agents are told holds the lease
+    pub name: String,
+    /// Lock file; agents only exclude each other when they share it
+    pub path: PathBuf,
+}
+
+impl Default for LeaseOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(LEASE_TIMEOUT_MS),
+            name: program_name(),
+            path: std::env::temp_dir().join(LEASE_FILE),
+        }
+    }
+}
+
+/// The input lease, held until dropped
+#[must_use = "the lease is released when dropped"]
+#[derive(Debug)]
+pub struct InputLease {
+    path: PathBuf,
+    /// Released on the thread that took it
+    _thread_bound: PhantomData<*const ()>,
+}
+
+/// A lease this process holds, the thread holding it, and how many
+/// `InputLease`s share it
+struct Held {
+    path: PathBuf,
+    owner: ThreadId,
+    _file: File,
+    depth: usize,
+}
+
+static HELD: Mutex<Vec<Held>> = Mutex::new(Vec::new());
+
+impl InputLease {
+    /// Take the lease, waiting up to `options.timeout` for another holder
+    ///
+    /// Fails with `InputBusy` naming the holder when the wait runs out.
+    pub fn acquire(options: &LeaseOptions) -> Result<Self> {
+        let me = thread::current().id();
+        if let Some(held) = HELD
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|h| h.path == options.path && h.owner == me)
+        {
+            held.depth += 1;
+            return Ok(Self {
+                path: options.path.clone(),
+                _thread_bound: PhantomData,
+            });
+        }
+
+        // Each acquire opens the file anew, and locks on separate opens
+        // exclude each other even within a process, so another thread's
+        // lease is waited on here like another agent's
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .open(&options.path)
+            .map_err(|e| Error::action_failed("input lease", &format!("{}: {}", options.path.display(), e)))?;
+        let start = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(e)) => return Err(Error::action_failed("input lease", &e.to_string())),
+            }
+            if start.elapsed() >= options.timeout {
+                return Err(busy(read_note(&options.path), start.elapsed()));
+            }
+            thread::sleep(Duration::from_millis(LEASE_POLL_MS));
+        }
+
+        let holder = LeaseHolder {
+            pid: std::process::id(),
+            name: options.name.clone(),
+            since_ms: now_ms(),
+        };
+        // The lock is what excludes; a missing note only costs the error its details
+        let _ = std::fs::write(note_path(&options.path), serde_json::to_vec(&holder).unwrap_or_default());
+        HELD.lock().unwrap().push(Held {
+            path: options.path.clone(),
+            owner: me,
+            _file: file,
+            depth: 1,
+        });
+        Ok(Self {
+            path: options.path.clone(),
+            _thread_bound: PhantomData,
+        })
+    }
+
+    /// Join whichever lease this thread holds, else take `options`'s
+    ///
+    /// For actions that can't tell which lease their caller took, such as
+    /// element actions run inside `Desktop::hold_input`.
+    pub fn join_or_acquire(options: &LeaseOptions) -> Result<Self> {
+        let me = thread::current().id();
+        let held = HELD.lock().unwrap().iter().find(|h| h.owner == me).map(|h| h.path.clone());
+        match held {
+            Some(path) => Self::acquire(&LeaseOptions {
+                path,
+                ..options.clone()
+            }),
+            None => Self::acquire(options),
+        }
+    }
+
+    /// Who holds the lease right now, if anyone
+    pub fn holder(options: &LeaseOptions) -> Option<LeaseHolder> {
+        let file = File::open(&options.path).ok()?;
+        match file.try_lock_shared() {
+            Ok(()) => None,
+            Err(_) => read_note(&options.path),
+        }
+    }
+}
+
+impl Drop for InputLease {
+    fn drop(&mut self) {
+        let me = thread::current().id();
+        let mut held = HELD.lock().unwrap();
+        let Some(i) = held.iter().position(|h| h.path == self.path && h.owner == me) else {
+            return;
+        };
+        held[i].depth -= 1;
+        if held[i].depth == 0 {
+            let _ = std::fs::remove_file(note_path(&self.path));
+            // Closing the file releases the lock
+            held.swap_remove(i);
+        }
+    }
+}
+
+fn busy(holder: Option<LeaseHolder>, waited: Duration) -> Error {
+    let who = match &holder {
+        Some(h) => format!(
+            "{} (pid {}) for {:.1}s",
+            h.name,
+            h.pid,
+            h.held_ms() as f64 / 1000.0
+        ),
+        None => "another process".to_string(),
+    };
+    Error::new(
+        ErrorCode::InputBusy,
+        format!("Input is held by {}; gave up after {}ms", who, waited.as_millis()),
+    )
+    .with_suggestions(vec![
+        "Retry once the other agent is done, or wait longer with a larger lease timeout".to_string(),
+    ])
+    .with_context(serde_json::json!({
+        "holder": holder,
+        "waited_ms": waited.as_millis() as u64,
+    }))
+}
+
+fn note_path(lock: &Path) -> PathBuf {
+    lock.with_extension("holder")
+}
+
+fn read_note(lock: &Path) -> Option<LeaseHolder> {
+    serde_json::from_slice(&std::fs::read(note_path(lock)).ok()?).ok()
+}
+
+fn program_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "bigbrother".to_string())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(name: &str, timeout_ms: u64) -> LeaseOptions {
+        LeaseOptions {
+            timeout: Duration::from_millis(timeout_ms),
+            name: name.to_string(),
+            path: std::env::temp_dir().join(format!("bb-lease-test-{}-{}.lock", std::process::id(), name)),
+        }
+    }
+
+    #[test]
+    fn leases_nest_within_a_process() {
+        let opts = options("nest", 0);
+        let outer = InputLease::acquire(&opts).unwrap();
+        let inner = InputLease::acquire(&opts).unwrap();
+        drop(inner);
+        let holder = read_note(&opts.path).unwrap();
+        assert_eq!((holder.pid, holder.name.as_str()), (std::process::id(), "nest"));
+        drop(outer);
+        assert!(read_note(&opts.path).is_none());
+        assert!(HELD.lock().unwrap().iter().all(|h| h.path != opts.path));
+    }
+
+    #[test]
+    fn actions_join_the_lease_their_caller_holds() {
+        let held = options("join-held", 0);
+        let other = options("join-other", 0);
+        let outer = InputLease::acquire(&held).unwrap();
+        let joined = InputLease::join_or_acquire(&other).unwrap();
+        assert_eq!(joined.path, held.path);
+        assert!(HELD.lock().unwrap().iter().all(|h| h.path != other.path));
+        drop(joined);
+        drop(outer);
+        assert!(read_note(&held.path).is_none());
+    }
+
+    #[test]
+    fn other_threads_wait_their_turn() {
+        let opts = options("threads", 60);
+        let lease = InputLease::acquire(&opts).unwrap();
+        let waiting = opts.clone();
+        let err = thread::spawn(move || InputLease::acquire(&waiting).map(drop)).join().unwrap().unwrap_err();
+        assert_eq!(err.code, ErrorCode::InputBusy);
+
+        // Given long enough, the other thread gets it once this one lets go
+        let waiting = LeaseOptions {
+            timeout: Duration::from_secs(5),
+            ..opts.clone()
+        };
+        let other = thread::spawn(move || InputLease::acquire(&waiting).map(drop));
+        thread::sleep(Duration::from_millis(50));
+        drop(lease);
+        other.join().unwrap().unwrap();
+        let _ = std::fs::remove_file(&opts.path);
+    }
+
+    #[test]
+    fn busy_names_the_holder() {
+        let opts = options("busy", 60);
+        // Another agent: its own lock on the file, and its note
+        let other = OpenOptions::new().create(true).truncate(false).write(true).open(&opts.path).unwrap();
+        other.try_lock().unwrap();
+        let holder = LeaseHolder {
+            pid: 4242,
+            name: "agent-b".to_string(),
+            since_ms: now_ms() - 5_000,
+        };
+        std::fs::write(note_path(&opts.path), serde_json::to_vec(&holder).unwrap()).unwrap();
+
+        let err = InputLease::acquire(&opts).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InputBusy);
+        assert!(err.message.contains("agent-b (pid 4242)"), "{}", err.message);
+        let context = err.context.unwrap();
+        assert_eq!(context["holder"]["pid"], 4242);
+        assert!(context["waited_ms"].as_u64().unwrap() >= 60);
+
+        drop(other);
+        let lease = InputLease::acquire(&opts).unwrap();
+        assert_eq!(read_note(&opts.path).unwrap().name, "busy");
+        drop(lease);
+        let _ = std::fs::remove_file(&opts.path);
+    }
+}
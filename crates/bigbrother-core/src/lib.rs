@@ -9,8 +9,35 @@
 //! - **Windows**: Full support via UI Automation + SendInput
 //! - **Linux**: Coming soon (AT-SPI2)
 
+pub mod attributes;
+pub mod clipboard;
+pub mod dialog;
+pub mod diff;
 pub mod error;
+#[cfg(test)]
+mod fake;
+pub mod form;
+pub mod keys;
+pub mod launch;
+pub mod lease;
+pub mod marks;
+pub mod menu;
+pub mod mouse;
+pub mod name;
+pub mod node;
+pub mod observe;
+pub mod pacing;
 pub mod platform;
+pub mod pool;
+pub mod role;
+pub mod scroll;
+pub mod selector;
+pub mod table;
+pub mod text_entry;
+pub mod traversal;
+pub mod tree;
+pub mod verify;
+pub mod window;
 
 #[cfg(target_os = "macos")]
 pub mod accessibility;
@@ -24,8 +51,30 @@ pub mod element;
 pub mod input;
 #[cfg(target_os = "macos")]
 pub mod locator;
-#[cfg(target_os = "macos")]
-pub mod selector;
+
+// Cross-platform exports
+pub use attributes::AttributeRequest;
+pub use clipboard::{ClipboardChange, ClipboardContent, ClipboardFormat, ClipboardWatcher, Selection};
+pub use dialog::{DialogAction, DialogEvent, DialogRule, Intervention};
+pub use diff::TreeDiff;
+pub use keys::{Chord, Key, Modifier};
+pub use launch::{AppExit, AppNames, AppSnapshot, EndedBy, LaunchOptions, LaunchTarget};
+pub use lease::{InputLease, LeaseHolder, LeaseOptions};
+pub use marks::{Mark, SetOfMarks};
+pub use mouse::{MotionCurve, MotionProfile, MouseButton};
+pub use name::accessible_name;
+pub use node::AccessibleNode;
+pub use role::Role;
+pub use observe::{Observation, ObserveOptions};
+pub use pacing::{Pacer, PacingPolicy};
+pub use scroll::{Scroll, ScrollReport, ScrollUnit, ScrollUntilOptions};
+pub use selector::Selector;
+pub use table::{Table, TableCell, TableOptions, TableRow};
+pub use text_entry::{EntryStrategy, TextEntryOptions, TextEntryReport};
+pub use traversal::{TraversalPolicy, Truncation, TruncationReason};
+pub use tree::{Bounds, TreeNode, TreeResult};
+pub use verify::{Normalization, VerifiedTyping, VerifyOptions};
+pub use window::WindowInfo;
 
 // macOS exports
 #[cfg(target_os = "macos")]
@@ -36,15 +85,19 @@ pub use error::{Error, ErrorCode, Result};
 #[cfg(target_os = "macos")]
 pub use locator::Locator;
 #[cfg(target_os = "macos")]
-pub use selector::Selector;
+pub use platform::macos::{App, DialogWatcher, Window};
+
+// Linux exports
+#[cfg(target_os = "linux")]
+pub use platform::linux::{enter_text, scroll_at, type_text, App, Window};
 
 // Windows exports
 #[cfg(target_os = "windows")]
 pub use platform::windows::{
     Automation, Element, TreeWalker,
     find_window, get_windows,
-    move_mouse, click, click_at, double_click, right_click, middle_click,
-    scroll, press_key, key_down, key_up, type_text, shortcut, vk,
+    move_mouse, move_mouse_with, mouse_position, click, click_at, click_at_with, double_click, right_click, middle_click, drag,
+    scroll, scroll_at, press_key, press_chord, key_down, key_up, type_text, enter_text, shortcut, vk,
 };
 
 pub mod prelude {
@@ -55,15 +108,14 @@ pub mod prelude {
     pub use crate::error::{Error, ErrorCode, Result};
     #[cfg(target_os = "macos")]
     pub use crate::locator::Locator;
-    #[cfg(target_os = "macos")]
     pub use crate::selector::Selector;
 
     #[cfg(target_os = "windows")]
     pub use crate::platform::windows::{
         Automation, Element, TreeWalker,
         find_window, get_windows,
-        move_mouse, click, click_at, double_click, right_click, middle_click,
-        scroll, press_key, key_down, key_up, type_text, shortcut, vk,
+        move_mouse, move_mouse_with, mouse_position, click, click_at, click_at_with, double_click, right_click, middle_click, drag,
+        scroll, scroll_at, press_key, press_chord, key_down, key_up, type_text, enter_text, shortcut, vk,
     };
 }
 
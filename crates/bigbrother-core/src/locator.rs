@@ -1,9 +1,16 @@
 //! Locator - fluent API for finding and interacting with elements
 
 use crate::accessibility::*;
+use crate::apps;
+use crate::attributes::value_text;
 use crate::element::{ActionResult, UIElement};
 use crate::error::{Error, Result};
+use crate::lease::{InputLease, LeaseOptions};
+use crate::pool;
 use crate::selector::{Attribute, Selector};
+use crate::traversal::{merge_truncations, Traversal, TraversalPolicy, Truncation};
+use crate::tree::{element_id, identity_label, SiblingPaths};
+use crate::verify::{VerifiedTyping, VerifyOptions};
 use cidre::ax;
 use std::time::{Duration, Instant};
 
@@ -12,6 +19,10 @@ pub struct Locator {
     root: Option<UIElement>,
     timeout_ms: u64,
     max_depth: usize,
+    traversal: TraversalPolicy,
+    all_apps: bool,
+    workers: usize,
+    lease: LeaseOptions,
 }
 
 impl Locator {
@@ -21,6 +32,10 @@ impl Locator {
             root: None,
             timeout_ms: 5000,
             max_depth: 30,
+            traversal: TraversalPolicy::default(),
+            all_apps: false,
+            workers: pool::default_workers(),
+            lease: LeaseOptions::default(),
         }
     }
 
@@ -43,11 +58,49 @@ impl Locator {
         self
     }
 
+    pub fn traversal(mut self, policy: TraversalPolicy) -> Self {
+        self.traversal = policy;
+        self
+    }
+
+    /// Without a root, search every running app's tree in parallel
+    /// instead of walking the system-wide element
+    ///
+    /// Matches are merged in app order, so results and indices are deterministic.
+    /// The traversal deadline is shared; `max_nodes` applies to each app.
+    pub fn all_apps(mut self) -> Self {
+        self.all_apps = true;
+        self
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// How actions wait for the input lease, see `crate::lease`
+    pub fn input_lease(mut self, options: LeaseOptions) -> Self {
+        self.lease = options;
+        self
+    }
+
+    pub fn selector(&self) -> &Selector {
+        &self.selector
+    }
+
     pub fn find(&self) -> Result<UIElement> {
-        let elements = self.find_all()?;
+        let (elements, truncated) = self.search();
 
         if elements.is_empty() {
-            return Err(Error::element_not_found(&self.selector.to_string()));
+            let err = Error::element_not_found(&self.selector.to_string());
+            if truncated.is_empty() {
+                return Err(err);
+            }
+            return Err(err
+                .with_suggestions(vec![
+                    "The search was cut short; raise the traversal budget or scope to an app".to_string(),
+                ])
+                .with_context(serde_json::json!({ "truncated": truncated })));
         }
 
         if elements.len() > 1 {
@@ -65,17 +118,50 @@ impl Locator {
     }
 
     pub fn find_all(&self) -> Result<Vec<UIElement>> {
-        let root = match &self.root {
-            Some(r) => r.clone(),
-            None => {
-                // Get system-wide element as root
-                let sys = ax::UiElement::sys_wide();
-                UIElement::new(sys)
-            }
+        Ok(self.search().0)
+    }
+
+    /// Matches in tree order, plus whatever the traversal budget left out
+    fn search(&self) -> (Vec<UIElement>, Vec<Truncation>) {
+        let roots: Vec<UIElement> = match (&self.root, self.all_apps) {
+            (Some(r), _) => vec![r.clone()],
+            (None, true) => app_roots(),
+            // Get system-wide element as root
+            (None, false) => vec![UIElement::new(ax::UiElement::sys_wide())],
         };
 
+        let screens = if self.traversal.skip_offscreen {
+            crate::desktop::Desktop::screens()
+        } else {
+            Vec::new()
+        };
+        let started = Instant::now();
+        let (selector, policy, max_depth) = (&self.selector, &self.traversal, self.max_depth);
+
+        let per_root = pool::parallel_map(roots, self.workers, |root| {
+            let mut traversal = Traversal::new(policy, max_depth)
+                .with_start(started)
+                .with_screens(screens.clone());
+            let mut results = Vec::new();
+            let (parent_path, mut siblings) = root_position(&root);
+            find_recursive(
+                selector,
+                root.raw(),
+                parent_path.as_deref(),
+                &mut siblings,
+                0,
+                &mut traversal,
+                &mut results,
+            );
+            (results, traversal.finish())
+        });
+
         let mut results = Vec::new();
-        self.find_recursive(root.raw(), 0, &mut results);
+        let mut truncated = Vec::new();
+        for (found, cut) in per_root {
+            results.extend(found);
+            merge_truncations(&mut truncated, cut);
+        }
 
         // Add indices
         let results: Vec<UIElement> = results
@@ -84,45 +170,7 @@ impl Locator {
             .map(|(i, e)| e.with_index(i))
             .collect();
 
-        Ok(results)
-    }
-
-    fn find_recursive(&self, element: &ax::UiElement, depth: usize, results: &mut Vec<UIElement>) {
-        if depth > self.max_depth {
-            return;
-        }
-
-        if self.matches(element) {
-            results.push(UIElement::new(element.retained()));
-        }
-
-        for child in get_children(element) {
-            self.find_recursive(&child, depth + 1, results);
-        }
-    }
-
-    fn matches(&self, element: &ax::UiElement) -> bool {
-        let role = get_role(element);
-        let name = get_role_desc(element);
-        let title = get_title(element);
-        let value = get_value(element);
-        let desc = get_description(element);
-
-        for cond in &self.selector.conditions {
-            if cond.attr == Attribute::Index {
-                continue; // Index handled separately
-            }
-            if !cond.matches(
-                role.as_deref(),
-                name.as_deref(),
-                title.as_deref(),
-                value.as_deref(),
-                desc.as_deref(),
-            ) {
-                return false;
-            }
-        }
-        true
+        (results, truncated)
     }
 
     pub fn exists(&self) -> bool {
@@ -166,16 +214,171 @@ impl Locator {
         }
     }
 
-    // Actions - find then act
+    // Actions - find then act, under one input lease so no other agent
+    // gets in between
 
     pub fn click(&self) -> Result<ActionResult> {
+        let _lease = InputLease::join_or_acquire(&self.lease)?;
         self.find()?.click()
     }
 
     pub fn type_text(&self, text: &str) -> Result<ActionResult> {
+        let _lease = InputLease::join_or_acquire(&self.lease)?;
         let element = self.find()?;
         element.click()?;
         std::thread::sleep(Duration::from_millis(100));
         element.set_value(text)
     }
+
+    /// Like `type_text`, but reads the field back and retries on a mismatch
+    pub fn type_text_verified(&self, text: &str, options: &VerifyOptions) -> Result<VerifiedTyping> {
+        let _lease = InputLease::join_or_acquire(&self.lease)?;
+        let element = self.find()?;
+        element.click()?;
+        std::thread::sleep(Duration::from_millis(100));
+        element.type_verified(text, options)
+    }
+}
+
+/// Application roots of every running app, in `apps::running_app_pids` order
+fn app_roots() -> Vec<UIElement> {
+    apps::running_app_pids()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(_, pid)| UIElement::new(ax::UiElement::with_app_pid(pid)))
+        .collect()
+}
+
+/// Where a search root sits in its application's tree: the path of its
+/// parent, and its earlier siblings already counted
+///
+/// A search from a window then gives every element the id a walk from the
+/// application would, so `id:` selectors work whatever the root.
+fn root_position(root: &UIElement) -> (Option<String>, SiblingPaths) {
+    let mut chain = vec![root.raw().retained()];
+    while let Some(parent) = get_element_attr(chain.last().unwrap(), ax::attr::parent()) {
+        chain.push(parent);
+    }
+    chain.reverse();
+
+    let mut parent_path: Option<String> = None;
+    let mut siblings = SiblingPaths::new();
+    for pair in chain.windows(2) {
+        let (node, next) = (UIElement::new(pair[0].retained()), &pair[1]);
+        let role = node.role().unwrap_or_else(|| "Unknown".to_string());
+        let path = siblings.child(parent_path.as_deref(), &role, node.identity_label().as_deref());
+
+        siblings = SiblingPaths::new();
+        for child in node.children() {
+            if child.raw().equal(next) {
+                break;
+            }
+            let role = child.role().unwrap_or_else(|| "Unknown".to_string());
+            siblings.child(Some(&path), &role, child.identity_label().as_deref());
+        }
+        parent_path = Some(path);
+    }
+    (parent_path, siblings)
+}
+
+fn find_recursive(
+    selector: &Selector,
+    element: &ax::UiElement,
+    parent_path: Option<&str>,
+    siblings: &mut SiblingPaths,
+    depth: usize,
+    traversal: &mut Traversal,
+    results: &mut Vec<UIElement>,
+) {
+    if !traversal.visit() {
+        return;
+    }
+
+    // Skipped siblings still count, so a path doesn't depend on what is on screen
+    let node = UIElement::new(element.retained());
+    let role = node.role().unwrap_or_else(|| "Unknown".to_string());
+    let title = node.title();
+    let path = siblings.child(
+        parent_path,
+        &role,
+        identity_label(
+            title.as_deref(),
+            node.identifier().as_deref(),
+            node.description().as_deref(),
+        ),
+    );
+    if traversal.checks_offscreen() && traversal.is_offscreen(get_bounds(element).as_ref()) {
+        return;
+    }
+
+    let name = node.name();
+    let id = element_id(&path);
+
+    let found = Found {
+        id: &id,
+        role: &role,
+        name: name.as_deref(),
+        title: title.as_deref(),
+    };
+    if matches(selector, &node, &found) {
+        results.push(node.with_id(id));
+    }
+
+    let children = get_children(element);
+    let walk_children = traversal.children_to_walk(depth, &role, children.len());
+    let mut child_siblings = SiblingPaths::new();
+    for child in children.into_iter().take(walk_children) {
+        find_recursive(
+            selector,
+            &child,
+            Some(&path),
+            &mut child_siblings,
+            depth + 1,
+            traversal,
+            results,
+        );
+    }
+}
+
+/// Attributes already read during the walk, reused for matching
+struct Found<'a> {
+    id: &'a str,
+    role: &'a str,
+    name: Option<&'a str>,
+    title: Option<&'a str>,
+}
+
+fn matches(selector: &Selector, element: &UIElement, found: &Found) -> bool {
+    let needs = |attr: Attribute| selector.conditions.iter().any(|c| c.attr == attr);
+    let value = if needs(Attribute::Value) { element.value() } else { None };
+    let desc = if needs(Attribute::Description) { element.description() } else { None };
+
+    for cond in &selector.conditions {
+        if cond.attr == Attribute::Index {
+            continue; // Index handled separately
+        }
+        if cond.attr == Attribute::Id {
+            if !cond.matches_value(Some(found.id)) {
+                return false;
+            }
+            continue;
+        }
+        if let Attribute::Custom(name) = &cond.attr {
+            let text = element.attribute(name).map(|v| value_text(&v));
+            if !cond.matches_value(text.as_deref()) {
+                return false;
+            }
+            continue;
+        }
+        if !cond.matches(
+            Some(found.role),
+            found.name,
+            found.title,
+            value.as_deref(),
+            desc.as_deref(),
+        ) {
+            return false;
+        }
+    }
+    true
 }
@@ -0,0 +1,297 @@
+//! Set-of-marks: numbered boxes over the interactive elements on screen
+//!
+//! Vision models read a small number off a screenshot far more reliably than
+//! they produce coordinates. `SetOfMarks` lists the actionable elements that
+//! are visible, each with a short mark, and `SetOfMarks::draw` paints matching
+//! numbered boxes onto a PNG screenshot. Drawing is pure Rust so it also runs
+//! headless, e.g. on a Linux box post-processing captured screenshots.
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::observe::is_interactive;
+use crate::tree::{Bounds, TreeResult};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mark {
+    pub mark: usize,
+    pub id: String,
+    pub index: usize,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub bounds: Bounds,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetOfMarks {
+    pub app: String,
+    /// Screen area the marks were collected for, in points
+    pub screen: Bounds,
+    pub marks: Vec<Mark>,
+}
+
+impl TreeResult {
+    /// Interactive elements visible on `screen`, numbered from 1 in tree order
+    pub fn marks(&self, screen: &Bounds) -> SetOfMarks {
+        let marks = self
+            .nodes
+            .iter()
+            .filter(|n| is_interactive(&n.role))
+            .filter_map(|n| {
+                let bounds = n.bounds.filter(|b| !b.is_empty() && b.intersects(screen))?;
+                Some((n, bounds))
+            })
+            .enumerate()
+            .map(|(i, (n, bounds))| Mark {
+                mark: i + 1,
+                id: n.id.clone(),
+                index: n.index,
+                role: n.role.clone(),
+                label: n.label().map(String::from),
+                bounds,
+            })
+            .collect();
+
+        SetOfMarks {
+            app: self.app.clone(),
+            screen: *screen,
+            marks,
+        }
+    }
+}
+
+impl SetOfMarks {
+    /// Draw the numbered boxes onto a PNG screenshot of `screen`
+    ///
+    /// Points are mapped to pixels using the image width, so Retina captures
+    /// need no extra configuration.
+    pub fn draw(&self, png: &[u8]) -> Result<Vec<u8>> {
+        let mut canvas = Canvas::decode(png)?;
+        let scale = canvas.width as f64 / self.screen.width;
+        let thickness = (2.0 * scale).round().max(1.0) as i64;
+        let glyph = (2.0 * scale).round().max(2.0) as i64;
+
+        for mark in &self.marks {
+            let color = PALETTE[(mark.mark - 1) % PALETTE.len()];
+            let b = &mark.bounds;
+            let x0 = ((b.x - self.screen.x) * scale).round() as i64;
+            let y0 = ((b.y - self.screen.y) * scale).round() as i64;
+            let x1 = ((b.x + b.width - self.screen.x) * scale).round() as i64;
+            let y1 = ((b.y + b.height - self.screen.y) * scale).round() as i64;
+
+            canvas.stroke_rect(x0, y0, x1, y1, thickness, color);
+            canvas.draw_tag(x0, y0, &mark.mark.to_string(), glyph, color);
+        }
+
+        canvas.encode()
+    }
+}
+
+/// Box colors, cycled by mark number so neighbours are easy to tell apart
+const PALETTE: [[u8; 3]; 8] = [
+    [230, 25, 75],
+    [60, 180, 75],
+    [0, 130, 200],
+    [245, 130, 48],
+    [145, 30, 180],
+    [0, 160, 160],
+    [240, 50, 230],
+    [128, 128, 0],
+];
+
+/// 3x5 bitmap digits, one row per byte, high bit on the left
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// RGBA8 pixel buffer
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+fn png_error(e: impl std::fmt::Display) -> Error {
+    Error::new(ErrorCode::Unknown, format!("Invalid PNG: {}", e))
+}
+
+impl Canvas {
+    fn decode(png: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(png);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(png_error)?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).map_err(png_error)?;
+        let bytes = &buf[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => bytes.to_vec(),
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Grayscale => bytes.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            png::ColorType::Indexed => return Err(png_error("palette was not expanded")),
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(&self.pixels).map_err(png_error)?;
+        writer.finish().map_err(png_error)?;
+        Ok(out)
+    }
+
+    /// Fill `[x0, x1) x [y0, y1)`, clipped to the image
+    fn fill_rect(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, color: [u8; 3]) {
+        let (x0, x1) = (x0.max(0), x1.min(self.width as i64));
+        let (y0, y1) = (y0.max(0), y1.min(self.height as i64));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let i = (y as usize * self.width as usize + x as usize) * 4;
+                self.pixels[i..i + 3].copy_from_slice(&color);
+                self.pixels[i + 3] = 255;
+            }
+        }
+    }
+
+    fn stroke_rect(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, t: i64, color: [u8; 3]) {
+        self.fill_rect(x0, y0, x1, y0 + t, color);
+        self.fill_rect(x0, y1 - t, x1, y1, color);
+        self.fill_rect(x0, y0, x0 + t, y1, color);
+        self.fill_rect(x1 - t, y0, x1, y1, color);
+    }
+
+    /// Filled label with white digits, above the box when there is room, inside otherwise
+    fn draw_tag(&mut self, x: i64, y: i64, text: &str, glyph: i64, color: [u8; 3]) {
+        let digits = text.len() as i64;
+        let w = glyph * (4 * digits + 1);
+        let h = glyph * 7;
+        let x = x.min(self.width as i64 - w).max(0);
+        let y = if y - h >= 0 { y - h } else { y.max(0) };
+
+        self.fill_rect(x, y, x + w, y + h, color);
+        for (i, c) in text.bytes().enumerate() {
+            let rows = DIGITS[(c - b'0') as usize];
+            let gx = x + glyph + i as i64 * 4 * glyph;
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) != 0 {
+                        let px = gx + col * glyph;
+                        let py = y + glyph + row as i64 * glyph;
+                        self.fill_rect(px, py, px + glyph, py + glyph, [255, 255, 255]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::TreeNode;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Bounds {
+        Bounds { x, y, width, height }
+    }
+
+    fn blank_png(width: u32, height: u32) -> Vec<u8> {
+        let canvas = Canvas {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        };
+        canvas.encode().unwrap()
+    }
+
+    fn pixel(png: &[u8], x: usize, y: usize) -> [u8; 4] {
+        let canvas = Canvas::decode(png).unwrap();
+        let i = (y * canvas.width as usize + x) * 4;
+        canvas.pixels[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn only_visible_interactive_elements_are_marked() {
+        let mut nodes = vec![
+            TreeNode::test(0, "AXWindow").with_title("Main").with_bounds(rect(0.0, 0.0, 200.0, 100.0)),
+            TreeNode::test(1, "AXButton").with_title("OK").with_bounds(rect(10.0, 10.0, 40.0, 20.0)),
+            TreeNode::test(1, "AXStaticText").with_title("Hello").with_bounds(rect(60.0, 10.0, 40.0, 20.0)),
+            TreeNode::test(1, "AXButton").with_title("Hidden").with_bounds(rect(500.0, 10.0, 40.0, 20.0)),
+            TreeNode::test(1, "AXButton").with_title("Empty").with_bounds(rect(10.0, 40.0, 0.0, 0.0)),
+            TreeNode::test(1, "AXLink").with_title("Docs").with_bounds(rect(10.0, 70.0, 30.0, 10.0)),
+        ];
+        for (i, n) in nodes.iter_mut().enumerate() {
+            n.index = i;
+        }
+        let tree = TreeResult {
+            app: "Test".to_string(),
+            element_count: nodes.len(),
+            nodes,
+            truncated: Vec::new(),
+        };
+
+        let som = tree.marks(&Bounds { x: 0.0, y: 0.0, width: 200.0, height: 100.0 });
+        let labels: Vec<_> = som.marks.iter().map(|m| m.label.as_deref()).collect();
+        assert_eq!(labels, vec![Some("OK"), Some("Docs")]);
+        assert_eq!(som.marks[1].mark, 2);
+        assert_eq!(som.marks[1].index, 5);
+    }
+
+    #[test]
+    fn draws_boxes_scaled_to_the_image() {
+        let som = SetOfMarks {
+            app: "Test".to_string(),
+            screen: Bounds { x: 0.0, y: 0.0, width: 50.0, height: 50.0 },
+            marks: vec![Mark {
+                mark: 7,
+                id: String::new(),
+                index: 0,
+                role: "AXButton".to_string(),
+                label: None,
+                bounds: Bounds { x: 10.0, y: 20.0, width: 20.0, height: 10.0 },
+            }],
+        };
+
+        // 2x capture: points map to twice as many pixels
+        let out = som.draw(&blank_png(100, 100)).unwrap();
+        let color = PALETTE[6];
+        assert_eq!(pixel(&out, 40, 40), [color[0], color[1], color[2], 255]);
+        assert_eq!(pixel(&out, 59, 50), [color[0], color[1], color[2], 255]);
+        assert_eq!(pixel(&out, 40, 50), [0, 0, 0, 0]);
+        assert_eq!(pixel(&out, 90, 90), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rejects_non_png_input() {
+        let som = SetOfMarks {
+            app: "Test".to_string(),
+            screen: Bounds { x: 0.0, y: 0.0, width: 10.0, height: 10.0 },
+            marks: Vec::new(),
+        };
+        assert!(som.draw(b"not a png").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Menu paths such as `File > Export > PDF…`
+//!
+//! A path is a list of item titles, one per menu level. Titles match
+//! case-insensitively and ignore a trailing ellipsis, so `Save As...`
+//! finds `Save As…`. When a level has no matching item the error lists
+//! what that level does offer.
+
+use crate::error::{Error, ErrorCode, Result};
+use serde_json::json;
+
+/// Split a `>`-separated menu path into titles
+pub fn parse_menu_path(path: &str) -> Result<Vec<String>> {
+    let titles: Vec<String> = path.split('>').map(|t| t.trim().to_string()).collect();
+    if titles.iter().any(String::is_empty) {
+        return Err(Error::new(
+            ErrorCode::SelectorInvalid,
+            format!("Invalid menu path: '{}'", path),
+        )
+        .with_suggestions(vec![
+            "Separate levels with '>', e.g. \"File > Export > PDF…\"".to_string(),
+        ]));
+    }
+    Ok(titles)
+}
+
+/// Index of the item to choose at one level
+///
+/// An exact title wins; otherwise the first loose match (case, surrounding
+/// whitespace and a trailing `…` or `...` ignored).
+pub fn pick_menu_item(wanted: &str, titles: &[String]) -> Option<usize> {
+    titles.iter().position(|t| t == wanted).or_else(|| {
+        let wanted = loose_title(wanted);
+        titles.iter().position(|t| loose_title(t) == wanted)
+    })
+}
+
+fn loose_title(title: &str) -> String {
+    let title = title.trim();
+    let title = title
+        .strip_suffix('…')
+        .or_else(|| title.strip_suffix("..."))
+        .unwrap_or(title);
+    title.trim_end().to_lowercase()
+}
+
+/// Error for a path that leaves the menu at `level`
+///
+/// `root` names where the path starts, e.g. "menu bar" or "context menu".
+pub fn menu_divergence(root: &str, path: &[String], level: usize, available: &[String]) -> Error {
+    let wanted = &path[level];
+    let message = match level {
+        0 => format!("No item '{}' in the {}", wanted, root),
+        _ => format!("No item '{}' in menu '{}'", wanted, path[..level].join(" > ")),
+    };
+    Error::new(ErrorCode::ElementNotFound, message)
+        .with_suggestions(vec![format!("Available here: {}", available.join(", "))])
+        .with_context(json!({
+            "path": path,
+            "level": level,
+            "missing": wanted,
+            "available": available,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(items: &[&str]) -> Vec<String> {
+        items.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_path() {
+        assert_eq!(
+            parse_menu_path("File > Export >PDF…").unwrap(),
+            titles(&["File", "Export", "PDF…"])
+        );
+        assert!(parse_menu_path("File >> PDF").is_err());
+        assert!(parse_menu_path("").is_err());
+    }
+
+    #[test]
+    fn loose_matching() {
+        let items = titles(&["New", "Save As…", "save", "Save"]);
+        assert_eq!(pick_menu_item("Save", &items), Some(3));
+        assert_eq!(pick_menu_item("save as...", &items), Some(1));
+        assert_eq!(pick_menu_item("Save As", &items), Some(1));
+        assert_eq!(pick_menu_item("Print", &items), None);
+    }
+
+    #[test]
+    fn divergence_lists_the_level() {
+        let path = titles(&["File", "Export", "PDF"]);
+        let err = menu_divergence("menu bar", &path, 0, &titles(&["Apple", "Edit"]));
+        assert_eq!(err.message, "No item 'File' in the menu bar");
+
+        let err = menu_divergence("menu bar", &path, 1, &titles(&["New", "Open…"]));
+        assert_eq!(err.code, ErrorCode::ElementNotFound);
+        assert_eq!(err.message, "No item 'Export' in menu 'File'");
+        let context = err.context.unwrap();
+        assert_eq!(context["level"], 1);
+        assert_eq!(context["available"], json!(["New", "Open…"]));
+    }
+}
@@ -0,0 +1,342 @@
+//! Pointer primitives shared by the platform injectors
+//!
+//! A drag is a press at one point, a series of moves with the button held
+//! and a release at another. Apps only see a drag when the moves arrive
+//! spread over time, so the path is stepped at a fixed interval rather than
+//! jumping straight to the end.
+//!
+//! Plain moves can be stepped the same way. A `MotionProfile` says how: the
+//! curve the pointer follows, how long it takes, how often it moves and how
+//! much it wobbles. Hover menus and web apps that reject instant jumps need
+//! one; everything else is fine with `MotionProfile::instant()`.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Interval between pointer moves while dragging
+pub const DRAG_STEP_MS: u64 = 16;
+
+/// Default time a drag takes from press to release
+pub const DRAG_DURATION_MS: u64 = 300;
+
+/// Duration of a parsed motion profile that doesn't name one
+pub const DEFAULT_MOTION_MS: u64 = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Right,
+    Middle,
+}
+
+impl MouseButton {
+    /// `left`, `right` or `middle`
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "left" => Ok(MouseButton::Left),
+            "right" => Ok(MouseButton::Right),
+            "middle" | "center" => Ok(MouseButton::Middle),
+            _ => Err(Error::selector_invalid(s, "expected left, right or middle")),
+        }
+    }
+
+    /// Button number as recorded events store it
+    pub fn index(self) -> u8 {
+        match self {
+            MouseButton::Left => 0,
+            MouseButton::Right => 1,
+            MouseButton::Middle => 2,
+        }
+    }
+
+    pub fn from_index(index: u8) -> Self {
+        match index {
+            0 => MouseButton::Left,
+            1 => MouseButton::Right,
+            _ => MouseButton::Middle,
+        }
+    }
+}
+
+impl std::fmt::Display for MouseButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MouseButton::Left => write!(f, "left"),
+            MouseButton::Right => write!(f, "right"),
+            MouseButton::Middle => write!(f, "middle"),
+        }
+    }
+}
+
+/// Points to move through after pressing at `from`, ending exactly at `to`
+///
+/// One point per `DRAG_STEP_MS` of `duration`, and always at least one.
+pub fn drag_path(from: (i32, i32), to: (i32, i32), duration: Duration) -> Vec<(i32, i32)> {
+    MotionProfile::linear(duration.as_millis() as u64).path(from, to)
+}
+
+/// Shape of the way from one point to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MotionCurve {
+    /// Jump straight to the target
+    #[default]
+    Instant,
+    /// Straight line at constant speed
+    Linear,
+    /// Straight line, slow at both ends
+    Eased,
+    /// Curved, slow at both ends; the bend differs on every move
+    Bezier,
+}
+
+/// How the pointer travels between two points
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MotionProfile {
+    pub curve: MotionCurve,
+    pub duration_ms: u64,
+    /// Interval between moves
+    pub step_ms: u64,
+    /// Largest random offset of each point along the way, in pixels
+    pub jitter_px: f64,
+    /// Fixes the randomness of bends and jitter, for repeatable runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Default for MotionProfile {
+    fn default() -> Self {
+        Self::instant()
+    }
+}
+
+impl MotionProfile {
+    pub fn instant() -> Self {
+        Self::with_curve(MotionCurve::Instant, 0)
+    }
+
+    pub fn linear(duration_ms: u64) -> Self {
+        Self::with_curve(MotionCurve::Linear, duration_ms)
+    }
+
+    pub fn eased(duration_ms: u64) -> Self {
+        Self::with_curve(MotionCurve::Eased, duration_ms)
+    }
+
+    /// Curved path with a pixel of jitter
+    pub fn bezier(duration_ms: u64) -> Self {
+        Self::with_curve(MotionCurve::Bezier, duration_ms).jitter(1.0)
+    }
+
+    fn with_curve(curve: MotionCurve, duration_ms: u64) -> Self {
+        Self {
+            curve,
+            duration_ms,
+            step_ms: DRAG_STEP_MS,
+            jitter_px: 0.0,
+            seed: None,
+        }
+    }
+
+    pub fn jitter(mut self, px: f64) -> Self {
+        self.jitter_px = px.max(0.0);
+        self
+    }
+
+    pub fn step(mut self, ms: u64) -> Self {
+        self.step_ms = ms.max(1);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// `instant`, or `linear`, `eased` or `bezier` with an optional
+    /// `:<ms>` duration, e.g. `bezier:400`
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        let (curve, ms) = match s.split_once(':') {
+            Some((curve, ms)) => {
+                let ms = ms
+                    .trim()
+                    .parse()
+                    .map_err(|_| Error::selector_invalid(&s, "duration must be milliseconds, e.g. bezier:400"))?;
+                (curve.trim(), ms)
+            }
+            None => (s.as_str(), DEFAULT_MOTION_MS),
+        };
+        match curve {
+            "instant" => Ok(Self::instant()),
+            "linear" => Ok(Self::linear(ms)),
+            "eased" => Ok(Self::eased(ms)),
+            "bezier" => Ok(Self::bezier(ms)),
+            _ => Err(Error::selector_invalid(&s, "expected instant, linear, eased or bezier")),
+        }
+    }
+
+    pub fn is_instant(&self) -> bool {
+        self.curve == MotionCurve::Instant || self.duration_ms == 0
+    }
+
+    /// The same profile squeezed into at most `budget_ms`
+    pub fn within(&self, budget_ms: u64) -> Self {
+        Self {
+            duration_ms: self.duration_ms.min(budget_ms),
+            ..self.clone()
+        }
+    }
+
+    /// Points to move through after `from`, one per step, ending exactly at `to`
+    pub fn path(&self, from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+        if self.is_instant() || from == to {
+            return vec![to];
+        }
+        let steps = (self.duration_ms / self.step_ms.max(1)).max(1);
+        let mut rng = Rng::new(self.seed);
+        let (fx, fy) = (from.0 as f64, from.1 as f64);
+        let (dx, dy) = ((to.0 - from.0) as f64, (to.1 - from.1) as f64);
+
+        // Control points off to one side of the line, by up to a fifth of its length
+        let bend = |rng: &mut Rng, along: f64| {
+            let off = rng.signed() * 0.2;
+            (fx + dx * along - dy * off, fy + dy * along + dx * off)
+        };
+        let controls = match self.curve {
+            MotionCurve::Bezier => Some((bend(&mut rng, 0.3), bend(&mut rng, 0.7))),
+            _ => None,
+        };
+
+        (1..=steps)
+            .map(|i| {
+                if i == steps {
+                    return to;
+                }
+                let t = i as f64 / steps as f64;
+                let (x, y) = match (self.curve, controls) {
+                    (MotionCurve::Bezier, Some((c1, c2))) => cubic((fx, fy), c1, c2, (to.0 as f64, to.1 as f64), ease(t)),
+                    (MotionCurve::Eased, _) => (fx + dx * ease(t), fy + dy * ease(t)),
+                    _ => (fx + dx * t, fy + dy * t),
+                };
+                (
+                    (x + rng.signed() * self.jitter_px).round() as i32,
+                    (y + rng.signed() * self.jitter_px).round() as i32,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Ease in and out, cubic
+fn ease(t: f64) -> f64 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+fn cubic(p0: (f64, f64), p1: (f64, f64), p2: (f64, f64), p3: (f64, f64), t: f64) -> (f64, f64) {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    (
+        a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+        a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+    )
+}
+
+/// Small xorshift generator; motion only needs to look random, not be random
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Self(seed | 1)
+    }
+
+    /// Uniform in [-1, 1]
+    fn signed(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+}
+
+/// Screen coordinates written as `x,y`
+pub fn parse_point(s: &str) -> Option<(i32, i32)> {
+    let (x, y) = s.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_is_stepped_and_ends_at_target() {
+        let path = drag_path((0, 0), (100, -50), Duration::from_millis(160));
+        assert_eq!(path.len(), 10);
+        assert_eq!(path[0], (10, -5));
+        assert_eq!(*path.last().unwrap(), (100, -50));
+
+        assert_eq!(drag_path((5, 5), (9, 9), Duration::ZERO), vec![(9, 9)]);
+    }
+
+    #[test]
+    fn profiles_end_at_target() {
+        for profile in [
+            MotionProfile::linear(200),
+            MotionProfile::eased(200),
+            MotionProfile::bezier(200).jitter(3.0),
+        ] {
+            let path = profile.seed(7).path((0, 0), (300, 120));
+            assert_eq!(path.len(), 12);
+            assert_eq!(*path.last().unwrap(), (300, 120));
+        }
+        assert_eq!(MotionProfile::instant().path((0, 0), (300, 120)), vec![(300, 120)]);
+    }
+
+    #[test]
+    fn eased_starts_slow_and_bezier_bends() {
+        let linear = MotionProfile::linear(160).path((0, 0), (1000, 0));
+        let eased = MotionProfile::eased(160).path((0, 0), (1000, 0));
+        assert!(eased[0].0 < linear[0].0);
+        assert!(eased[4].0 <= linear[4].0);
+
+        let bezier = MotionProfile::bezier(160).jitter(0.0).seed(42).path((0, 0), (1000, 0));
+        assert!(bezier.iter().any(|p| p.1 != 0));
+        assert_eq!(bezier, MotionProfile::bezier(160).jitter(0.0).seed(42).path((0, 0), (1000, 0)));
+    }
+
+    #[test]
+    fn parse_profiles() {
+        assert_eq!(MotionProfile::parse("bezier:400").unwrap().duration_ms, 400);
+        assert_eq!(MotionProfile::parse("Eased").unwrap(), MotionProfile::eased(DEFAULT_MOTION_MS));
+        assert!(MotionProfile::parse("instant").unwrap().is_instant());
+        assert!(MotionProfile::parse("wiggle").is_err());
+        assert!(MotionProfile::parse("linear:soon").is_err());
+        assert_eq!(MotionProfile::linear(300).within(120).duration_ms, 120);
+    }
+
+    #[test]
+    fn buttons_and_points() {
+        assert_eq!(MouseButton::parse("Right").unwrap(), MouseButton::Right);
+        assert!(MouseButton::parse("fourth").is_err());
+        assert_eq!(MouseButton::from_index(MouseButton::Middle.index()), MouseButton::Middle);
+
+        assert_eq!(parse_point("120, 45"), Some((120, 45)));
+        assert_eq!(parse_point("-3,7"), Some((-3, 7)));
+        assert_eq!(parse_point("name:Trash"), None);
+        assert_eq!(parse_point("1,2,3"), None);
+    }
+}
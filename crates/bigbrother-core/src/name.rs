@@ -0,0 +1,198 @@
+//! Accessible name computation
+//!
+//! The name is what a screen reader would announce for an element, and what
+//! `name:` selectors match. Sources are tried in order and the first
+//! non-empty one wins:
+//!
+//! 1. title
+//! 2. text of the elements that label this one (label-by relation)
+//! 3. description
+//! 4. placeholder
+//! 5. value, for static text only
+//! 6. text of the children, for roles that take their name from content
+//!    (buttons, links, menu items, cells, ...)
+//!
+//! Whitespace is collapsed. The role description ("button", "text field")
+//! is never used as a name; it is exposed separately as `role_description`.
+
+use crate::node::AccessibleNode;
+use crate::role::Role;
+
+/// How deep child text is gathered for name-from-content roles
+const MAX_CONTENT_DEPTH: usize = 3;
+
+/// Longest name produced from child text
+const MAX_CONTENT_LEN: usize = 200;
+
+pub fn is_static_text(role: &str) -> bool {
+    Role::from_native(role) == Role::StaticText
+}
+
+/// Roles whose name comes from their content when nothing else labels them
+pub fn takes_name_from_content(role: &str) -> bool {
+    matches!(
+        Role::from_native(role),
+        Role::Button
+            | Role::Cell
+            | Role::CheckBox
+            | Role::ColumnHeader
+            | Role::DisclosureTriangle
+            | Role::Link
+            | Role::ListItem
+            | Role::MenuItem
+            | Role::PopUpButton
+            | Role::RadioButton
+            | Role::Row
+            | Role::Tab
+            | Role::TreeItem
+    )
+}
+
+/// Compute the accessible name of an element, see the module docs for the precedence
+pub fn accessible_name<N: AccessibleNode>(node: &N) -> Option<String> {
+    let role = node.role().unwrap_or_default();
+
+    clean(node.title())
+        .or_else(|| label_text(node))
+        .or_else(|| clean(node.description()))
+        .or_else(|| clean(node.placeholder()))
+        .or_else(|| {
+            if is_static_text(&role) {
+                clean(node.value())
+            } else {
+                None
+            }
+        })
+        .or_else(|| {
+            if takes_name_from_content(&role) {
+                content_text(node)
+            } else {
+                None
+            }
+        })
+}
+
+/// Text of the labelling elements, joined
+fn label_text<N: AccessibleNode>(node: &N) -> Option<String> {
+    let parts: Vec<String> = node.labelled_by().iter().filter_map(text_of).collect();
+    clean(Some(parts.join(" ")))
+}
+
+/// Visible text of an element used as a label or as content
+fn text_of<N: AccessibleNode>(node: &N) -> Option<String> {
+    let role = node.role().unwrap_or_default();
+    clean(node.title())
+        .or_else(|| if is_static_text(&role) { clean(node.value()) } else { None })
+        .or_else(|| clean(node.description()))
+        .or_else(|| content_text(node))
+}
+
+/// Text of the descendants, in tree order
+fn content_text<N: AccessibleNode>(node: &N) -> Option<String> {
+    let mut parts = Vec::new();
+    collect_content(node, 0, &mut parts);
+    let text = clean(Some(parts.join(" ")))?;
+    Some(text.chars().take(MAX_CONTENT_LEN).collect())
+}
+
+fn collect_content<N: AccessibleNode>(node: &N, depth: usize, parts: &mut Vec<String>) {
+    if depth >= MAX_CONTENT_DEPTH {
+        return;
+    }
+    for child in node.children() {
+        let role = child.role().unwrap_or_default();
+        let own = clean(child.title())
+            .or_else(|| if is_static_text(&role) { clean(child.value()) } else { None })
+            .or_else(|| clean(child.description()));
+        match own {
+            Some(text) => parts.push(text),
+            None => collect_content(&child, depth + 1, parts),
+        }
+    }
+}
+
+/// Collapse whitespace; `None` when nothing is left
+fn clean(text: Option<String>) -> Option<String> {
+    let text = text?.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeTree;
+
+    #[test]
+    fn title_wins_over_everything() {
+        let window = FakeTree::root("AXWindow");
+        let button = window
+            .add("AXButton")
+            .with_title("Save")
+            .with_description("Save the document")
+            .with_role_description("button");
+        assert_eq!(accessible_name(&button).as_deref(), Some("Save"));
+    }
+
+    #[test]
+    fn label_relation_names_a_field() {
+        let form = FakeTree::root("AXGroup");
+        let label = form.add("AXStaticText").with_value("Email address:");
+        let field = form
+            .add("AXTextField")
+            .with_placeholder("you@example.com")
+            .labelled_by_node(&label);
+        assert_eq!(accessible_name(&field).as_deref(), Some("Email address:"));
+    }
+
+    #[test]
+    fn description_then_placeholder() {
+        let form = FakeTree::root("AXGroup");
+        let described = form
+            .add("AXTextField")
+            .with_description("Search")
+            .with_placeholder("Type to search");
+        let placeholder_only = form.add("AXTextField").with_placeholder("Type to search");
+        assert_eq!(accessible_name(&described).as_deref(), Some("Search"));
+        assert_eq!(accessible_name(&placeholder_only).as_deref(), Some("Type to search"));
+    }
+
+    #[test]
+    fn value_names_static_text_but_not_fields() {
+        let window = FakeTree::root("AXWindow");
+        let text = window.add("AXStaticText").with_value("  Welcome\n back ");
+        let field = window.add("AXTextField").with_value("typed by the user");
+        assert_eq!(accessible_name(&text).as_deref(), Some("Welcome back"));
+        assert_eq!(accessible_name(&field), None);
+    }
+
+    #[test]
+    fn buttons_take_their_name_from_child_text() {
+        let window = FakeTree::root("AXWindow");
+        let button = window.add("AXButton");
+        button.add("AXImage");
+        let inner = button.add("AXGroup");
+        inner.add("AXStaticText").with_value("Sign");
+        inner.add("AXStaticText").with_value("in");
+        assert_eq!(accessible_name(&button).as_deref(), Some("Sign in"));
+    }
+
+    #[test]
+    fn containers_do_not_take_child_text() {
+        let window = FakeTree::root("AXWindow");
+        let group = window.add("AXGroup");
+        group.add("AXStaticText").with_value("Hello");
+        assert_eq!(accessible_name(&group), None);
+    }
+
+    #[test]
+    fn role_description_is_never_the_name() {
+        let window = FakeTree::root("AXWindow");
+        let field = window.add("AXTextField").with_role_description("text field");
+        assert_eq!(accessible_name(&field), None);
+        assert_eq!(field.role_description().as_deref(), Some("text field"));
+    }
+}
@@ -0,0 +1,22 @@
+//! Platform-neutral view of an accessibility element
+//!
+//! Logic that only reads the tree (name computation, labelling) is written
+//! against `AccessibleNode`, so it can be unit-tested against the in-memory
+//! fake tree instead of a live accessibility API.
+
+use crate::tree::Bounds;
+
+pub trait AccessibleNode: Sized {
+    fn role(&self) -> Option<String>;
+    fn title(&self) -> Option<String>;
+    fn description(&self) -> Option<String>;
+    fn placeholder(&self) -> Option<String>;
+    fn value(&self) -> Option<String>;
+    /// Localized role text such as "button" or "text field"
+    fn role_description(&self) -> Option<String>;
+    /// Elements that label this one (title element, label relations)
+    fn labelled_by(&self) -> Vec<Self>;
+    /// Screen rectangle, used to pair labels with nearby fields
+    fn bounds(&self) -> Option<Bounds>;
+    fn children(&self) -> Vec<Self>;
+}
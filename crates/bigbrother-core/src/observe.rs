@@ -0,0 +1,457 @@
+//! Compact, token-budgeted rendering of an accessibility tree for LLMs
+//!
+//! Output is indented text, one element per line. Interactive elements get a
+//! `[n]` mark that maps back to a stable id in `Observation::elements`.
+//! Unlabeled layout groups are collapsed into their children, long values are
+//! truncated, and when the text exceeds the budget the least relevant subtrees
+//! are pruned first. The focused element and its ancestors are never pruned.
+
+use crate::role::Role;
+use crate::tree::{TreeNode, TreeResult};
+use serde::{Deserialize, Serialize};
+
+/// Roughly how many characters one token covers in English UI text
+pub const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObserveOptions {
+    /// Character budget for `Observation::text`, `None` for unlimited
+    pub max_chars: Option<usize>,
+    /// Labels and values longer than this are truncated
+    pub max_value_len: usize,
+    /// Depth passed to the tree walk
+    pub max_depth: usize,
+}
+
+impl Default for ObserveOptions {
+    fn default() -> Self {
+        Self {
+            max_chars: Some(8000),
+            max_value_len: 60,
+            max_depth: 15,
+        }
+    }
+}
+
+impl ObserveOptions {
+    pub fn chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = Some(max_chars);
+        self
+    }
+
+    pub fn tokens(mut self, max_tokens: usize) -> Self {
+        self.max_chars = Some(max_tokens * CHARS_PER_TOKEN);
+        self
+    }
+
+    pub fn unlimited(mut self) -> Self {
+        self.max_chars = None;
+        self
+    }
+
+    pub fn depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Observation {
+    pub app: String,
+    pub text: String,
+    /// Interactive elements in the order of their `[n]` marks
+    pub elements: Vec<MarkedElement>,
+    pub total_nodes: usize,
+    pub pruned_nodes: usize,
+    /// True when subtrees were pruned to fit the budget
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkedElement {
+    pub mark: usize,
+    pub id: String,
+    pub index: usize,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+pub fn is_interactive(role: &str) -> bool {
+    Role::from_native(role).is_interactive()
+}
+
+pub fn is_layout(role: &str) -> bool {
+    Role::from_native(role).is_layout()
+}
+
+impl TreeResult {
+    /// Render this snapshot as a compact observation
+    pub fn observe(&self, options: &ObserveOptions) -> Observation {
+        observe(self, options)
+    }
+}
+
+/// Render a tree snapshot as a compact observation within the budget
+pub fn observe(tree: &TreeResult, options: &ObserveOptions) -> Observation {
+    let nodes = &tree.nodes;
+    let n = nodes.len();
+    let parents = tree.parents();
+
+    let collapsed: Vec<bool> = nodes.iter().map(is_collapsed).collect();
+
+    // Visible depth skips collapsed ancestors
+    let mut visible_depth = vec![0usize; n];
+    for i in 0..n {
+        if let Some(p) = parents[i] {
+            visible_depth[i] = visible_depth[p] + usize::from(!collapsed[p]);
+        }
+    }
+
+    // Marks are numbered after pruning, so reserve room for the widest one
+    let interactive = nodes.iter().filter(|node| is_interactive(&node.role)).count();
+    let mark_width = format!("[{}] ", interactive).len();
+    let cost: Vec<usize> = (0..n)
+        .map(|i| {
+            if collapsed[i] {
+                0
+            } else {
+                let mark = if is_interactive(&nodes[i].role) { mark_width } else { 0 };
+                visible_depth[i] * 2 + mark + render_line(&nodes[i], options).chars().count() + 1
+            }
+        })
+        .collect();
+
+    let mut removed = vec![false; n];
+    let mut pruned_under = vec![0usize; n];
+    let mut total: usize = cost.iter().sum();
+    let mut pruned_nodes = 0;
+
+    if let Some(budget) = options.max_chars {
+        if total > budget {
+            let plan = PrunePlan::new(nodes, &parents, &cost);
+            for i in plan.order {
+                if total <= budget {
+                    break;
+                }
+                if removed[i] {
+                    continue;
+                }
+
+                let mut freed = 0;
+                let mut newly = 0;
+                for j in i..plan.subtree_end[i] {
+                    if !removed[j] {
+                        removed[j] = true;
+                        freed += cost[j];
+                        if pruned_under[j] > 0 {
+                            freed += placeholder_cost(j, &collapsed, &visible_depth, n);
+                        }
+                        newly += 1;
+                    }
+                }
+
+                // One count for the whole subtree, taking in the placeholders
+                // of descendants pruned before it
+                let parent = parents[i].expect("root is never a prune candidate");
+                if pruned_under[parent] == 0 {
+                    total += placeholder_cost(parent, &collapsed, &visible_depth, n);
+                }
+                pruned_under[parent] += plan.subtree_end[i] - i;
+                pruned_nodes += newly;
+                total -= freed;
+            }
+        }
+    }
+
+    let mut text = String::new();
+    let mut elements = Vec::new();
+    // Open nodes whose `… N more` line is emitted once their subtree is done
+    let mut open: Vec<usize> = Vec::new();
+
+    for i in 0..n {
+        if removed[i] {
+            continue;
+        }
+        while let Some(&top) = open.last() {
+            if nodes[top].depth < nodes[i].depth {
+                break;
+            }
+            open.pop();
+            push_placeholder(&mut text, top, &collapsed, &visible_depth, &pruned_under);
+        }
+        open.push(i);
+
+        if collapsed[i] {
+            continue;
+        }
+
+        let node = &nodes[i];
+        text.push_str(&"  ".repeat(visible_depth[i]));
+        if is_interactive(&node.role) {
+            let mark = elements.len() + 1;
+            text.push_str(&format!("[{}] ", mark));
+            elements.push(MarkedElement {
+                mark,
+                id: node.id.clone(),
+                index: node.index,
+                role: node.role.clone(),
+                label: node.label().map(String::from),
+            });
+        }
+        text.push_str(&render_line(node, options));
+        text.push('\n');
+    }
+    while let Some(top) = open.pop() {
+        push_placeholder(&mut text, top, &collapsed, &visible_depth, &pruned_under);
+    }
+
+    Observation {
+        app: tree.app.clone(),
+        text,
+        elements,
+        total_nodes: n,
+        pruned_nodes,
+        truncated: pruned_nodes > 0,
+    }
+}
+
+fn is_collapsed(node: &TreeNode) -> bool {
+    is_layout(&node.role)
+        && node.title.as_deref().unwrap_or("").is_empty()
+        && node.value.as_deref().unwrap_or("").is_empty()
+        && !node.focused
+}
+
+fn render_line(node: &TreeNode, options: &ObserveOptions) -> String {
+    let mut line = node.role.strip_prefix("AX").unwrap_or(&node.role).to_string();
+    let label = node.label();
+    if let Some(label) = label {
+        line.push_str(&format!(" \"{}\"", truncate(label, options.max_value_len)));
+    }
+    if let Some(value) = node.value.as_deref() {
+        if !value.is_empty() && Some(value) != label {
+            line.push_str(&format!(" = \"{}\"", truncate(value, options.max_value_len)));
+        }
+    }
+    if node.focused {
+        line.push_str(" (focused)");
+    }
+    line
+}
+
+fn push_placeholder(
+    text: &mut String,
+    node: usize,
+    collapsed: &[bool],
+    visible_depth: &[usize],
+    pruned_under: &[usize],
+) {
+    if pruned_under[node] == 0 {
+        return;
+    }
+    text.push_str(&"  ".repeat(placeholder_depth(node, collapsed, visible_depth)));
+    text.push_str(&format!("… {} more\n", pruned_under[node]));
+}
+
+fn placeholder_depth(node: usize, collapsed: &[bool], visible_depth: &[usize]) -> usize {
+    visible_depth[node] + usize::from(!collapsed[node])
+}
+
+/// Characters a `… N more` line under `node` takes, with `N` at most `total`
+fn placeholder_cost(node: usize, collapsed: &[bool], visible_depth: &[usize], total: usize) -> usize {
+    placeholder_depth(node, collapsed, visible_depth) * 2 + format!("… {} more\n", total).chars().count()
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    let s = s.replace('\n', " ");
+    if s.chars().count() <= max {
+        s
+    } else {
+        let mut out: String = s.chars().take(max.saturating_sub(1)).collect();
+        out.push('…');
+        out
+    }
+}
+
+/// Order in which subtrees are given up when over budget
+struct PrunePlan {
+    order: Vec<usize>,
+    /// Exclusive end of each node's subtree in DFS order
+    subtree_end: Vec<usize>,
+}
+
+impl PrunePlan {
+    fn new(nodes: &[TreeNode], parents: &[Option<usize>], cost: &[usize]) -> Self {
+        let n = nodes.len();
+
+        // Focused elements and their ancestors always stay
+        let mut protected = vec![false; n];
+        if n > 0 {
+            protected[0] = true;
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            if node.focused {
+                let mut cur = Some(i);
+                while let Some(c) = cur {
+                    protected[c] = true;
+                    cur = parents[c];
+                }
+            }
+        }
+
+        let mut size = vec![1usize; n];
+        let mut value: Vec<usize> = nodes.iter().map(relevance).collect();
+        let mut weight: Vec<usize> = cost.to_vec();
+        for i in (0..n).rev() {
+            if let Some(p) = parents[i] {
+                size[p] += size[i];
+                value[p] += value[i];
+                weight[p] += weight[i];
+            }
+        }
+        let subtree_end: Vec<usize> = (0..n).map(|i| i + size[i]).collect();
+
+        let mut order: Vec<usize> = (0..n).filter(|&i| !protected[i]).collect();
+        // Least relevance per character first; deeper and later nodes break ties
+        order.sort_by(|&a, &b| {
+            let da = value[a] as f64 / weight[a].max(1) as f64;
+            let db = value[b] as f64 / weight[b].max(1) as f64;
+            da.partial_cmp(&db)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(nodes[b].depth.cmp(&nodes[a].depth))
+                .then(b.cmp(&a))
+        });
+
+        Self { order, subtree_end }
+    }
+}
+
+fn relevance(node: &TreeNode) -> usize {
+    if is_interactive(&node.role) {
+        3
+    } else if node.label().is_some() || node.value.as_deref().is_some_and(|v| !v.is_empty()) {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(mut nodes: Vec<TreeNode>) -> TreeResult {
+        for (i, n) in nodes.iter_mut().enumerate() {
+            n.index = i;
+            n.id = format!("id{}", i);
+        }
+        TreeResult {
+            app: "Test".to_string(),
+            element_count: nodes.len(),
+            nodes,
+            truncated: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renders_marks_and_collapses_groups() {
+        let t = tree(vec![
+            TreeNode::test(0, "AXWindow").with_title("Main"),
+            TreeNode::test(1, "AXGroup"),
+            TreeNode::test(2, "AXButton").with_title("OK"),
+            TreeNode::test(2, "AXTextField").with_value("hello"),
+        ]);
+        let obs = t.observe(&ObserveOptions::default());
+        assert_eq!(
+            obs.text,
+            "Window \"Main\"\n  [1] Button \"OK\"\n  [2] TextField = \"hello\"\n"
+        );
+        assert_eq!(obs.elements.len(), 2);
+        assert_eq!(obs.elements[1].id, "id3");
+        assert!(!obs.truncated);
+    }
+
+    #[test]
+    fn truncates_long_values() {
+        let long = "x".repeat(200);
+        let t = tree(vec![TreeNode::test(0, "AXStaticText").with_value(&long)]);
+        let obs = t.observe(&ObserveOptions { max_value_len: 10, ..Default::default() });
+        assert_eq!(obs.text, "StaticText = \"xxxxxxxxx…\"\n");
+    }
+
+    #[test]
+    fn budget_prunes_irrelevant_subtrees_and_keeps_focus() {
+        let mut nodes = vec![TreeNode::test(0, "AXWindow").with_title("Main")];
+        nodes.push(TreeNode::test(1, "AXList").with_title("Noise"));
+        for i in 0..50 {
+            nodes.push(TreeNode::test(2, "AXStaticText").with_value(&format!("row {}", i)));
+        }
+        nodes.push(TreeNode::test(1, "AXGroup").with_title("Form"));
+        let mut field = TreeNode::test(2, "AXTextField").with_title("Email");
+        field.focused = true;
+        nodes.push(field);
+        let t = tree(nodes);
+
+        let obs = t.observe(&ObserveOptions::default().chars(120));
+        assert!(obs.truncated);
+        assert!(obs.text.chars().count() <= 120, "{}", obs.text);
+        assert!(obs.text.contains("TextField \"Email\" (focused)"));
+        assert!(obs.text.contains("more"));
+        assert_eq!(obs.elements.len(), 1);
+    }
+
+    #[test]
+    fn budget_counts_chars_and_indentation() {
+        let mut nodes = vec![TreeNode::test(0, "AXWindow").with_title("Fenêtre")];
+        for i in 0..6 {
+            nodes.push(TreeNode::test(1, "AXGroup").with_title(&format!("Région {}", i)));
+            for j in 0..8 {
+                nodes.push(TreeNode::test(2, "AXGroup").with_title("Liste"));
+                nodes.push(TreeNode::test(3, "AXButton").with_title(&format!("Élément {}", j)));
+            }
+        }
+        let t = tree(nodes);
+
+        for budget in (40..600).step_by(7) {
+            let obs = t.observe(&ObserveOptions::default().chars(budget));
+            assert!(obs.text.chars().count() <= budget, "budget {}:\n{}", budget, obs.text);
+        }
+    }
+
+    #[test]
+    fn placeholders_count_every_pruned_node() {
+        // Lists inside sections, so pruning happens at both depths
+        let mut nodes = vec![TreeNode::test(0, "AXWindow").with_title("Main")];
+        for i in 0..4 {
+            nodes.push(TreeNode::test(1, "AXGroup").with_title(&format!("Section {}", i)));
+            nodes.push(TreeNode::test(2, "AXList").with_title("Items"));
+            for j in 0..10 {
+                nodes.push(TreeNode::test(3, "AXStaticText").with_value(&format!("item {}", j)));
+            }
+        }
+        let t = tree(nodes);
+
+        for budget in (40..500).step_by(11) {
+            let obs = t.observe(&ObserveOptions::default().chars(budget));
+            let placeholders: Vec<usize> = obs
+                .text
+                .lines()
+                .filter_map(|line| line.trim_start().strip_prefix("… ")?.strip_suffix(" more")?.parse().ok())
+                .collect();
+            assert_eq!(placeholders.iter().sum::<usize>(), obs.pruned_nodes, "budget {}:\n{}", budget, obs.text);
+            assert_eq!(obs.text.lines().count() - placeholders.len() + obs.pruned_nodes, obs.total_nodes);
+        }
+    }
+
+    #[test]
+    fn unlimited_keeps_everything() {
+        let mut nodes = vec![TreeNode::test(0, "AXWindow")];
+        for _ in 0..100 {
+            nodes.push(TreeNode::test(1, "AXStaticText").with_value("text"));
+        }
+        let obs = tree(nodes).observe(&ObserveOptions::default().unlimited());
+        assert_eq!(obs.pruned_nodes, 0);
+        assert_eq!(obs.text.lines().count(), 101);
+    }
+}
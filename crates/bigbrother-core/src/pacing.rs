@@ -0,0 +1,223 @@
+//! How fast injected input goes out
+//!
+//! Backends post input as fast as they can, faster than a busy app reads
+//! it, and the app loses characters and clicks. A pacing policy spaces input
+//! out: a delay between typed characters, a settle time after each action
+//! and a cap on actions per second. With adaptive pacing each failed
+//! verification, such as a field that reads back something other than what
+//! was typed, doubles those delays up to `MAX_SLOWDOWN`; verifications that
+//! pass ease them back down.
+
+use crate::text_entry::{TextEntryOptions, KEY_DELAY_MS};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Most that adaptive pacing stretches the policy's delays
+pub const MAX_SLOWDOWN: f64 = 8.0;
+
+/// Slow-down after a failed verification, as a factor of the current one
+const SLOWDOWN_STEP: f64 = 2.0;
+
+/// Slow-down kept after a verification that passed
+const RECOVERY_STEP: f64 = 0.8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PacingPolicy {
+    /// Pause after each typed character
+    pub key_delay_ms: u64,
+    /// Pause after each action, for the app to catch up
+    pub settle_ms: u64,
+    /// Cap on actions per second; `None` leaves them uncapped
+    pub max_actions_per_sec: Option<f64>,
+    /// Slow down when verification fails
+    pub adaptive: bool,
+}
+
+impl Default for PacingPolicy {
+    fn default() -> Self {
+        Self {
+            key_delay_ms: KEY_DELAY_MS,
+            settle_ms: 0,
+            max_actions_per_sec: None,
+            adaptive: true,
+        }
+    }
+}
+
+impl PacingPolicy {
+    /// For apps that drop input at full speed: remote desktops, Electron, VMs
+    pub fn gentle() -> Self {
+        Self {
+            key_delay_ms: 25,
+            settle_ms: 100,
+            max_actions_per_sec: Some(10.0),
+            adaptive: true,
+        }
+    }
+}
+
+/// A pacing policy applied to a stream of actions
+///
+/// Shared by reference: actions taken through one pacer count against the
+/// same rate cap and slow down together.
+#[derive(Debug)]
+pub struct Pacer {
+    policy: PacingPolicy,
+    state: Mutex<PacerState>,
+}
+
+#[derive(Debug)]
+struct PacerState {
+    /// When the latest action was allowed to start
+    last_turn: Option<Instant>,
+    slowdown: f64,
+}
+
+impl Pacer {
+    pub fn new(policy: PacingPolicy) -> Self {
+        Self {
+            policy,
+            state: Mutex::new(PacerState {
+                last_turn: None,
+                slowdown: 1.0,
+            }),
+        }
+    }
+
+    pub fn policy(&self) -> &PacingPolicy {
+        &self.policy
+    }
+
+    /// Factor the policy's delays are currently stretched by
+    pub fn slowdown(&self) -> f64 {
+        self.state.lock().unwrap().slowdown
+    }
+
+    pub fn key_delay(&self) -> Duration {
+        self.scaled(self.policy.key_delay_ms)
+    }
+
+    pub fn settle(&self) -> Duration {
+        self.scaled(self.policy.settle_ms)
+    }
+
+    /// Run one action: wait its turn under the rate cap, then let it settle
+    pub fn action<T>(&self, action: impl FnOnce() -> T) -> T {
+        let wait = self.take_turn(Instant::now());
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+        let result = action();
+        let settle = self.settle();
+        if !settle.is_zero() {
+            thread::sleep(settle);
+        }
+        result
+    }
+
+    /// Tell adaptive pacing whether an action's result checked out
+    pub fn verified(&self, ok: bool) {
+        if !self.policy.adaptive {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.slowdown = if ok {
+            (state.slowdown * RECOVERY_STEP).max(1.0)
+        } else {
+            (state.slowdown * SLOWDOWN_STEP).min(MAX_SLOWDOWN)
+        };
+    }
+
+    /// `options` with this pacer's key delay
+    pub fn text_entry(&self, options: &TextEntryOptions) -> TextEntryOptions {
+        TextEntryOptions {
+            key_delay_ms: self.key_delay().as_millis() as u64,
+            ..options.clone()
+        }
+    }
+
+    /// How long an action starting at `now` waits, booking its slot
+    fn take_turn(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let interval = match self.policy.max_actions_per_sec {
+            Some(rate) if rate > 0.0 => Duration::from_secs_f64(state.slowdown / rate),
+            _ => Duration::ZERO,
+        };
+        let turn = match state.last_turn {
+            Some(last) => (last + interval).max(now),
+            None => now,
+        };
+        state.last_turn = Some(turn);
+        turn - now
+    }
+
+    fn scaled(&self, ms: u64) -> Duration {
+        Duration::from_millis(ms).mul_f64(self.slowdown())
+    }
+}
+
+impl Default for Pacer {
+    fn default() -> Self {
+        Self::new(PacingPolicy::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_cap_spaces_actions() {
+        let pacer = Pacer::new(PacingPolicy {
+            max_actions_per_sec: Some(10.0),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        assert_eq!(pacer.take_turn(now), Duration::ZERO);
+        assert_eq!(pacer.take_turn(now), Duration::from_millis(100));
+        assert_eq!(pacer.take_turn(now + Duration::from_millis(50)), Duration::from_millis(150));
+        // A late action doesn't wait at all
+        assert_eq!(pacer.take_turn(now + Duration::from_secs(1)), Duration::ZERO);
+
+        let uncapped = Pacer::default();
+        assert_eq!(uncapped.take_turn(now), Duration::ZERO);
+        assert_eq!(uncapped.take_turn(now), Duration::ZERO);
+    }
+
+    #[test]
+    fn failed_verification_slows_down_until_it_passes() {
+        let pacer = Pacer::new(PacingPolicy {
+            key_delay_ms: 10,
+            settle_ms: 50,
+            ..Default::default()
+        });
+        pacer.verified(false);
+        pacer.verified(false);
+        assert_eq!(pacer.slowdown(), 4.0);
+        assert_eq!(pacer.key_delay(), Duration::from_millis(40));
+        assert_eq!(pacer.settle(), Duration::from_millis(200));
+        assert_eq!(pacer.text_entry(&TextEntryOptions::default()).key_delay_ms, 40);
+
+        for _ in 0..10 {
+            pacer.verified(false);
+        }
+        assert_eq!(pacer.slowdown(), MAX_SLOWDOWN);
+        for _ in 0..20 {
+            pacer.verified(true);
+        }
+        assert_eq!(pacer.slowdown(), 1.0);
+    }
+
+    #[test]
+    fn fixed_pacing_ignores_verification() {
+        let pacer = Pacer::new(PacingPolicy {
+            adaptive: false,
+            ..Default::default()
+        });
+        pacer.verified(false);
+        assert_eq!(pacer.slowdown(), 1.0);
+        assert_eq!(pacer.key_delay(), Duration::from_millis(KEY_DELAY_MS));
+    }
+}
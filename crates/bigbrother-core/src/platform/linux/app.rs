@@ -0,0 +1,242 @@
+//! Launching apps on X11
+//!
+//! Commands are spawned directly, so the handle knows their pid from the
+//! start. Desktop ids (`org.gnome.Calculator`, `foo.desktop`) go through
+//! `gtk-launch`, whose child is not the app; their pid is taken from the
+//! `_NET_WM_PID` of the first new window whose WM_CLASS belongs to the
+//! desktop entry instead.
+
+use super::Window;
+use crate::error::{Error, ErrorCode, Result};
+use crate::launch::{AppExit, AppSnapshot, LaunchOptions, LaunchTarget, Process};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const POLL_MS: u64 = 100;
+
+/// A launched or attached app
+pub struct App {
+    process: Process,
+    name: String,
+}
+
+impl App {
+    pub fn launch(target: &str, options: &LaunchOptions) -> Result<App> {
+        let known: HashSet<u64> = Window::all()
+            .map(|ws| ws.iter().map(Window::id).collect())
+            .unwrap_or_default();
+        let (program, name, desktop_id, entry) = match LaunchTarget::parse(target) {
+            LaunchTarget::AppId(id) => {
+                let entry = find_desktop_entry(&id);
+                ("gtk-launch".to_string(), id.clone(), Some(id), entry)
+            }
+            LaunchTarget::AppPath(path) => {
+                let id = Path::new(&path)
+                    .file_name()
+                    .map(|f| f.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone());
+                let name = id.trim_end_matches(".desktop").to_string();
+                ("gtk-launch".to_string(), name, Some(id), Some(PathBuf::from(path)))
+            }
+            LaunchTarget::AppName(command) | LaunchTarget::Command(command) => {
+                let name = Path::new(&command)
+                    .file_name()
+                    .map(|f| f.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| command.clone());
+                (command, name, None, None)
+            }
+        };
+
+        let mut command = Command::new(&program);
+        command
+            .args(desktop_id.iter())
+            .args(&options.args)
+            .envs(options.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        let child = command.spawn().map_err(|e| {
+            Error::new(ErrorCode::AppNotRunning, format!("Could not launch '{}': {}", target, e))
+        })?;
+
+        if desktop_id.is_none() {
+            let app = App {
+                process: Process::spawned(child),
+                name,
+            };
+            if options.wait_for_window {
+                app.wait_for_window(options.timeout_ms)?;
+            }
+            return Ok(app);
+        }
+
+        // gtk-launch exits once the app is started; only a window names the pid.
+        // Other apps open windows meanwhile, so only the entry's own count.
+        let contents = entry.and_then(|path| std::fs::read_to_string(path).ok());
+        let classes = wm_classes(&name, contents.as_deref());
+        let mut launcher = Process::spawned(child);
+        let deadline = Instant::now() + Duration::from_millis(options.timeout_ms);
+        loop {
+            let new_window = Window::all()?.into_iter().find(|w| {
+                !known.contains(&w.id())
+                    && w.pid().is_some()
+                    && w.wm_class().iter().any(|c| classes.iter().any(|k| k.eq_ignore_ascii_case(c)))
+            });
+            if let Some(window) = new_window {
+                return Ok(App {
+                    process: Process::adopted(window.pid().unwrap_or_default()),
+                    name: window.app().unwrap_or(name),
+                });
+            }
+            if let Some((Some(code), _)) = launcher.poll() {
+                if code != 0 {
+                    return Err(Error::new(
+                        ErrorCode::AppNotRunning,
+                        format!("gtk-launch could not start '{}' (exit code {})", target, code),
+                    ));
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::timeout(&format!("first window of {}", target), options.timeout_ms));
+            }
+            std::thread::sleep(Duration::from_millis(POLL_MS));
+        }
+    }
+
+    /// Follow an app that is already running, found by its windows' WM_CLASS
+    pub fn attach(app: &str) -> Result<App> {
+        let pid = Window::for_app(app)?
+            .iter()
+            .find_map(Window::pid)
+            .ok_or_else(|| Error::app_not_running(app))?;
+        Ok(App {
+            process: Process::adopted(pid),
+            name: app.to_string(),
+        })
+    }
+
+    pub fn pid(&self) -> i32 {
+        self.process.pid()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn windows(&self) -> Result<Vec<Window>> {
+        Window::for_pid(self.pid())
+    }
+
+    /// Wait for the app's first window
+    pub fn wait_for_window(&self, timeout_ms: u64) -> Result<Window> {
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        loop {
+            if let Some(window) = self.windows()?.into_iter().next() {
+                return Ok(window);
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::timeout(&format!("first window of {}", self.name), timeout_ms));
+            }
+            std::thread::sleep(Duration::from_millis(POLL_MS));
+        }
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        self.process.is_running()
+    }
+
+    pub fn info(&self) -> AppSnapshot {
+        AppSnapshot {
+            pid: self.pid(),
+            name: self.name.clone(),
+            windows: self
+                .windows()
+                .map(|ws| ws.iter().map(Window::info).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Close the app's windows, then escalate to SIGTERM and SIGKILL
+    pub fn quit(&mut self, grace_ms: u64) -> Result<AppExit> {
+        let windows = self.windows().unwrap_or_default();
+        self.process.end(
+            || !windows.is_empty() && windows.iter().all(|w| w.close().is_ok()),
+            Duration::from_millis(grace_ms),
+        )
+    }
+
+    pub fn kill(&mut self) -> Result<AppExit> {
+        self.process.kill()
+    }
+
+    /// Block until the app exits; `None` waits indefinitely
+    pub fn wait_exit(&mut self, timeout_ms: Option<u64>) -> Result<AppExit> {
+        self.process.wait_exit(timeout_ms)
+    }
+}
+
+/// The `.desktop` file for a desktop id, searched in the XDG data directories
+fn find_desktop_entry(id: &str) -> Option<PathBuf> {
+    let file = if id.ends_with(".desktop") { id.to_string() } else { format!("{}.desktop", id) };
+    let home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/share")));
+    let dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    home.into_iter()
+        .chain(dirs.split(':').map(PathBuf::from))
+        .map(|dir| dir.join("applications").join(&file))
+        .find(|path| path.is_file())
+}
+
+/// WM_CLASS values a desktop entry's windows may carry: its `StartupWMClass`,
+/// the desktop id itself and the program it runs
+fn wm_classes(id: &str, entry: Option<&str>) -> Vec<String> {
+    let mut classes = vec![id.to_string()];
+    let mut in_entry = false;
+    for line in entry.unwrap_or_default().lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "StartupWMClass" => classes.push(value.trim().to_string()),
+            "Exec" => {
+                let program = value
+                    .split_whitespace()
+                    .find(|word| *word != "env" && !word.contains('='));
+                if let Some(program) = program.and_then(|p| Path::new(p).file_name()) {
+                    classes.push(program.to_string_lossy().into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+    classes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wm_classes_come_from_the_desktop_entry() {
+        let entry = "[Desktop Entry]\nName=Calculator\nExec=env GDK_BACKEND=x11 /usr/bin/gnome-calculator %U\n\
+                     StartupWMClass=Gnome-calculator\n[Desktop Action new]\nExec=other\n";
+        assert_eq!(
+            wm_classes("org.gnome.Calculator", Some(entry)),
+            vec!["org.gnome.Calculator", "gnome-calculator", "Gnome-calculator"]
+        );
+        assert_eq!(wm_classes("xterm", None), vec!["xterm"]);
+    }
+}
@@ -0,0 +1,434 @@
+//! X11 selections
+//!
+//! Reading converts the selection into a property on a hidden window and
+//! collects it, incrementally (`INCR`) when the owner sends it in chunks.
+//! X11 has no clipboard storage: the owner serves the data on request and it
+//! is gone once the owner disconnects. Writing therefore forks a detached
+//! server, like `xclip`, which takes ownership and answers requests until
+//! another client takes the selection over, outliving the caller. Change
+//! markers count the XFixes owner-change notifications seen for each
+//! selection.
+
+use super::window::{x11_error, X11};
+use crate::clipboard::{ClipboardContent, ClipboardFormat, Selection};
+use crate::error::{Error, ErrorCode, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::fd::{FromRawFd, RawFd};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+use x11rb::protocol::xproto::{
+    self, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, Property, SelectionNotifyEvent,
+    WindowClass,
+};
+use x11rb::protocol::Event;
+use x11rb::wrapper::ConnectionExt as _;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        TARGETS,
+        INCR,
+        UTF8_STRING,
+        TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+        TEXT_HTML: b"text/html",
+        TEXT_URI_LIST: b"text/uri-list",
+        IMAGE_PNG: b"image/png",
+        BB_SELECTION,
+    }
+}
+
+/// How long the owner gets to answer a request, and to send each chunk
+const TRANSFER_TIMEOUT_MS: u64 = 2000;
+
+const EVENT_POLL_MS: u64 = 5;
+
+impl Atoms {
+    fn selection(&self, selection: Selection) -> xproto::Atom {
+        match selection {
+            Selection::Clipboard => self.CLIPBOARD,
+            Selection::Primary => AtomEnum::PRIMARY.into(),
+        }
+    }
+
+    /// Targets a format is served under, preferred first
+    fn targets(&self, format: ClipboardFormat) -> Vec<xproto::Atom> {
+        match format {
+            ClipboardFormat::Text => vec![self.UTF8_STRING, self.TEXT_PLAIN_UTF8],
+            ClipboardFormat::Html => vec![self.TEXT_HTML],
+            ClipboardFormat::UriList => vec![self.TEXT_URI_LIST],
+            ClipboardFormat::Png => vec![self.IMAGE_PNG],
+        }
+    }
+
+    fn format(&self, target: xproto::Atom) -> Option<ClipboardFormat> {
+        if target == u32::from(AtomEnum::STRING) {
+            return Some(ClipboardFormat::Text);
+        }
+        ClipboardFormat::ALL
+            .into_iter()
+            .find(|&format| self.targets(format).contains(&target))
+    }
+}
+
+/// A hidden window on its own connection, to exchange selections through
+struct Endpoint {
+    x11: Arc<X11>,
+    atoms: Atoms,
+    window: xproto::Window,
+}
+
+impl Endpoint {
+    fn open() -> Result<Self> {
+        let x11 = X11::connect()?;
+        let atoms = Atoms::new(&x11.conn).map_err(x11_error)?.reply().map_err(x11_error)?;
+        let window = x11.conn.generate_id().map_err(x11_error)?;
+        x11.conn
+            .create_window(
+                x11rb::COPY_DEPTH_FROM_PARENT,
+                window,
+                x11.root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                x11rb::COPY_FROM_PARENT,
+                &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            )
+            .map_err(x11_error)?;
+        x11.conn.flush().map_err(x11_error)?;
+        Ok(Self { x11, atoms, window })
+    }
+
+    fn owner(&self, selection: Selection) -> Result<xproto::Window> {
+        Ok(self
+            .x11
+            .conn
+            .get_selection_owner(self.atoms.selection(selection))
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?
+            .owner)
+    }
+
+    /// The selection converted to `target`, `None` when the owner refuses
+    fn convert(&self, selection: Selection, target: xproto::Atom) -> Result<Option<Vec<u8>>> {
+        let conn = &self.x11.conn;
+        conn.convert_selection(
+            self.window,
+            self.atoms.selection(selection),
+            target,
+            self.atoms.BB_SELECTION,
+            x11rb::CURRENT_TIME,
+        )
+        .map_err(x11_error)?;
+        conn.flush().map_err(x11_error)?;
+
+        let notified = self.wait(|event| match event {
+            Event::SelectionNotify(ev) if ev.requestor == self.window => Some(ev.property),
+            _ => None,
+        })?;
+        if notified == u32::from(AtomEnum::NONE) {
+            return Ok(None);
+        }
+
+        let reply = self.take_property()?;
+        if reply.type_ != self.atoms.INCR {
+            return Ok(Some(reply.value));
+        }
+        // Deleting the INCR property asked for the first chunk; an empty one ends the transfer
+        let mut data = Vec::new();
+        loop {
+            self.wait(|event| match event {
+                Event::PropertyNotify(ev)
+                    if ev.window == self.window && ev.atom == self.atoms.BB_SELECTION && ev.state == Property::NEW_VALUE =>
+                {
+                    Some(())
+                }
+                _ => None,
+            })?;
+            let chunk = self.take_property()?;
+            if chunk.value.is_empty() {
+                return Ok(Some(data));
+            }
+            data.extend(chunk.value);
+        }
+    }
+
+    fn take_property(&self) -> Result<xproto::GetPropertyReply> {
+        self.x11
+            .conn
+            .get_property(true, self.window, self.atoms.BB_SELECTION, AtomEnum::ANY, 0, u32::MAX / 4)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)
+    }
+
+    /// First event `pick` accepts, waiting at most `TRANSFER_TIMEOUT_MS`
+    fn wait<T>(&self, pick: impl Fn(&Event) -> Option<T>) -> Result<T> {
+        let deadline = Instant::now() + Duration::from_millis(TRANSFER_TIMEOUT_MS);
+        loop {
+            match self.x11.conn.poll_for_event().map_err(x11_error)? {
+                Some(event) => {
+                    if let Some(picked) = pick(&event) {
+                        return Ok(picked);
+                    }
+                }
+                None if Instant::now() > deadline => {
+                    return Err(Error::timeout("selection transfer", TRANSFER_TIMEOUT_MS)
+                        .with_suggestions(vec!["The selection owner did not answer; try copying again".to_string()]));
+                }
+                None => thread::sleep(Duration::from_millis(EVENT_POLL_MS)),
+            }
+        }
+    }
+}
+
+impl Drop for Endpoint {
+    fn drop(&mut self) {
+        let _ = self.x11.conn.destroy_window(self.window);
+        let _ = self.x11.conn.flush();
+    }
+}
+
+pub fn formats(selection: Selection) -> Result<Vec<ClipboardFormat>> {
+    let endpoint = Endpoint::open()?;
+    if endpoint.owner(selection)? == x11rb::NONE {
+        return Ok(Vec::new());
+    }
+    let Some(bytes) = endpoint.convert(selection, endpoint.atoms.TARGETS)? else {
+        return Ok(Vec::new());
+    };
+    let mut formats = Vec::new();
+    for target in bytes.chunks_exact(4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]])) {
+        if let Some(format) = endpoint.atoms.format(target).filter(|f| !formats.contains(f)) {
+            formats.push(format);
+        }
+    }
+    Ok(formats)
+}
+
+pub fn get(selection: Selection, format: ClipboardFormat) -> Result<Option<ClipboardContent>> {
+    let endpoint = Endpoint::open()?;
+    if endpoint.owner(selection)? == x11rb::NONE {
+        return Ok(None);
+    }
+    for target in endpoint.atoms.targets(format) {
+        if let Some(bytes) = endpoint.convert(selection, target)? {
+            return ClipboardContent::from_bytes(format, bytes).map(Some);
+        }
+    }
+    // Old clients only offer Latin-1 text
+    if format == ClipboardFormat::Text {
+        if let Some(bytes) = endpoint.convert(selection, AtomEnum::STRING.into())? {
+            return Ok(Some(ClipboardContent::Text(bytes.iter().map(|&b| b as char).collect())));
+        }
+    }
+    Ok(None)
+}
+
+pub fn set(selection: Selection, contents: &[ClipboardContent]) -> Result<()> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(failed(&std::io::Error::last_os_error().to_string()));
+    }
+    let [read, write] = fds;
+    match unsafe { libc::fork() } {
+        -1 => {
+            let reason = std::io::Error::last_os_error().to_string();
+            unsafe {
+                libc::close(read);
+                libc::close(write);
+            }
+            Err(failed(&reason))
+        }
+        0 => {
+            // Fork again so the server is nobody's child to reap
+            unsafe { libc::close(read) };
+            if unsafe { libc::fork() } == 0 {
+                unsafe { libc::setsid() };
+                detach(write);
+                run_server(selection, contents, unsafe { File::from_raw_fd(write) });
+            }
+            unsafe { libc::_exit(0) }
+        }
+        child => {
+            unsafe {
+                libc::close(write);
+                libc::waitpid(child, std::ptr::null_mut(), 0);
+            }
+            // The server reports whether it took ownership, then closes its end
+            let mut status = Vec::new();
+            let _ = unsafe { File::from_raw_fd(read) }.read_to_end(&mut status);
+            match serde_json::from_slice::<Option<Error>>(&status) {
+                Ok(None) => Ok(()),
+                Ok(Some(e)) => Err(e),
+                Err(_) => Err(failed("the selection server exited before taking ownership")),
+            }
+        }
+    }
+}
+
+/// Let go of everything inherited, so the server holds none of the caller's
+/// files, locks or pipes open while it lives on
+fn detach(keep: RawFd) {
+    let open: Vec<RawFd> = std::fs::read_dir("/proc/self/fd")
+        .map(|dir| {
+            dir.flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    for fd in open.into_iter().filter(|&fd| fd > 2 && fd != keep) {
+        unsafe { libc::close(fd) };
+    }
+    unsafe {
+        let null = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+        if null >= 0 {
+            for fd in 0..=2 {
+                libc::dup2(null, fd);
+            }
+            if null > 2 {
+                libc::close(null);
+            }
+        }
+    }
+}
+
+/// Take ownership, report how that went on `status`, and serve; never returns
+fn run_server(selection: Selection, contents: &[ClipboardContent], mut status: File) -> ! {
+    let owned = own(selection, contents);
+    let _ = status.write_all(&serde_json::to_vec(&owned.as_ref().err()).unwrap_or_default());
+    drop(status);
+    if let Ok((endpoint, atom)) = owned {
+        serve(&endpoint, atom, contents);
+    }
+    unsafe { libc::_exit(0) }
+}
+
+fn own(selection: Selection, contents: &[ClipboardContent]) -> Result<(Endpoint, xproto::Atom)> {
+    let endpoint = Endpoint::open()?;
+    let limit = endpoint.x11.conn.maximum_request_bytes().saturating_sub(64);
+    if let Some(big) = contents.iter().find(|c| c.to_bytes().len() > limit) {
+        return Err(Error::new(
+            ErrorCode::ActionFailed,
+            format!("{} content is larger than the X server's {} byte request limit", big.format(), limit),
+        ));
+    }
+
+    let atom = endpoint.atoms.selection(selection);
+    endpoint
+        .x11
+        .conn
+        .set_selection_owner(endpoint.window, atom, x11rb::CURRENT_TIME)
+        .map_err(x11_error)?;
+    if endpoint.owner(selection)? != endpoint.window {
+        return Err(Error::new(
+            ErrorCode::ActionFailed,
+            "Could not take ownership of the selection".to_string(),
+        ));
+    }
+    Ok((endpoint, atom))
+}
+
+fn failed(reason: &str) -> Error {
+    Error::action_failed("clipboard set", reason)
+}
+
+/// Answer requests for the selection until another client owns it
+fn serve(endpoint: &Endpoint, selection: xproto::Atom, contents: &[ClipboardContent]) {
+    let conn = &endpoint.x11.conn;
+    let atoms = &endpoint.atoms;
+    while let Ok(event) = conn.wait_for_event() {
+        let request = match event {
+            Event::SelectionRequest(request) => request,
+            Event::SelectionClear(clear) if clear.selection == selection => break,
+            _ => continue,
+        };
+        // Obsolete clients leave the property out and expect the target name
+        let property = if request.property == x11rb::NONE {
+            request.target
+        } else {
+            request.property
+        };
+        let served = if request.target == atoms.TARGETS {
+            let mut targets = vec![atoms.TARGETS];
+            targets.extend(contents.iter().flat_map(|c| atoms.targets(c.format())));
+            conn.change_property32(PropMode::REPLACE, request.requestor, property, AtomEnum::ATOM, &targets)
+                .is_ok()
+        } else if let Some(content) = contents.iter().find(|c| atoms.targets(c.format()).contains(&request.target)) {
+            conn.change_property8(PropMode::REPLACE, request.requestor, property, request.target, &content.to_bytes())
+                .is_ok()
+        } else {
+            false
+        };
+
+        let notify = SelectionNotifyEvent {
+            response_type: xproto::SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property: if served { property } else { x11rb::NONE },
+        };
+        let _ = conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify);
+        let _ = conn.flush();
+    }
+}
+
+/// Owner changes seen per selection, on a connection kept for the process
+struct ChangeCounter {
+    endpoint: Endpoint,
+    counts: HashMap<xproto::Atom, u64>,
+}
+
+impl ChangeCounter {
+    fn open() -> Result<Self> {
+        let endpoint = Endpoint::open()?;
+        let conn = &endpoint.x11.conn;
+        conn.xfixes_query_version(5, 0)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(|e| {
+                Error::new(ErrorCode::NotImplemented, format!("XFixes is not available: {}", e))
+            })?;
+        let mask = SelectionEventMask::SET_SELECTION_OWNER
+            | SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+        for selection in [Selection::Clipboard, Selection::Primary] {
+            conn.xfixes_select_selection_input(endpoint.window, endpoint.atoms.selection(selection), mask)
+                .map_err(x11_error)?;
+        }
+        conn.flush().map_err(x11_error)?;
+        Ok(Self {
+            endpoint,
+            counts: HashMap::new(),
+        })
+    }
+
+    fn count(&mut self, selection: Selection) -> Result<u64> {
+        while let Some(event) = self.endpoint.x11.conn.poll_for_event().map_err(x11_error)? {
+            if let Event::XfixesSelectionNotify(ev) = event {
+                *self.counts.entry(ev.selection).or_default() += 1;
+            }
+        }
+        let atom = self.endpoint.atoms.selection(selection);
+        Ok(self.counts.get(&atom).copied().unwrap_or_default())
+    }
+}
+
+pub fn change_marker(selection: Selection) -> Result<u64> {
+    static COUNTER: OnceLock<Mutex<Option<ChangeCounter>>> = OnceLock::new();
+    let mut counter = COUNTER.get_or_init(Mutex::default).lock().unwrap();
+    if counter.is_none() {
+        *counter = Some(ChangeCounter::open()?);
+    }
+    counter.as_mut().map_or(Ok(0), |c| c.count(selection))
+}
@@ -0,0 +1,235 @@
+//! Keyboard and wheel input over XTest
+//!
+//! Characters the current keymap has a key for are pressed on that key,
+//! with Shift held for the ones on its shifted level.
+//! Anything else gets mapped onto a spare keycode (one with no keysyms) just
+//! long enough to press it, the way xdotool does, so the layout never limits
+//! what can be typed. Without a spare keycode, and for long texts, text is
+//! pasted with ctrl+v instead. The wheel is buttons 4 to 7, one click per
+//! line.
+
+use super::window::{x11_error, X11};
+use crate::error::{Error, ErrorCode, Result};
+use crate::scroll::Scroll;
+use crate::text_entry::{
+    char_keysym, enter_with, paste_text, EntryStrategy, RunError, TextEntryBackend, TextEntryOptions,
+    TextEntryReport,
+};
+use std::thread;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{self, ConnectionExt as _};
+use x11rb::protocol::xtest::ConnectionExt as _;
+
+/// Time for clients to pick up a keymap change before the key is pressed
+const REMAP_SETTLE_MS: u64 = 20;
+
+const XK_SHIFT_L: u32 = 0xffe1;
+const XK_CONTROL_L: u32 = 0xffe3;
+const XK_V: u32 = 0x76;
+
+/// Type text, failing when any character could not be typed; `enter_text`
+/// reports them instead
+pub fn type_text(text: &str) -> Result<()> {
+    enter_text(text, &TextEntryOptions::default())?.into_result()?;
+    Ok(())
+}
+
+/// Type text through the strategy stack, see `crate::text_entry`
+pub fn enter_text(text: &str, options: &TextEntryOptions) -> Result<TextEntryReport> {
+    let mut backend = Keymap::load(X11::connect()?, Duration::from_millis(options.key_delay_ms))?;
+    let report = enter_with(&mut backend, text, options);
+    backend.restore()?;
+    Ok(report)
+}
+
+/// Scroll with the wheel at a screen point, see `crate::scroll`
+pub fn scroll_at(x: i32, y: i32, scroll: &Scroll) -> Result<()> {
+    let x11 = X11::connect()?;
+    let (x, y) = (x.clamp(0, i16::MAX as i32) as i16, y.clamp(0, i16::MAX as i32) as i16);
+    x11.conn
+        .xtest_fake_input(xproto::MOTION_NOTIFY_EVENT, 0, x11rb::CURRENT_TIME, x11.root, x, y, 0)
+        .map_err(x11_error)?;
+    let (dx, dy) = scroll.in_lines();
+    let clicks = [(dy, 5u8, 4u8), (dx, 7, 6)];
+    for (lines, forward, back) in clicks {
+        let button = if lines > 0 { forward } else { back };
+        for _ in 0..lines.unsigned_abs() {
+            for kind in [xproto::BUTTON_PRESS_EVENT, xproto::BUTTON_RELEASE_EVENT] {
+                x11.conn
+                    .xtest_fake_input(kind, button, x11rb::CURRENT_TIME, x11.root, 0, 0, 0)
+                    .map_err(x11_error)?;
+            }
+        }
+    }
+    x11.conn.get_input_focus().map_err(x11_error)?.reply().map_err(x11_error)?;
+    Ok(())
+}
+
+struct Keymap {
+    x11: std::sync::Arc<X11>,
+    min_keycode: u8,
+    per_keycode: u8,
+    keysyms: Vec<u32>,
+    /// Keycode borrowed for keysyms the keymap lacks
+    spare: Option<u8>,
+    remapped: bool,
+    key_delay: Duration,
+}
+
+impl Keymap {
+    fn load(x11: std::sync::Arc<X11>, key_delay: Duration) -> Result<Self> {
+        let setup = x11.conn.setup();
+        let (min_keycode, max_keycode) = (setup.min_keycode, setup.max_keycode);
+        let mapping = x11
+            .conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        let per_keycode = mapping.keysyms_per_keycode.max(1);
+        let spare = mapping
+            .keysyms
+            .chunks(per_keycode as usize)
+            .rposition(|syms| syms.iter().all(|&s| s == 0))
+            .map(|i| min_keycode + i as u8);
+        Ok(Self {
+            x11,
+            min_keycode,
+            per_keycode,
+            keysyms: mapping.keysyms,
+            spare,
+            remapped: false,
+            key_delay,
+        })
+    }
+
+    /// Keycode whose unshifted keysym is `keysym`
+    fn keycode(&self, keysym: u32) -> Option<u8> {
+        self.keycode_at(0, keysym)
+    }
+
+    fn keycode_at(&self, level: usize, keysym: u32) -> Option<u8> {
+        self.keysyms
+            .chunks(self.per_keycode as usize)
+            .position(|syms| syms.get(level) == Some(&keysym))
+            .map(|i| self.min_keycode + i as u8)
+    }
+
+    /// Keycode that types `keysym`, with the Shift keycode to hold when it
+    /// is on the shifted level
+    fn locate(&self, keysym: u32) -> Option<(u8, Option<u8>)> {
+        if let Some(keycode) = self.keycode(keysym) {
+            return Some((keycode, None));
+        }
+        let shift = self.keycode(XK_SHIFT_L)?;
+        self.keycode_at(1, keysym).map(|keycode| (keycode, Some(shift)))
+    }
+
+    fn remap_spare(&mut self, keysym: u32) -> Result<u8> {
+        let spare = self.spare.ok_or_else(|| {
+            Error::new(ErrorCode::ActionFailed, "No spare keycode to map the character onto".to_string())
+        })?;
+        let syms = vec![keysym; self.per_keycode as usize];
+        self.x11
+            .conn
+            .change_keyboard_mapping(1, spare, self.per_keycode, &syms)
+            .map_err(x11_error)?;
+        self.remapped = true;
+        self.sync()?;
+        thread::sleep(Duration::from_millis(REMAP_SETTLE_MS));
+        Ok(spare)
+    }
+
+    fn tap(&self, keycode: u8) -> Result<()> {
+        for kind in [xproto::KEY_PRESS_EVENT, xproto::KEY_RELEASE_EVENT] {
+            self.fake_key(kind, keycode)?;
+        }
+        self.sync()
+    }
+
+    fn fake_key(&self, kind: u8, keycode: u8) -> Result<()> {
+        self.x11
+            .conn
+            .xtest_fake_input(kind, keycode, x11rb::CURRENT_TIME, self.x11.root, 0, 0, 0)
+            .map_err(x11_error)?;
+        Ok(())
+    }
+
+    /// Tap `keycode` with the `modifier` key held down
+    fn tap_with(&self, modifier: u8, keycode: u8) -> Result<()> {
+        self.fake_key(xproto::KEY_PRESS_EVENT, modifier)?;
+        let tapped = self.tap(keycode);
+        // Release even when the tap failed, or the modifier stays stuck down
+        let released = self.fake_key(xproto::KEY_RELEASE_EVENT, modifier).and_then(|()| self.sync());
+        tapped.and(released)
+    }
+
+    /// Press ctrl+v
+    fn press_paste(&self) -> Result<()> {
+        let (Some(control), Some(v)) = (self.keycode(XK_CONTROL_L), self.keycode(XK_V)) else {
+            return Err(Error::action_failed("paste", "the keymap has no Control_L or v key"));
+        };
+        self.tap_with(control, v)
+    }
+
+    fn type_char(&mut self, c: char) -> Result<()> {
+        let keysym = char_keysym(c).ok_or_else(|| Error::action_failed("type", "character has no keysym"))?;
+        match self.locate(keysym) {
+            Some((keycode, None)) => self.tap(keycode),
+            Some((keycode, Some(shift))) => self.tap_with(shift, keycode),
+            None => {
+                let spare = self.remap_spare(keysym)?;
+                self.tap(spare)
+            }
+        }
+    }
+
+    /// Round trip, so the server has handled everything sent so far
+    fn sync(&self) -> Result<()> {
+        self.x11.conn.get_input_focus().map_err(x11_error)?.reply().map_err(x11_error)?;
+        Ok(())
+    }
+
+    /// Give the spare keycode its empty mapping back
+    fn restore(&mut self) -> Result<()> {
+        let Some(spare) = self.spare.filter(|_| self.remapped) else {
+            return Ok(());
+        };
+        thread::sleep(Duration::from_millis(REMAP_SETTLE_MS));
+        let syms = vec![0; self.per_keycode as usize];
+        self.x11
+            .conn
+            .change_keyboard_mapping(1, spare, self.per_keycode, &syms)
+            .map_err(x11_error)?;
+        self.remapped = false;
+        self.sync()
+    }
+}
+
+impl TextEntryBackend for Keymap {
+    fn strategies(&self) -> Vec<EntryStrategy> {
+        vec![EntryStrategy::Keysym, EntryStrategy::Paste]
+    }
+
+    fn can_type(&self, strategy: EntryStrategy, c: char) -> bool {
+        match strategy {
+            EntryStrategy::Keysym => {
+                char_keysym(c).is_some_and(|sym| self.spare.is_some() || self.locate(sym).is_some())
+            }
+            EntryStrategy::Paste => !c.is_control() || c == '\n' || c == '\t',
+            EntryStrategy::Unicode => false,
+        }
+    }
+
+    fn type_run(&mut self, strategy: EntryStrategy, text: &str) -> std::result::Result<(), RunError> {
+        if strategy == EntryStrategy::Paste {
+            return Ok(paste_text(text, || self.press_paste())?);
+        }
+        for (typed, c) in text.chars().enumerate() {
+            self.type_char(c).map_err(|error| RunError { typed, error })?;
+            thread::sleep(self.key_delay);
+        }
+        Ok(())
+    }
+}
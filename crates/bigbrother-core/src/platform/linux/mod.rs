@@ -1,12 +1,25 @@
 //! Linux platform implementation
 //!
-//! TODO: Implement using AT-SPI2, libatspi, or similar.
+//! Window management is implemented over X11 with EWMH (`Window`), app
+//! launching on top of it (`App`), typing over XTest (`enter_text`) and the
+//! CLIPBOARD and PRIMARY selections (`crate::clipboard`).
+//!
+//! TODO: Implement the rest using AT-SPI2, libatspi, or similar.
 //!
 //! Planned approach:
 //! - AT-SPI2 for accessibility tree
 //! - XTest or libevdev for input injection
 //! - D-Bus for app enumeration
 
+mod app;
+pub(crate) mod clipboard;
+mod input;
+mod window;
+
+pub use app::App;
+pub use input::{enter_text, scroll_at, type_text};
+pub use window::Window;
+
 use crate::{Error, Result};
 
 /// Check if the process has accessibility permissions
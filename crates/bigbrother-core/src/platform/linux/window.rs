@@ -0,0 +1,373 @@
+//! Window management over X11 with EWMH
+//!
+//! Works the way pagers and taskbars do: windows come from the root
+//! window's `_NET_CLIENT_LIST`, and changes are requested from the window
+//! manager with client messages sent to the root window. Needs an X server
+//! (XWayland covers X11 clients on Wayland) and an EWMH-compliant window
+//! manager.
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::tree::Bounds;
+use crate::window::{pick_window, window_not_found, WindowInfo};
+use std::sync::Arc;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{self, AtomEnum, ClientMessageEvent, ConnectionExt, EventMask};
+use x11rb::rust_connection::RustConnection;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_CLIENT_LIST,
+        _NET_ACTIVE_WINDOW,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_MOVERESIZE_WINDOW,
+        _NET_CLOSE_WINDOW,
+        WM_CHANGE_STATE,
+        UTF8_STRING,
+    }
+}
+
+/// `_NET_WM_STATE` actions
+const STATE_REMOVE: u32 = 0;
+const STATE_ADD: u32 = 1;
+
+/// Source indication in client messages: a pager, i.e. a direct user request
+const SOURCE_PAGER: u32 = 2;
+
+/// ICCCM `IconicState`, requested through `WM_CHANGE_STATE` to minimize
+const ICONIC_STATE: u32 = 3;
+
+pub(super) struct X11 {
+    pub(super) conn: RustConnection,
+    pub(super) root: xproto::Window,
+    atoms: Atoms,
+}
+
+impl X11 {
+    pub(super) fn connect() -> Result<Arc<Self>> {
+        let (conn, screen) = x11rb::connect(None).map_err(|e| {
+            Error::new(ErrorCode::Unknown, format!("Cannot connect to the X server: {}", e))
+                .with_suggestions(vec![
+                    "Check that DISPLAY is set".to_string(),
+                    "On Wayland only XWayland windows can be managed".to_string(),
+                ])
+        })?;
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::new(&conn).map_err(x11_error)?.reply().map_err(x11_error)?;
+        Ok(Arc::new(Self { conn, root, atoms }))
+    }
+
+    fn property(&self, window: xproto::Window, property: u32, type_: impl Into<u32>) -> Option<xproto::GetPropertyReply> {
+        self.conn
+            .get_property(false, window, property, type_, 0, u32::MAX)
+            .ok()?
+            .reply()
+            .ok()
+    }
+
+    fn property32(&self, window: xproto::Window, property: u32, type_: impl Into<u32>) -> Vec<u32> {
+        self.property(window, property, type_)
+            .and_then(|r| r.value32().map(|v| v.collect()))
+            .unwrap_or_default()
+    }
+
+    /// Ask the window manager for a change to `window`
+    fn request(&self, window: xproto::Window, message_type: u32, data: [u32; 5]) -> Result<()> {
+        let event = ClientMessageEvent::new(32, window, message_type, data);
+        self.conn
+            .send_event(
+                false,
+                self.root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )
+            .map_err(x11_error)?;
+        self.conn.flush().map_err(x11_error)?;
+        Ok(())
+    }
+}
+
+pub(super) fn x11_error(e: impl std::fmt::Display) -> Error {
+    Error::new(ErrorCode::Unknown, format!("X11 request failed: {}", e))
+}
+
+/// A top-level window managed by the window manager
+#[derive(Clone)]
+pub struct Window {
+    x11: Arc<X11>,
+    id: xproto::Window,
+}
+
+impl Window {
+    /// Every managed window, in the window manager's order
+    pub fn all() -> Result<Vec<Window>> {
+        let x11 = X11::connect()?;
+        let ids = x11.property32(x11.root, x11.atoms._NET_CLIENT_LIST, AtomEnum::WINDOW);
+        Ok(ids
+            .into_iter()
+            .map(|id| Window { x11: x11.clone(), id })
+            .collect())
+    }
+
+    /// Windows whose WM_CLASS matches the app name, ignoring case
+    pub fn for_app(app: &str) -> Result<Vec<Window>> {
+        Ok(Self::all()?
+            .into_iter()
+            .filter(|w| w.app().is_some_and(|a| a.eq_ignore_ascii_case(app)))
+            .collect())
+    }
+
+    /// Windows whose `_NET_WM_PID` is this process
+    pub fn for_pid(pid: i32) -> Result<Vec<Window>> {
+        Ok(Self::all()?
+            .into_iter()
+            .filter(|w| w.pid() == Some(pid))
+            .collect())
+    }
+
+    /// The app's window with this title, see `crate::window::pick_window`
+    pub fn find(app: &str, title: &str) -> Result<Window> {
+        let windows = Self::for_app(app)?;
+        let titles: Vec<String> = windows.iter().map(|w| w.title().unwrap_or_default()).collect();
+        match pick_window(&titles, title) {
+            Some(i) => Ok(windows[i].clone()),
+            None => Err(window_not_found(app, title, &titles)),
+        }
+    }
+
+    /// The active window, if the window manager reports one
+    pub fn focused() -> Result<Option<Window>> {
+        let x11 = X11::connect()?;
+        let active = x11
+            .property32(x11.root, x11.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW)
+            .first()
+            .copied()
+            .filter(|&id| id != 0);
+        Ok(active.map(|id| Window { x11, id }))
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id as u64
+    }
+
+    pub fn title(&self) -> Option<String> {
+        let atoms = &self.x11.atoms;
+        self.x11
+            .property(self.id, atoms._NET_WM_NAME, atoms.UTF8_STRING)
+            .filter(|r| !r.value.is_empty())
+            .map(|r| String::from_utf8_lossy(&r.value).into_owned())
+            .or_else(|| {
+                // ICCCM WM_NAME is Latin-1
+                self.x11
+                    .property(self.id, AtomEnum::WM_NAME.into(), AtomEnum::STRING)
+                    .map(|r| r.value.iter().map(|&b| b as char).collect())
+            })
+    }
+
+    /// Application name from the class part of WM_CLASS
+    pub fn app(&self) -> Option<String> {
+        self.x11
+            .property(self.id, AtomEnum::WM_CLASS.into(), AtomEnum::STRING)
+            .and_then(|r| wm_class_name(&r.value))
+    }
+
+    /// Both parts of WM_CLASS, instance first
+    pub fn wm_class(&self) -> Vec<String> {
+        self.x11
+            .property(self.id, AtomEnum::WM_CLASS.into(), AtomEnum::STRING)
+            .map(|r| {
+                r.value
+                    .split(|&b| b == 0)
+                    .filter(|p| !p.is_empty())
+                    .map(|p| String::from_utf8_lossy(p).into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn pid(&self) -> Option<i32> {
+        self.x11
+            .property32(self.id, self.x11.atoms._NET_WM_PID, AtomEnum::CARDINAL)
+            .first()
+            .map(|&pid| pid as i32)
+    }
+
+    /// Client area in root window coordinates, without the frame
+    pub fn bounds(&self) -> Option<Bounds> {
+        let conn = &self.x11.conn;
+        let geometry = conn.get_geometry(self.id).ok()?.reply().ok()?;
+        let origin = conn
+            .translate_coordinates(self.id, self.x11.root, 0, 0)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some(Bounds {
+            x: origin.dst_x as f64,
+            y: origin.dst_y as f64,
+            width: geometry.width as f64,
+            height: geometry.height as f64,
+        })
+    }
+
+    pub fn is_focused(&self) -> bool {
+        let x11 = &self.x11;
+        x11.property32(x11.root, x11.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW)
+            .first()
+            == Some(&self.id)
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.states().contains(&self.x11.atoms._NET_WM_STATE_HIDDEN)
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        let states = self.states();
+        let atoms = &self.x11.atoms;
+        states.contains(&atoms._NET_WM_STATE_MAXIMIZED_VERT)
+            && states.contains(&atoms._NET_WM_STATE_MAXIMIZED_HORZ)
+    }
+
+    pub fn info(&self) -> WindowInfo {
+        WindowInfo {
+            id: Some(self.id()),
+            app: self.app(),
+            pid: self.pid(),
+            title: self.title().unwrap_or_default(),
+            bounds: self.bounds(),
+            focused: self.is_focused(),
+            minimized: self.is_minimized(),
+            maximized: self.is_maximized(),
+        }
+    }
+
+    pub fn move_to(&self, x: f64, y: f64) -> Result<()> {
+        let data = moveresize_data(Some(x as i32), Some(y as i32), None, None);
+        self.x11.request(self.id, self.x11.atoms._NET_MOVERESIZE_WINDOW, data)
+    }
+
+    pub fn resize(&self, width: f64, height: f64) -> Result<()> {
+        let data = moveresize_data(None, None, Some(width as u32), Some(height as u32));
+        self.x11.request(self.id, self.x11.atoms._NET_MOVERESIZE_WINDOW, data)
+    }
+
+    pub fn set_bounds(&self, bounds: Bounds) -> Result<()> {
+        let data = moveresize_data(
+            Some(bounds.x as i32),
+            Some(bounds.y as i32),
+            Some(bounds.width as u32),
+            Some(bounds.height as u32),
+        );
+        self.x11.request(self.id, self.x11.atoms._NET_MOVERESIZE_WINDOW, data)
+    }
+
+    pub fn minimize(&self) -> Result<()> {
+        self.x11
+            .request(self.id, self.x11.atoms.WM_CHANGE_STATE, [ICONIC_STATE, 0, 0, 0, 0])
+    }
+
+    pub fn maximize(&self) -> Result<()> {
+        self.change_maximized(STATE_ADD)
+    }
+
+    /// Undo minimize and maximize
+    pub fn restore(&self) -> Result<()> {
+        self.change_maximized(STATE_REMOVE)?;
+        if self.is_minimized() {
+            self.raise()?;
+        }
+        Ok(())
+    }
+
+    /// Ask the window to close, as its close button would; the app may refuse
+    pub fn close(&self) -> Result<()> {
+        self.x11
+            .request(self.id, self.x11.atoms._NET_CLOSE_WINDOW, [0, SOURCE_PAGER, 0, 0, 0])
+    }
+
+    /// Activate the window: unminimize, raise and focus it
+    pub fn raise(&self) -> Result<()> {
+        self.x11
+            .request(self.id, self.x11.atoms._NET_ACTIVE_WINDOW, [SOURCE_PAGER, 0, 0, 0, 0])
+    }
+
+    fn change_maximized(&self, action: u32) -> Result<()> {
+        let atoms = &self.x11.atoms;
+        let data = [
+            action,
+            atoms._NET_WM_STATE_MAXIMIZED_VERT,
+            atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+            SOURCE_PAGER,
+            0,
+        ];
+        self.x11.request(self.id, atoms._NET_WM_STATE, data)
+    }
+
+    fn states(&self) -> Vec<u32> {
+        self.x11
+            .property32(self.id, self.x11.atoms._NET_WM_STATE, AtomEnum::ATOM)
+    }
+}
+
+impl std::fmt::Debug for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Window")
+            .field("id", &self.id)
+            .field("title", &self.title())
+            .finish()
+    }
+}
+
+/// `_NET_MOVERESIZE_WINDOW` data; only the given fields are changed
+fn moveresize_data(x: Option<i32>, y: Option<i32>, width: Option<u32>, height: Option<u32>) -> [u32; 5] {
+    let mut flags = SOURCE_PAGER << 12; // gravity 0: keep the window's own
+    for (bit, present) in [x.is_some(), y.is_some(), width.is_some(), height.is_some()]
+        .into_iter()
+        .enumerate()
+    {
+        if present {
+            flags |= 1 << (8 + bit);
+        }
+    }
+    [
+        flags,
+        x.unwrap_or(0) as u32,
+        y.unwrap_or(0) as u32,
+        width.unwrap_or(0),
+        height.unwrap_or(0),
+    ]
+}
+
+/// Class part of a WM_CLASS value (`instance\0Class\0`)
+fn wm_class_name(value: &[u8]) -> Option<String> {
+    let mut parts = value.split(|&b| b == 0).filter(|p| !p.is_empty());
+    let instance = parts.next()?;
+    let class = parts.next().unwrap_or(instance);
+    Some(String::from_utf8_lossy(class).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moveresize_sets_only_given_fields() {
+        let [flags, x, y, w, h] = moveresize_data(Some(-20), Some(40), None, None);
+        assert_eq!(flags, (2 << 12) | (1 << 8) | (1 << 9));
+        assert_eq!((x as i32, y, w, h), (-20, 40, 0, 0));
+
+        let [flags, _, _, w, h] = moveresize_data(None, None, Some(800), Some(600));
+        assert_eq!(flags, (2 << 12) | (1 << 10) | (1 << 11));
+        assert_eq!((w, h), (800, 600));
+    }
+
+    #[test]
+    fn wm_class_prefers_the_class() {
+        assert_eq!(wm_class_name(b"navigator\0firefox\0").as_deref(), Some("firefox"));
+        assert_eq!(wm_class_name(b"xterm\0").as_deref(), Some("xterm"));
+        assert_eq!(wm_class_name(b""), None);
+    }
+}
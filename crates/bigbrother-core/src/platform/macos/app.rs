@@ -0,0 +1,208 @@
+//! Launching apps through LaunchServices
+//!
+//! Bundles, bundle ids and app names are opened with `open`, which exits
+//! as soon as LaunchServices has the app; the app itself is then picked out
+//! of the workspace's running apps. Executable paths are spawned directly.
+
+use super::Window;
+use crate::apps;
+use crate::error::{Error, ErrorCode, Result};
+use crate::launch::{pick_app, AppExit, AppNames, AppSnapshot, LaunchOptions, LaunchTarget, Process};
+use cidre::ns;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const POLL_MS: u64 = 100;
+
+/// How long a fresh instance gets before an existing one is taken instead
+const REUSE_AFTER_MS: u64 = 1_000;
+
+/// A launched or attached app
+pub struct App {
+    process: Process,
+    name: String,
+}
+
+impl App {
+    pub fn launch(target: &str, options: &LaunchOptions) -> Result<App> {
+        let known: HashSet<i32> = apps::running_apps().into_iter().map(|(_, pid)| pid).collect();
+
+        let app = match LaunchTarget::parse(target) {
+            LaunchTarget::Command(path) => {
+                let child = Command::new(&path)
+                    .args(&options.args)
+                    .envs(options.env.iter().map(|(k, v)| (k, v)))
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map_err(|e| launch_failed(target, &e.to_string()))?;
+                let name = Path::new(&path)
+                    .file_name()
+                    .map(|f| f.to_string_lossy().into_owned())
+                    .unwrap_or(path);
+                App {
+                    process: Process::spawned(child),
+                    name,
+                }
+            }
+            open_target => {
+                let (wanted, mut open) = open_command(&open_target);
+                for (key, value) in &options.env {
+                    open.arg("--env").arg(format!("{}={}", key, value));
+                }
+                if !options.args.is_empty() {
+                    open.arg("--args").args(&options.args);
+                }
+                let output = open.output().map_err(|e| launch_failed(target, &e.to_string()))?;
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    return Err(launch_failed(target, stderr.trim()));
+                }
+                wait_for_app(&wanted, &known, options.timeout_ms)?
+            }
+        };
+
+        if options.wait_for_window {
+            app.wait_for_window(options.timeout_ms)?;
+        }
+        Ok(app)
+    }
+
+    /// Follow an app that is already running
+    pub fn attach(app: &str) -> Result<App> {
+        let pid = apps::find_app_pid(app).map_err(|_| Error::app_not_running(app))?;
+        Ok(App {
+            process: Process::adopted(pid),
+            name: app.to_string(),
+        })
+    }
+
+    pub fn pid(&self) -> i32 {
+        self.process.pid()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn windows(&self) -> Result<Vec<Window>> {
+        Window::for_pid(self.pid())
+    }
+
+    /// Wait for the app's first window
+    pub fn wait_for_window(&self, timeout_ms: u64) -> Result<Window> {
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        loop {
+            if let Some(window) = self.windows()?.into_iter().next() {
+                return Ok(window);
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::timeout(&format!("first window of {}", self.name), timeout_ms));
+            }
+            std::thread::sleep(Duration::from_millis(POLL_MS));
+        }
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        self.process.is_running()
+    }
+
+    pub fn info(&self) -> AppSnapshot {
+        AppSnapshot {
+            pid: self.pid(),
+            name: self.name.clone(),
+            windows: self
+                .windows()
+                .map(|ws| ws.iter().map(Window::info).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Ask the app to quit as if from its menu, then escalate to SIGTERM and
+    /// SIGKILL
+    pub fn quit(&mut self, grace_ms: u64) -> Result<AppExit> {
+        let pid = self.pid();
+        self.process.end(
+            || ns::RunningApp::with_pid(pid).is_some_and(|app| app.terminate()),
+            Duration::from_millis(grace_ms),
+        )
+    }
+
+    pub fn kill(&mut self) -> Result<AppExit> {
+        self.process.kill()
+    }
+
+    /// Block until the app exits; `None` waits indefinitely
+    pub fn wait_exit(&mut self, timeout_ms: Option<u64>) -> Result<AppExit> {
+        self.process.wait_exit(timeout_ms)
+    }
+}
+
+/// `open` invocation for a target, with the name the app will run under
+fn open_command(target: &LaunchTarget) -> (String, Command) {
+    let mut open = Command::new("open");
+    let wanted = match target {
+        LaunchTarget::AppId(id) => {
+            open.arg("-b").arg(id);
+            id.clone()
+        }
+        LaunchTarget::AppPath(path) => {
+            open.arg(path);
+            Path::new(path)
+                .file_stem()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone())
+        }
+        LaunchTarget::AppName(name) | LaunchTarget::Command(name) => {
+            open.arg("-a").arg(name);
+            name.clone()
+        }
+    };
+    (wanted, open)
+}
+
+/// Wait for the opened app to show up among the running apps
+///
+/// A newly started process wins. If none has appeared after
+/// `REUSE_AFTER_MS`, `open` activated an instance that was already running,
+/// and that one is adopted.
+fn wait_for_app(wanted: &str, known: &HashSet<i32>, timeout_ms: u64) -> Result<App> {
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(timeout_ms);
+    loop {
+        let (fresh, existing): (Vec<_>, Vec<_>) = apps::running_apps()
+            .into_iter()
+            .partition(|(_, pid)| !known.contains(pid));
+        let found = pick(&fresh, wanted).or_else(|| {
+            (start.elapsed() >= Duration::from_millis(REUSE_AFTER_MS))
+                .then(|| pick(&existing, wanted))
+                .flatten()
+        });
+        if let Some((names, pid)) = found {
+            return Ok(App {
+                process: Process::adopted(pid),
+                name: names.name.unwrap_or_else(|| wanted.to_string()),
+            });
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::timeout(wanted, timeout_ms)
+                .with_suggestions(vec![format!("'{}' did not appear among the running apps", wanted)]));
+        }
+        std::thread::sleep(Duration::from_millis(POLL_MS));
+    }
+}
+
+fn pick(apps: &[(AppNames, i32)], wanted: &str) -> Option<(AppNames, i32)> {
+    let names: Vec<AppNames> = apps.iter().map(|(names, _)| names.clone()).collect();
+    pick_app(&names, wanted).map(|i| apps[i].clone())
+}
+
+fn launch_failed(target: &str, reason: &str) -> Error {
+    Error::new(
+        ErrorCode::AppNotRunning,
+        format!("Could not launch '{}': {}", target, reason),
+    )
+}
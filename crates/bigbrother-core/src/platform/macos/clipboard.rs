@@ -0,0 +1,232 @@
+//! General pasteboard
+//!
+//! cidre has no NSPasteboard binding, so the pasteboard is driven through
+//! a small JavaScript for Automation script. Data goes in and out through
+//! temporary files, which keeps images out of argument lists. URI lists are
+//! one pasteboard item per URI, the way Finder writes them; screenshots are
+//! often TIFF only and are converted to PNG on read. Pasting text is one
+//! script run: it saves the pasteboard, presses cmd+v and puts it back.
+
+use crate::clipboard::{ClipboardContent, ClipboardFormat, Selection};
+use crate::error::{Error, ErrorCode, Result};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const PASTEBOARD_JS: &str = r#"
+ObjC.import('AppKit');
+function run(argv) {
+  const pb = $.NSPasteboard.generalPasteboard;
+  const op = argv[0];
+  if (op === 'count') return String(pb.changeCount);
+  if (op === 'types') return ObjC.deepUnwrap(pb.types || $([])).join('\n');
+  if (op === 'read') {
+    let data = pb.dataForType(argv[1]);
+    if (data.isNil() && argv[1] === 'public.png') {
+      const tiff = pb.dataForType('public.tiff');
+      if (!tiff.isNil()) {
+        data = $.NSBitmapImageRep.imageRepWithData(tiff).representationUsingTypeProperties($.NSBitmapImageFileTypePNG, $({}));
+      }
+    }
+    if (data.isNil()) return 'none';
+    data.writeToFileAtomically(argv[2], true);
+    return 'ok';
+  }
+  if (op === 'urls') {
+    const urls = [];
+    const items = pb.pasteboardItems;
+    for (let i = 0; i < items.count; i++) {
+      const item = items.objectAtIndex(i);
+      let url = item.stringForType('public.file-url');
+      if (url.isNil()) url = item.stringForType('public.url');
+      if (!url.isNil()) urls.push(url.js);
+    }
+    return urls.join('\n');
+  }
+  if (op === 'write') {
+    const first = $.NSPasteboardItem.alloc.init;
+    const items = [first];
+    let urls = 0;
+    for (let i = 1; i < argv.length; i += 2) {
+      const type = argv[i], value = argv[i + 1];
+      if (type === 'url') {
+        const item = urls++ === 0 ? first : $.NSPasteboardItem.alloc.init;
+        if (item !== first) items.push(item);
+        item.setStringForType(value, 'public.url');
+        if (value.startsWith('file://')) item.setStringForType(value, 'public.file-url');
+      } else {
+        first.setDataForType($.NSData.dataWithContentsOfFile(value), type);
+      }
+    }
+    pb.clearContents;
+    return pb.writeObjects($(items)) ? 'ok' : 'failed';
+  }
+  if (op === 'paste') {
+    const saved = [];
+    const items = pb.pasteboardItems;
+    for (let i = 0; i < items.count; i++) {
+      const item = items.objectAtIndex(i);
+      const copy = $.NSPasteboardItem.alloc.init;
+      const types = item.types;
+      for (let j = 0; j < types.count; j++) {
+        const data = item.dataForType(types.objectAtIndex(j));
+        if (!data.isNil()) copy.setDataForType(data, types.objectAtIndex(j));
+      }
+      saved.push(copy);
+    }
+    const text = $.NSString.stringWithContentsOfFileEncodingError(argv[1], $.NSUTF8StringEncoding, null);
+    pb.clearContents;
+    if (!pb.setStringForType(text, 'public.utf8-plain-text')) return 'failed';
+    Application('System Events').keystroke('v', {using: 'command down'});
+    delay(Number(argv[2]) / 1000);
+    if (saved.length > 0) {
+      pb.clearContents;
+      pb.writeObjects($(saved));
+    }
+    return 'ok';
+  }
+  throw new Error('unknown pasteboard operation ' + op);
+}
+"#;
+
+fn pasteboard(args: &[&str]) -> Result<String> {
+    let output = Command::new("osascript")
+        .args(["-l", "JavaScript", "-e", PASTEBOARD_JS])
+        .args(args)
+        .output()
+        .map_err(|e| Error::new(ErrorCode::ActionFailed, format!("Failed to run osascript: {}", e)))?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorCode::ActionFailed,
+            format!("Pasteboard access failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+/// Pasteboard type a format is read and written as
+fn pasteboard_type(format: ClipboardFormat) -> &'static str {
+    match format {
+        ClipboardFormat::Text => "public.utf8-plain-text",
+        ClipboardFormat::Html => "public.html",
+        ClipboardFormat::UriList => "public.url",
+        ClipboardFormat::Png => "public.png",
+    }
+}
+
+fn format_of(pasteboard_type: &str) -> Option<ClipboardFormat> {
+    match pasteboard_type {
+        "public.utf8-plain-text" | "NSStringPboardType" => Some(ClipboardFormat::Text),
+        "public.html" | "Apple HTML pasteboard type" => Some(ClipboardFormat::Html),
+        "public.url" | "public.file-url" | "NSFilenamesPboardType" => Some(ClipboardFormat::UriList),
+        "public.png" | "public.tiff" => Some(ClipboardFormat::Png),
+        _ => None,
+    }
+}
+
+fn general_only(selection: Selection) -> Result<()> {
+    match selection {
+        Selection::Clipboard => Ok(()),
+        Selection::Primary => Err(Error::new(
+            ErrorCode::NotImplemented,
+            "The PRIMARY selection only exists on X11".to_string(),
+        )),
+    }
+}
+
+/// Temporary file removed when dropped
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        Self(std::env::temp_dir().join(format!("bb-pasteboard-{}-{}", std::process::id(), n)))
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap_or_default()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+pub fn formats(selection: Selection) -> Result<Vec<ClipboardFormat>> {
+    general_only(selection)?;
+    let mut formats = Vec::new();
+    for format in pasteboard(&["types"])?.lines().filter_map(format_of) {
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    Ok(formats)
+}
+
+pub fn get(selection: Selection, format: ClipboardFormat) -> Result<Option<ClipboardContent>> {
+    general_only(selection)?;
+    if format == ClipboardFormat::UriList {
+        let urls = pasteboard(&["urls"])?;
+        return Ok((!urls.is_empty()).then(|| ClipboardContent::UriList(urls.lines().map(String::from).collect())));
+    }
+    let scratch = Scratch::new();
+    if pasteboard(&["read", pasteboard_type(format), scratch.path()])? != "ok" {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(&scratch.0)
+        .map_err(|e| Error::new(ErrorCode::ActionFailed, format!("Failed to read pasteboard data: {}", e)))?;
+    ClipboardContent::from_bytes(format, bytes).map(Some)
+}
+
+pub fn set(selection: Selection, contents: &[ClipboardContent]) -> Result<()> {
+    general_only(selection)?;
+    let mut scratches = Vec::new();
+    let mut args = vec!["write".to_string()];
+    for content in contents {
+        if let ClipboardContent::UriList(uris) = content {
+            for uri in uris {
+                args.extend(["url".to_string(), uri.clone()]);
+            }
+            continue;
+        }
+        let scratch = Scratch::new();
+        std::fs::write(&scratch.0, content.to_bytes())
+            .map_err(|e| Error::new(ErrorCode::ActionFailed, format!("Failed to stage pasteboard data: {}", e)))?;
+        args.extend([pasteboard_type(content.format()).to_string(), scratch.path().to_string()]);
+        scratches.push(scratch);
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match pasteboard(&args)?.as_str() {
+        "ok" => Ok(()),
+        _ => Err(Error::new(
+            ErrorCode::ActionFailed,
+            "The pasteboard refused the content".to_string(),
+        )),
+    }
+}
+
+/// Paste `text` into the frontmost app, giving it `settle_ms` to read the
+/// pasteboard before the previous content goes back
+pub fn paste(text: &str, settle_ms: u64) -> Result<()> {
+    let scratch = Scratch::new();
+    std::fs::write(&scratch.0, text)
+        .map_err(|e| Error::new(ErrorCode::ActionFailed, format!("Failed to stage pasteboard data: {}", e)))?;
+    match pasteboard(&["paste", scratch.path(), &settle_ms.to_string()])?.as_str() {
+        "ok" => Ok(()),
+        _ => Err(Error::new(
+            ErrorCode::ActionFailed,
+            "The pasteboard refused the text to paste".to_string(),
+        )),
+    }
+}
+
+/// The pasteboard's change count
+pub fn change_marker(selection: Selection) -> Result<u64> {
+    general_only(selection)?;
+    pasteboard(&["count"])?
+        .parse()
+        .map_err(|_| Error::new(ErrorCode::ActionFailed, "Unreadable pasteboard change count".to_string()))
+}
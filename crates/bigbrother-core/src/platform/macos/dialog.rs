@@ -0,0 +1,239 @@
+//! Background dialog watcher
+//!
+//! Dialogs are windows whose subrole is `AXDialog`/`AXSystemDialog`, and
+//! sheets attached to any window. The watcher thread polls the apps in
+//! scope, hands each newly shown dialog to `crate::dialog` rules and logs
+//! the outcome. A dialog whose rule failed is tried again at the next poll.
+//! Actions hold the input lease like any other input.
+
+use super::Window;
+use crate::apps;
+use crate::dialog::{is_dialog_role, DialogAction, DialogEvent, DialogLog, DialogRule, Intervention, SeenDialogs};
+use crate::element::UIElement;
+use crate::error::{Error, Result};
+use crate::input;
+use crate::keys::{Chord, Key};
+use crate::lease::{InputLease, LeaseOptions};
+use crate::locator::Locator;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How deep to look for a dialog's message text
+const TEXT_DEPTH: usize = 6;
+const MAX_TEXTS: usize = 10;
+
+/// Handle to a running dialog watcher; dropping it stops the watcher
+pub struct DialogWatcher {
+    log: DialogLog,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DialogWatcher {
+    /// Watch `app`'s dialogs, or every app's when `None`
+    pub(crate) fn start(
+        app: Option<String>,
+        rules: Vec<DialogRule>,
+        interval: Duration,
+        lease: LeaseOptions,
+    ) -> Self {
+        let log = DialogLog::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (log, stop) = (log.clone(), stop.clone());
+            thread::spawn(move || {
+                let mut seen = SeenDialogs::default();
+                while !stop.load(Ordering::Relaxed) {
+                    let dialogs = open_dialogs(app.as_deref());
+                    let keys: Vec<String> = dialogs.iter().map(Dialog::key).collect();
+                    for i in seen.update(&keys) {
+                        let event = handle(&dialogs[i], &rules, &lease);
+                        if event.outcome == Intervention::Failed {
+                            seen.failed(&keys[i]);
+                        }
+                        log.push(event);
+                    }
+                    thread::sleep(interval);
+                }
+            })
+        };
+        Self {
+            log,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Every event logged so far
+    pub fn events(&self) -> Vec<DialogEvent> {
+        self.log.events()
+    }
+
+    /// Events logged since the last drain
+    pub fn drain(&self) -> Vec<DialogEvent> {
+        self.log.drain()
+    }
+
+    /// Stop watching and return the events not yet drained
+    pub fn stop(mut self) -> Vec<DialogEvent> {
+        self.halt();
+        self.log.drain()
+    }
+
+    fn halt(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for DialogWatcher {
+    fn drop(&mut self) {
+        self.halt();
+    }
+}
+
+struct Dialog {
+    element: UIElement,
+    app: String,
+    pid: i32,
+    role: String,
+    title: String,
+}
+
+impl Dialog {
+    /// Identity across polls: the same accessibility element, wherever it
+    /// moves and whatever its title changes to
+    fn key(&self) -> String {
+        format!("{}:{}:{:x}", self.pid, self.role, self.element.raw().hash())
+    }
+}
+
+fn open_dialogs(app: Option<&str>) -> Vec<Dialog> {
+    let apps: Vec<(String, i32)> = match app {
+        Some(a) => apps::find_app_pid(a)
+            .map(|pid| vec![(a.to_string(), pid)])
+            .unwrap_or_default(),
+        None => apps::running_apps()
+            .into_iter()
+            .filter_map(|(names, pid)| Some((names.name?, pid)))
+            .collect(),
+    };
+
+    let mut dialogs = Vec::new();
+    for (name, pid) in apps {
+        for window in Window::for_pid(pid).unwrap_or_default() {
+            let element = window.element().clone();
+            let sheets = element
+                .children()
+                .into_iter()
+                .filter(|c| c.role().as_deref() == Some("AXSheet"));
+            for candidate in std::iter::once(element.clone()).chain(sheets) {
+                let role = candidate.role().unwrap_or_default();
+                let subrole = candidate
+                    .attribute("AXSubrole")
+                    .and_then(|v| v.as_str().map(String::from));
+                if is_dialog_role(&role, subrole.as_deref()) {
+                    dialogs.push(Dialog {
+                        title: candidate.title().unwrap_or_default(),
+                        element: candidate,
+                        app: name.clone(),
+                        pid,
+                        role,
+                    });
+                }
+            }
+        }
+    }
+    dialogs
+}
+
+/// Apply the first matching rule and describe what happened
+fn handle(dialog: &Dialog, rules: &[DialogRule], lease: &LeaseOptions) -> DialogEvent {
+    let mut event = DialogEvent {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default(),
+        app: dialog.app.clone(),
+        pid: dialog.pid,
+        title: dialog.title.clone(),
+        role: dialog.role.clone(),
+        text: dialog_text(&dialog.element),
+        rule: None,
+        action: None,
+        outcome: Intervention::Unmatched,
+        error: None,
+    };
+
+    let rule = rules.iter().find(|rule| {
+        rule.applies_to_app(&dialog.app)
+            && Locator::new(rule.when.clone())
+                .with_root(dialog.element.clone())
+                .exists()
+    });
+    let Some(rule) = rule else {
+        return event;
+    };
+
+    event.rule = Some(rule.name.clone());
+    event.action = Some(rule.action.to_string());
+    event.outcome = match apply(&rule.action, dialog, lease) {
+        Ok(true) => Intervention::Handled,
+        Ok(false) => Intervention::Ignored,
+        Err(e) => {
+            event.error = Some(e);
+            Intervention::Failed
+        }
+    };
+    event
+}
+
+/// Carry out an action; false when the action is to leave the dialog be
+fn apply(action: &DialogAction, dialog: &Dialog, lease: &LeaseOptions) -> Result<bool> {
+    if *action == DialogAction::Ignore {
+        return Ok(false);
+    }
+    let _lease = InputLease::acquire(lease)?;
+    match action {
+        DialogAction::Click(target) => {
+            Locator::parse(target)?
+                .with_root(dialog.element.clone())
+                .find()?
+                .click()?;
+        }
+        DialogAction::Cancel => press(dialog, Key::Escape)?,
+        DialogAction::Confirm => press(dialog, Key::Return)?,
+        DialogAction::Ignore => {}
+    }
+    Ok(true)
+}
+
+fn press(dialog: &Dialog, key: Key) -> Result<()> {
+    apps::activate_app(&dialog.app).map_err(Error::from)?;
+    input::press_chord(&Chord::new(key)).map_err(Error::from)
+}
+
+/// Static text inside the dialog, in tree order
+fn dialog_text(element: &UIElement) -> Vec<String> {
+    fn walk(element: &UIElement, depth: usize, texts: &mut Vec<String>) {
+        if texts.len() >= MAX_TEXTS || depth > TEXT_DEPTH {
+            return;
+        }
+        if element.role().as_deref() == Some("AXStaticText") {
+            if let Some(text) = element.value().or_else(|| element.title()).filter(|t| !t.is_empty()) {
+                texts.push(text);
+            }
+        }
+        for child in element.children() {
+            walk(&child, depth + 1, texts);
+        }
+    }
+
+    let mut texts = Vec::new();
+    walk(element, 0, &mut texts);
+    texts
+}
@@ -2,6 +2,15 @@
 //!
 //! Uses Accessibility API (AX) and Core Graphics (CG) via cidre.
 
+mod app;
+pub(crate) mod clipboard;
+mod dialog;
+mod window;
+
+pub use app::App;
+pub use dialog::DialogWatcher;
+pub use window::Window;
+
 use cidre::ax;
 
 /// Check if the process has accessibility permissions
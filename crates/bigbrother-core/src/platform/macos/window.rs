@@ -0,0 +1,211 @@
+//! Windows through the Accessibility API
+//!
+//! An app's windows are its `AXWindows`. macOS has no maximize in the X11
+//! sense, so maximize means full screen here, and `is_maximized` reports
+//! `AXFullScreen`. Changing a window moves things under other agents'
+//! pointers, so window actions hold the input lease.
+
+use crate::accessibility::*;
+use crate::apps;
+use crate::element::{hold_input, UIElement};
+use crate::error::{Error, Result};
+use crate::locator::Locator;
+use crate::tree::Bounds;
+use crate::window::{pick_window, window_not_found, WindowInfo};
+use cidre::{ax, cf, cg};
+
+/// A top-level window of a running app
+#[derive(Clone, Debug)]
+pub struct Window {
+    element: UIElement,
+    app: String,
+    pid: i32,
+}
+
+impl Window {
+    /// Windows of every running app, app by app
+    pub fn all() -> Result<Vec<Window>> {
+        let apps = apps::running_app_pids().map_err(Error::from)?;
+        Ok(apps
+            .into_iter()
+            .flat_map(|(name, pid)| windows_of(&name, pid))
+            .collect())
+    }
+
+    pub fn for_app(app: &str) -> Result<Vec<Window>> {
+        let pid = apps::find_app_pid(app).map_err(|_| Error::app_not_running(app))?;
+        Ok(windows_of(app, pid))
+    }
+
+    /// Windows of the app with this process id
+    pub fn for_pid(pid: i32) -> Result<Vec<Window>> {
+        let app = ax::UiElement::with_app_pid(pid);
+        let name = get_title(&app).unwrap_or_default();
+        Ok(windows_of(&name, pid))
+    }
+
+    /// The app's window with this title, see `crate::window::pick_window`
+    pub fn find(app: &str, title: &str) -> Result<Window> {
+        let windows = Self::for_app(app)?;
+        let titles: Vec<String> = windows.iter().map(|w| w.title().unwrap_or_default()).collect();
+        match pick_window(&titles, title) {
+            Some(i) => Ok(windows[i].clone()),
+            None => Err(window_not_found(app, title, &titles)),
+        }
+    }
+
+    /// Focused window of the frontmost app
+    pub fn focused() -> Result<Option<Window>> {
+        let Ok(app) = ax::UiElement::sys_wide().focused_app() else {
+            return Ok(None);
+        };
+        let pid = app.pid().map_err(|e| Error::action_failed("focused window", &format!("{:?}", e)))?;
+        let name = get_title(&app).unwrap_or_default();
+        Ok(get_element_attr(&app, ax::attr::focused_window()).map(|w| Window {
+            element: UIElement::new(w),
+            app: name,
+            pid,
+        }))
+    }
+
+    pub fn element(&self) -> &UIElement {
+        &self.element
+    }
+
+    /// Locator that only searches this window
+    pub fn locator(&self, selector: &str) -> Result<Locator> {
+        Ok(Locator::parse(selector)?.with_root(self.element.clone()))
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.element.title()
+    }
+
+    pub fn app(&self) -> Option<String> {
+        Some(self.app.clone())
+    }
+
+    pub fn pid(&self) -> Option<i32> {
+        Some(self.pid)
+    }
+
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.element.bounds()
+    }
+
+    pub fn is_focused(&self) -> bool {
+        get_bool_attr(self.element.raw(), ax::attr::focused()) == Some(true)
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        get_bool_attr(self.element.raw(), ax::attr::minimized()) == Some(true)
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        let name = cf::String::from_str(FULL_SCREEN);
+        get_bool_attr(self.element.raw(), ax::Attr::with_string(&name)) == Some(true)
+    }
+
+    pub fn info(&self) -> WindowInfo {
+        WindowInfo {
+            id: None,
+            app: self.app(),
+            pid: self.pid(),
+            title: self.title().unwrap_or_default(),
+            bounds: self.bounds(),
+            focused: self.is_focused(),
+            minimized: self.is_minimized(),
+            maximized: self.is_maximized(),
+        }
+    }
+
+    pub fn move_to(&self, x: f64, y: f64) -> Result<()> {
+        let value = ax::Value::with_cg_point(&cg::Point { x, y });
+        self.set(ax::attr::pos(), &value, "move")
+    }
+
+    pub fn resize(&self, width: f64, height: f64) -> Result<()> {
+        let value = ax::Value::with_cg_size(&cg::Size { width, height });
+        self.set(ax::attr::size(), &value, "resize")
+    }
+
+    pub fn set_bounds(&self, bounds: Bounds) -> Result<()> {
+        self.move_to(bounds.x, bounds.y)?;
+        self.resize(bounds.width, bounds.height)
+    }
+
+    pub fn minimize(&self) -> Result<()> {
+        self.set_flag(ax::attr::minimized(), true, "minimize")
+    }
+
+    /// Enter full screen
+    pub fn maximize(&self) -> Result<()> {
+        let name = cf::String::from_str(FULL_SCREEN);
+        self.set_flag(ax::Attr::with_string(&name), true, "maximize")
+    }
+
+    /// Leave full screen and unminimize
+    pub fn restore(&self) -> Result<()> {
+        if self.is_maximized() {
+            let name = cf::String::from_str(FULL_SCREEN);
+            self.set_flag(ax::Attr::with_string(&name), false, "restore")?;
+        }
+        if self.is_minimized() {
+            self.set_flag(ax::attr::minimized(), false, "restore")?;
+        }
+        Ok(())
+    }
+
+    /// Press the close button; the app may still ask to save first
+    pub fn close(&self) -> Result<()> {
+        let _lease = hold_input()?;
+        let button = get_element_attr(self.element.raw(), ax::attr::close_button())
+            .ok_or_else(|| Error::action_failed("close", "window has no close button"))?;
+        button
+            .perform_action(ax::action::press())
+            .map_err(|e| Error::action_failed("close", &format!("{:?}", e)))
+    }
+
+    /// Bring the window in front of the app's others and activate the app
+    pub fn raise(&self) -> Result<()> {
+        let _lease = hold_input()?;
+        self.element
+            .raw()
+            .perform_action(ax::action::raise())
+            .map_err(|e| Error::action_failed("raise", &format!("{:?}", e)))?;
+        set_bool_attr(self.element.raw(), ax::attr::main(), true);
+        apps::activate_app(&self.app).map_err(Error::from)
+    }
+
+    fn set(&self, attr: &ax::Attr, value: &ax::Value, action: &str) -> Result<()> {
+        let _lease = hold_input()?;
+        if set_ax_value_attr(self.element.raw(), attr, value) {
+            Ok(())
+        } else {
+            Err(Error::action_failed(action, "the window refused the change"))
+        }
+    }
+
+    fn set_flag(&self, attr: &ax::Attr, value: bool, action: &str) -> Result<()> {
+        let _lease = hold_input()?;
+        if set_bool_attr(self.element.raw(), attr, value) {
+            Ok(())
+        } else {
+            Err(Error::action_failed(action, "the window refused the change"))
+        }
+    }
+}
+
+/// Not among cidre's attribute constants, but set on every standard window
+const FULL_SCREEN: &str = "AXFullScreen";
+
+fn windows_of(app: &str, pid: i32) -> Vec<Window> {
+    get_elements_attr(&ax::UiElement::with_app_pid(pid), ax::attr::windows())
+        .into_iter()
+        .map(|w| Window {
+            element: UIElement::new(w),
+            app: app.to_string(),
+            pid,
+        })
+        .collect()
+}
@@ -0,0 +1,151 @@
+//! Windows clipboard
+//!
+//! Only plain text is supported, as `CF_UNICODETEXT`. The clipboard is a
+//! shared resource each process opens in turn, so opening retries briefly
+//! while another process has it. The change marker is the system's
+//! clipboard sequence number.
+
+use crate::clipboard::{ClipboardContent, ClipboardFormat, Selection};
+use crate::error::{Error, ErrorCode, Result};
+use std::thread;
+use std::time::Duration;
+use windows::Win32::Foundation::{GlobalFree, HANDLE, HGLOBAL, HWND};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData, GetClipboardSequenceNumber,
+    IsClipboardFormatAvailable, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE};
+
+const CF_TEXT: u32 = 1;
+const CF_OEMTEXT: u32 = 7;
+const CF_UNICODETEXT: u32 = 13;
+const CF_LOCALE: u32 = 16;
+
+/// Attempts at opening the clipboard while another process holds it
+const OPEN_ATTEMPTS: u32 = 10;
+const OPEN_RETRY_MS: u64 = 10;
+
+pub fn formats(selection: Selection) -> Result<Vec<ClipboardFormat>> {
+    check(selection)?;
+    let text = unsafe { IsClipboardFormatAvailable(CF_UNICODETEXT).is_ok() };
+    Ok(if text { vec![ClipboardFormat::Text] } else { Vec::new() })
+}
+
+pub fn get(selection: Selection, format: ClipboardFormat) -> Result<Option<ClipboardContent>> {
+    check(selection)?;
+    if format != ClipboardFormat::Text {
+        return Err(unsupported(format));
+    }
+    let _open = Open::new()?;
+    unsafe {
+        let Ok(handle) = GetClipboardData(CF_UNICODETEXT) else {
+            return Ok(None);
+        };
+        let global = HGLOBAL(handle.0);
+        let data = GlobalLock(global) as *const u16;
+        if data.is_null() {
+            return Err(failed("read", "the clipboard data could not be locked"));
+        }
+        let units = std::slice::from_raw_parts(data, GlobalSize(global) / 2);
+        let len = units.iter().position(|&u| u == 0).unwrap_or(units.len());
+        let text = String::from_utf16_lossy(&units[..len]);
+        let _ = GlobalUnlock(global);
+        Ok(Some(ClipboardContent::Text(text)))
+    }
+}
+
+pub fn set(selection: Selection, contents: &[ClipboardContent]) -> Result<()> {
+    check(selection)?;
+    let text = contents
+        .iter()
+        .find_map(|c| match c {
+            ClipboardContent::Text(text) => Some(text),
+            _ => None,
+        })
+        .ok_or_else(|| unsupported(contents[0].format()))?;
+    let units: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+
+    let _open = Open::new()?;
+    unsafe {
+        EmptyClipboard().map_err(|e| failed("write", &e.to_string()))?;
+        let global = GlobalAlloc(GMEM_MOVEABLE, units.len() * 2).map_err(|e| failed("write", &e.to_string()))?;
+        let data = GlobalLock(global) as *mut u16;
+        if data.is_null() {
+            let _ = GlobalFree(global);
+            return Err(failed("write", "the clipboard data could not be locked"));
+        }
+        std::ptr::copy_nonoverlapping(units.as_ptr(), data, units.len());
+        let _ = GlobalUnlock(global);
+        // The clipboard owns the memory once it takes it
+        if let Err(e) = SetClipboardData(CF_UNICODETEXT, HANDLE(global.0)) {
+            let _ = GlobalFree(global);
+            return Err(failed("write", &e.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Whether everything on the clipboard is text, which `get` and `set` can
+/// put back as it was; Windows adds the other text formats by itself
+pub(crate) fn holds_only_text() -> Result<bool> {
+    let _open = Open::new()?;
+    let mut format = 0;
+    loop {
+        format = unsafe { EnumClipboardFormats(format) };
+        match format {
+            0 => return Ok(true),
+            CF_TEXT | CF_OEMTEXT | CF_UNICODETEXT | CF_LOCALE => continue,
+            _ => return Ok(false),
+        }
+    }
+}
+
+pub fn change_marker(selection: Selection) -> Result<u64> {
+    check(selection)?;
+    Ok(unsafe { GetClipboardSequenceNumber() } as u64)
+}
+
+/// The clipboard, open until dropped
+struct Open;
+
+impl Open {
+    fn new() -> Result<Self> {
+        let mut last = None;
+        for _ in 0..OPEN_ATTEMPTS {
+            match unsafe { OpenClipboard(HWND(std::ptr::null_mut())) } {
+                Ok(()) => return Ok(Self),
+                Err(e) => last = Some(e),
+            }
+            thread::sleep(Duration::from_millis(OPEN_RETRY_MS));
+        }
+        let reason = last.map(|e| e.to_string()).unwrap_or_default();
+        Err(failed("open", &format!("another process holds it ({})", reason)))
+    }
+}
+
+impl Drop for Open {
+    fn drop(&mut self) {
+        let _ = unsafe { CloseClipboard() };
+    }
+}
+
+fn check(selection: Selection) -> Result<()> {
+    match selection {
+        Selection::Clipboard => Ok(()),
+        Selection::Primary => Err(Error::new(
+            ErrorCode::NotImplemented,
+            "The PRIMARY selection only exists on X11".to_string(),
+        )),
+    }
+}
+
+fn unsupported(format: ClipboardFormat) -> Error {
+    Error::new(
+        ErrorCode::NotImplemented,
+        format!("Only plain text clipboard content is supported on Windows, not {}", format),
+    )
+}
+
+fn failed(what: &str, reason: &str) -> Error {
+    Error::action_failed(&format!("clipboard {}", what), reason)
+}
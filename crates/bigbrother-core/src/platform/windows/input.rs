@@ -6,11 +6,20 @@ use windows::Win32::UI::Input::KeyboardAndMouse::{
     SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT, MOUSEINPUT,
     KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP,
     MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP,
-    MOUSEEVENTF_WHEEL,
+    MOUSEEVENTF_WHEEL, MOUSEEVENTF_HWHEEL,
     VIRTUAL_KEY,
 };
-use windows::Win32::UI::WindowsAndMessaging::SetCursorPos;
-
+use windows::Win32::Foundation::POINT;
+use windows::Win32::UI::WindowsAndMessaging::{GetCursorPos, SetCursorPos};
+
+use super::clipboard;
+use crate::keys::{Chord, Key};
+use crate::mouse::{drag_path, MotionProfile, MouseButton, DRAG_STEP_MS};
+use crate::scroll::Scroll;
+use crate::text_entry::{
+    enter_with, paste_text, EntryStrategy, RunError, TextEntryBackend, TextEntryOptions, TextEntryReport,
+};
+use std::time::Duration;
 use crate::{Error, ErrorCode, Result};
 
 /// Move the mouse to absolute coordinates
@@ -22,6 +31,28 @@ pub fn move_mouse(x: i32, y: i32) -> Result<()> {
     Ok(())
 }
 
+/// Move the mouse to absolute coordinates along `profile`
+pub fn move_mouse_with(x: i32, y: i32, profile: &MotionProfile) -> Result<()> {
+    if profile.is_instant() {
+        return move_mouse(x, y);
+    }
+    for (px, py) in profile.path(mouse_position()?, (x, y)) {
+        move_mouse(px, py)?;
+        std::thread::sleep(Duration::from_millis(profile.step_ms));
+    }
+    Ok(())
+}
+
+/// Current pointer position
+pub fn mouse_position() -> Result<(i32, i32)> {
+    let mut point = POINT::default();
+    unsafe {
+        GetCursorPos(&mut point)
+            .map_err(|e| Error::new(ErrorCode::ActionFailed, format!("Failed to read mouse position: {:?}", e)))?;
+    }
+    Ok((point.x, point.y))
+}
+
 /// Click at the current position
 pub fn click() -> Result<()> {
     let inputs = [
@@ -38,6 +69,12 @@ pub fn click_at(x: i32, y: i32) -> Result<()> {
     click()
 }
 
+/// Click at specific coordinates after moving there along `profile`
+pub fn click_at_with(x: i32, y: i32, profile: &MotionProfile) -> Result<()> {
+    move_mouse_with(x, y, profile)?;
+    click()
+}
+
 /// Double click at current position
 pub fn double_click() -> Result<()> {
     click()?;
@@ -63,6 +100,26 @@ pub fn middle_click() -> Result<()> {
     send_inputs(&inputs)
 }
 
+/// Press `button` at `from`, move to `to` over `duration` and release there
+pub fn drag(from: (i32, i32), to: (i32, i32), duration: Duration, button: MouseButton) -> Result<()> {
+    let (down, up) = match button {
+        MouseButton::Left => (MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP),
+        MouseButton::Right => (MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP),
+        MouseButton::Middle => (MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP),
+    };
+    move_mouse(from.0, from.1)?;
+    std::thread::sleep(Duration::from_millis(10));
+    send_inputs(&[make_mouse_input(down, 0, 0, 0)])?;
+
+    let moved = drag_path(from, to, duration).into_iter().try_for_each(|(x, y)| {
+        std::thread::sleep(Duration::from_millis(DRAG_STEP_MS));
+        move_mouse(x, y)
+    });
+    // Release even when a move failed, or the button stays stuck down
+    let released = send_inputs(&[make_mouse_input(up, 0, 0, 0)]);
+    moved.and(released)
+}
+
 /// Scroll the mouse wheel
 /// Positive delta = scroll up, negative = scroll down
 pub fn scroll(delta: i32) -> Result<()> {
@@ -70,6 +127,21 @@ pub fn scroll(delta: i32) -> Result<()> {
     send_inputs(&inputs)
 }
 
+/// Scroll with the wheel at a screen point, see `crate::scroll`
+pub fn scroll_at(x: i32, y: i32, scroll: &Scroll) -> Result<()> {
+    move_mouse(x, y)?;
+    // The vertical wheel counts up as positive, the horizontal one right
+    let (horizontal, vertical) = scroll.wheel_units();
+    let mut inputs = Vec::new();
+    if vertical != 0 {
+        inputs.push(make_mouse_input(MOUSEEVENTF_WHEEL, 0, 0, -vertical));
+    }
+    if horizontal != 0 {
+        inputs.push(make_mouse_input(MOUSEEVENTF_HWHEEL, 0, 0, horizontal));
+    }
+    send_inputs(&inputs)
+}
+
 /// Press and release a virtual key
 pub fn press_key(vk: u16) -> Result<()> {
     let inputs = [
@@ -92,18 +164,70 @@ pub fn key_up(vk: u16) -> Result<()> {
 }
 
 /// Type a string using Unicode input
+///
+/// Fails when any character could not be typed; `enter_text` reports them
+/// instead.
 pub fn type_text(text: &str) -> Result<()> {
-    let mut inputs = Vec::new();
+    enter_text(text, &TextEntryOptions::default()).into_result()?;
+    Ok(())
+}
 
-    for c in text.chars() {
-        let code = c as u16;
-        // Key down
-        inputs.push(make_unicode_input(code, false));
-        // Key up
-        inputs.push(make_unicode_input(code, true));
+/// Type text through the strategy stack, see `crate::text_entry`
+pub fn enter_text(text: &str, options: &TextEntryOptions) -> TextEntryReport {
+    let mut backend = WindowsTextEntry {
+        key_delay: Duration::from_millis(options.key_delay_ms),
+    };
+    enter_with(&mut backend, text, options)
+}
+
+struct WindowsTextEntry {
+    key_delay: Duration,
+}
+
+impl TextEntryBackend for WindowsTextEntry {
+    fn strategies(&self) -> Vec<EntryStrategy> {
+        vec![EntryStrategy::Unicode, EntryStrategy::Paste]
     }
 
-    send_inputs(&inputs)
+    fn can_type(&self, strategy: EntryStrategy, c: char) -> bool {
+        match strategy {
+            EntryStrategy::Unicode => Key::from_control_char(c).is_some() || !c.is_control(),
+            EntryStrategy::Paste => !c.is_control() || c == '\n' || c == '\t',
+            EntryStrategy::Keysym => false,
+        }
+    }
+
+    fn type_run(&mut self, strategy: EntryStrategy, text: &str) -> std::result::Result<(), RunError> {
+        if strategy == EntryStrategy::Paste {
+            // Only text can be put back, so anything else on the clipboard rules pasting out
+            if !clipboard::holds_only_text()? {
+                return Err(Error::action_failed(
+                    "paste",
+                    "the clipboard holds more than text, which would be lost",
+                )
+                .into());
+            }
+            return Ok(paste_text(text, || shortcut(vk::V, &[vk::CONTROL]))?);
+        }
+        // One SendInput per character, so the key delay spaces them out
+        for (typed, c) in text.chars().enumerate() {
+            let mut inputs = Vec::new();
+            if let Some(key) = Key::from_control_char(c) {
+                inputs.push(make_key_input(key.to_vk(), false));
+                inputs.push(make_key_input(key.to_vk(), true));
+            } else {
+                // Characters outside the BMP go as two surrogate units
+                let mut units = [0u16; 2];
+                for &unit in c.encode_utf16(&mut units).iter() {
+                    inputs.push(make_unicode_input(unit, false));
+                    inputs.push(make_unicode_input(unit, true));
+                }
+            }
+            send_inputs(&inputs).map_err(|error| RunError { typed, error })?;
+            std::thread::sleep(self.key_delay);
+        }
+        Ok(())
+    }
 }
 
 /// Execute a keyboard shortcut (e.g., Ctrl+C)
@@ -127,6 +251,12 @@ pub fn shortcut(key: u16, modifiers: &[u16]) -> Result<()> {
     send_inputs(&inputs)
 }
 
+/// Press a key chord such as `ctrl+shift+t`, see `crate::keys`
+pub fn press_chord(chord: &Chord) -> Result<()> {
+    let modifiers: Vec<u16> = chord.modifiers.iter().map(|m| m.key().to_vk()).collect();
+    shortcut(chord.key.to_vk(), &modifiers)
+}
+
 // Helper functions
 
 fn make_mouse_input(flags: windows::Win32::UI::Input::KeyboardAndMouse::MOUSE_EVENT_FLAGS, dx: i32, dy: i32, data: i32) -> INPUT {
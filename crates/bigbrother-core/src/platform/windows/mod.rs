@@ -3,6 +3,7 @@
 //! Uses UI Automation API for accessibility and Win32 for input.
 
 mod accessibility;
+pub(crate) mod clipboard;
 mod input;
 
 pub use accessibility::*;
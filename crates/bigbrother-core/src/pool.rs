@@ -0,0 +1,85 @@
+//! Scoped worker pool for fanning work out across threads
+
+use std::sync::Mutex;
+use std::thread;
+
+/// Worker count used when the caller does not pick one
+pub fn default_workers() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(8)
+}
+
+/// Apply `f` to every item on up to `workers` threads
+///
+/// Results come back in input order regardless of which worker finished
+/// first, so merged output is deterministic.
+pub fn parallel_map<T, R, F>(items: Vec<T>, workers: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let n = items.len();
+    if workers <= 1 || n <= 1 {
+        return items.into_iter().map(f).collect();
+    }
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..n).map(|_| None).collect());
+
+    thread::scope(|s| {
+        for _ in 0..workers.min(n) {
+            s.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                let Some((i, item)) = next else { break };
+                let result = f(item);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every item is processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn keeps_input_order() {
+        let items: Vec<u64> = (0..16).collect();
+        // Earlier items take longer, so they finish last
+        let out = parallel_map(items, 4, |i| {
+            thread::sleep(Duration::from_millis(16 - i));
+            i * 10
+        });
+        assert_eq!(out, (0..16).map(|i| i * 10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn runs_on_several_threads() {
+        let ids = parallel_map((0..8).collect(), 4, |_: i32| {
+            thread::sleep(Duration::from_millis(20));
+            thread::current().id()
+        });
+        let mut distinct = ids.clone();
+        distinct.sort_by_key(|id| format!("{:?}", id));
+        distinct.dedup();
+        assert!(distinct.len() > 1);
+    }
+
+    #[test]
+    fn single_worker_runs_inline() {
+        let caller = thread::current().id();
+        let ids = parallel_map(vec![1, 2, 3], 1, |_| thread::current().id());
+        assert!(ids.iter().all(|id| *id == caller));
+    }
+}
@@ -0,0 +1,402 @@
+//! Canonical, platform-neutral element roles
+//!
+//! Each platform names roles its own way: macOS `AXButton`, Windows UI
+//! Automation `Button`, AT-SPI `push button`. `Role` is the shared vocabulary
+//! and the tables below map native names onto it, so `role:button` matches a
+//! button everywhere while the native name stays available as `native_role`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Application,
+    Window,
+    Dialog,
+    Button,
+    CheckBox,
+    RadioButton,
+    TextField,
+    TextArea,
+    StaticText,
+    Link,
+    Image,
+    Menu,
+    MenuBar,
+    MenuItem,
+    PopUpButton,
+    ComboBox,
+    List,
+    ListItem,
+    Table,
+    Row,
+    Column,
+    ColumnHeader,
+    Cell,
+    Tree,
+    TreeItem,
+    Tab,
+    TabGroup,
+    Toolbar,
+    Group,
+    ScrollArea,
+    ScrollBar,
+    SplitGroup,
+    Slider,
+    SpinButton,
+    ProgressBar,
+    DisclosureTriangle,
+    Heading,
+    Document,
+    Separator,
+    Unknown,
+}
+
+/// All roles, for parsing canonical names
+const ALL: &[Role] = &[
+    Role::Application,
+    Role::Window,
+    Role::Dialog,
+    Role::Button,
+    Role::CheckBox,
+    Role::RadioButton,
+    Role::TextField,
+    Role::TextArea,
+    Role::StaticText,
+    Role::Link,
+    Role::Image,
+    Role::Menu,
+    Role::MenuBar,
+    Role::MenuItem,
+    Role::PopUpButton,
+    Role::ComboBox,
+    Role::List,
+    Role::ListItem,
+    Role::Table,
+    Role::Row,
+    Role::Column,
+    Role::ColumnHeader,
+    Role::Cell,
+    Role::Tree,
+    Role::TreeItem,
+    Role::Tab,
+    Role::TabGroup,
+    Role::Toolbar,
+    Role::Group,
+    Role::ScrollArea,
+    Role::ScrollBar,
+    Role::SplitGroup,
+    Role::Slider,
+    Role::SpinButton,
+    Role::ProgressBar,
+    Role::DisclosureTriangle,
+    Role::Heading,
+    Role::Document,
+    Role::Separator,
+    Role::Unknown,
+];
+
+/// macOS accessibility roles (`AXRole`)
+pub const MACOS_ROLES: &[(&str, Role)] = &[
+    ("AXApplication", Role::Application),
+    ("AXWindow", Role::Window),
+    ("AXSheet", Role::Dialog),
+    ("AXDrawer", Role::Dialog),
+    ("AXButton", Role::Button),
+    ("AXMenuButton", Role::Button),
+    ("AXCheckBox", Role::CheckBox),
+    ("AXRadioButton", Role::RadioButton),
+    ("AXTextField", Role::TextField),
+    ("AXTextArea", Role::TextArea),
+    ("AXStaticText", Role::StaticText),
+    ("AXLink", Role::Link),
+    ("AXImage", Role::Image),
+    ("AXMenu", Role::Menu),
+    ("AXMenuBar", Role::MenuBar),
+    ("AXMenuItem", Role::MenuItem),
+    ("AXMenuBarItem", Role::MenuItem),
+    ("AXPopUpButton", Role::PopUpButton),
+    ("AXComboBox", Role::ComboBox),
+    ("AXList", Role::List),
+    ("AXTable", Role::Table),
+    ("AXGrid", Role::Table),
+    ("AXRow", Role::Row),
+    ("AXColumn", Role::Column),
+    ("AXCell", Role::Cell),
+    ("AXOutline", Role::Tree),
+    ("AXBrowser", Role::Tree),
+    ("AXTabGroup", Role::TabGroup),
+    ("AXToolbar", Role::Toolbar),
+    ("AXGroup", Role::Group),
+    ("AXRadioGroup", Role::Group),
+    ("AXLayoutArea", Role::Group),
+    ("AXLayoutItem", Role::Group),
+    ("AXScrollArea", Role::ScrollArea),
+    ("AXScrollBar", Role::ScrollBar),
+    ("AXSplitGroup", Role::SplitGroup),
+    ("AXSplitter", Role::Separator),
+    ("AXSlider", Role::Slider),
+    ("AXIncrementor", Role::SpinButton),
+    ("AXProgressIndicator", Role::ProgressBar),
+    ("AXBusyIndicator", Role::ProgressBar),
+    ("AXDisclosureTriangle", Role::DisclosureTriangle),
+    ("AXHeading", Role::Heading),
+    ("AXWebArea", Role::Document),
+    ("AXUnknown", Role::Unknown),
+];
+
+/// Windows UI Automation control types, as named by `control_type_name`
+pub const WINDOWS_ROLES: &[(&str, Role)] = &[
+    ("Window", Role::Window),
+    ("Button", Role::Button),
+    ("SplitButton", Role::Button),
+    ("CheckBox", Role::CheckBox),
+    ("RadioButton", Role::RadioButton),
+    ("Edit", Role::TextField),
+    ("Text", Role::StaticText),
+    ("Hyperlink", Role::Link),
+    ("Image", Role::Image),
+    ("Menu", Role::Menu),
+    ("MenuBar", Role::MenuBar),
+    ("MenuItem", Role::MenuItem),
+    ("ComboBox", Role::ComboBox),
+    ("List", Role::List),
+    ("ListItem", Role::ListItem),
+    ("Table", Role::Table),
+    ("DataGrid", Role::Table),
+    ("DataItem", Role::Row),
+    ("Header", Role::Row),
+    ("HeaderItem", Role::ColumnHeader),
+    ("Tree", Role::Tree),
+    ("TreeItem", Role::TreeItem),
+    ("Tab", Role::TabGroup),
+    ("TabItem", Role::Tab),
+    ("ToolBar", Role::Toolbar),
+    ("StatusBar", Role::Toolbar),
+    ("Group", Role::Group),
+    ("Pane", Role::Group),
+    ("Calendar", Role::Group),
+    ("TitleBar", Role::Group),
+    ("ScrollBar", Role::ScrollBar),
+    ("Slider", Role::Slider),
+    ("Spinner", Role::SpinButton),
+    ("ProgressBar", Role::ProgressBar),
+    ("Document", Role::Document),
+    ("Separator", Role::Separator),
+    ("Custom", Role::Unknown),
+    ("Unknown", Role::Unknown),
+];
+
+/// AT-SPI role names (`atspi_role_get_name`)
+pub const ATSPI_ROLES: &[(&str, Role)] = &[
+    ("application", Role::Application),
+    ("frame", Role::Window),
+    ("window", Role::Window),
+    ("dialog", Role::Dialog),
+    ("alert", Role::Dialog),
+    ("file chooser", Role::Dialog),
+    ("push button", Role::Button),
+    ("toggle button", Role::Button),
+    ("check box", Role::CheckBox),
+    ("radio button", Role::RadioButton),
+    ("entry", Role::TextField),
+    ("password text", Role::TextField),
+    ("text", Role::TextArea),
+    ("label", Role::StaticText),
+    ("static", Role::StaticText),
+    ("link", Role::Link),
+    ("image", Role::Image),
+    ("icon", Role::Image),
+    ("menu", Role::Menu),
+    ("popup menu", Role::Menu),
+    ("menu bar", Role::MenuBar),
+    ("menu item", Role::MenuItem),
+    ("check menu item", Role::MenuItem),
+    ("radio menu item", Role::MenuItem),
+    ("tearoff menu item", Role::MenuItem),
+    ("combo box", Role::ComboBox),
+    ("list", Role::List),
+    ("list box", Role::List),
+    ("list item", Role::ListItem),
+    ("table", Role::Table),
+    ("tree table", Role::Table),
+    ("table row", Role::Row),
+    ("table cell", Role::Cell),
+    ("column header", Role::ColumnHeader),
+    ("table column header", Role::ColumnHeader),
+    ("tree", Role::Tree),
+    ("tree item", Role::TreeItem),
+    ("page tab", Role::Tab),
+    ("page tab list", Role::TabGroup),
+    ("tool bar", Role::Toolbar),
+    ("status bar", Role::Toolbar),
+    ("panel", Role::Group),
+    ("filler", Role::Group),
+    ("section", Role::Group),
+    ("form", Role::Group),
+    ("viewport", Role::ScrollArea),
+    ("scroll pane", Role::ScrollArea),
+    ("scroll bar", Role::ScrollBar),
+    ("split pane", Role::SplitGroup),
+    ("slider", Role::Slider),
+    ("spin button", Role::SpinButton),
+    ("progress bar", Role::ProgressBar),
+    ("heading", Role::Heading),
+    ("document frame", Role::Document),
+    ("document web", Role::Document),
+    ("separator", Role::Separator),
+    ("unknown", Role::Unknown),
+];
+
+impl Role {
+    /// Canonical name, as used in selectors and JSON output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Application => "application",
+            Role::Window => "window",
+            Role::Dialog => "dialog",
+            Role::Button => "button",
+            Role::CheckBox => "check_box",
+            Role::RadioButton => "radio_button",
+            Role::TextField => "text_field",
+            Role::TextArea => "text_area",
+            Role::StaticText => "static_text",
+            Role::Link => "link",
+            Role::Image => "image",
+            Role::Menu => "menu",
+            Role::MenuBar => "menu_bar",
+            Role::MenuItem => "menu_item",
+            Role::PopUpButton => "pop_up_button",
+            Role::ComboBox => "combo_box",
+            Role::List => "list",
+            Role::ListItem => "list_item",
+            Role::Table => "table",
+            Role::Row => "row",
+            Role::Column => "column",
+            Role::ColumnHeader => "column_header",
+            Role::Cell => "cell",
+            Role::Tree => "tree",
+            Role::TreeItem => "tree_item",
+            Role::Tab => "tab",
+            Role::TabGroup => "tab_group",
+            Role::Toolbar => "toolbar",
+            Role::Group => "group",
+            Role::ScrollArea => "scroll_area",
+            Role::ScrollBar => "scroll_bar",
+            Role::SplitGroup => "split_group",
+            Role::Slider => "slider",
+            Role::SpinButton => "spin_button",
+            Role::ProgressBar => "progress_bar",
+            Role::DisclosureTriangle => "disclosure_triangle",
+            Role::Heading => "heading",
+            Role::Document => "document",
+            Role::Separator => "separator",
+            Role::Unknown => "unknown",
+        }
+    }
+
+    /// Parse a canonical name, ignoring case and separators (`checkbox`, `check_box`, `CheckBox`)
+    pub fn from_name(name: &str) -> Option<Role> {
+        let wanted = squash(name);
+        ALL.iter().copied().find(|r| squash(r.as_str()) == wanted)
+    }
+
+    /// Map a native role from any supported platform; unmapped roles are `Unknown`
+    ///
+    /// The platforms' naming schemes do not collide (`AX` prefix, CamelCase,
+    /// lowercase with spaces), so the tables can be searched together.
+    pub fn from_native(native: &str) -> Role {
+        MACOS_ROLES
+            .iter()
+            .chain(WINDOWS_ROLES)
+            .chain(ATSPI_ROLES)
+            .find(|(n, _)| *n == native)
+            .map(|(_, r)| *r)
+            .unwrap_or(Role::Unknown)
+    }
+
+    /// Roles an agent can act on directly
+    pub fn is_interactive(&self) -> bool {
+        matches!(
+            self,
+            Role::Button
+                | Role::CheckBox
+                | Role::RadioButton
+                | Role::TextField
+                | Role::TextArea
+                | Role::Link
+                | Role::MenuItem
+                | Role::PopUpButton
+                | Role::ComboBox
+                | Role::Slider
+                | Role::SpinButton
+                | Role::DisclosureTriangle
+                | Role::Tab
+        )
+    }
+
+    /// Containers that only carry layout
+    pub fn is_layout(&self) -> bool {
+        matches!(
+            self,
+            Role::Group | Role::ScrollArea | Role::SplitGroup | Role::Unknown
+        )
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn squash(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_is_a_button_everywhere() {
+        assert_eq!(Role::from_native("AXButton"), Role::Button);
+        assert_eq!(Role::from_native("Button"), Role::Button);
+        assert_eq!(Role::from_native("push button"), Role::Button);
+    }
+
+    #[test]
+    fn canonical_names_are_lenient() {
+        assert_eq!(Role::from_name("check_box"), Some(Role::CheckBox));
+        assert_eq!(Role::from_name("checkbox"), Some(Role::CheckBox));
+        assert_eq!(Role::from_name("Text Field"), Some(Role::TextField));
+        assert_eq!(Role::from_name("AXButton"), None);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for role in ALL {
+            assert_eq!(Role::from_name(role.as_str()), Some(*role));
+            let json = serde_json::to_string(role).unwrap();
+            assert_eq!(json, format!("\"{}\"", role.as_str()));
+        }
+    }
+
+    #[test]
+    fn tables_have_no_conflicting_entries() {
+        let all: Vec<_> = MACOS_ROLES.iter().chain(WINDOWS_ROLES).chain(ATSPI_ROLES).collect();
+        for (i, (name, role)) in all.iter().enumerate() {
+            for (other, other_role) in &all[i + 1..] {
+                assert!(name != other || role == other_role, "{} maps twice", name);
+            }
+        }
+    }
+
+    #[test]
+    fn unmapped_roles_are_unknown() {
+        assert_eq!(Role::from_native("AXSomethingNew"), Role::Unknown);
+    }
+}
@@ -0,0 +1,227 @@
+//! Scrolling by lines or pixels, and scrolling until something shows up
+//!
+//! Amounts follow the content, not the wheel: positive `dy` moves toward the
+//! end of the content (down), positive `dx` toward the right. The platform
+//! injectors turn that into their own wheel conventions.
+//!
+//! `scroll_until` steps a container until a target is visible. Whether the
+//! end was reached is judged by what the container looks like before and
+//! after a step: when scroll bar values and content bounds come out the same,
+//! nothing moved and further steps won't either.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+
+/// Rough height of a line, for injectors that only scroll one way
+pub const PIXELS_PER_LINE: i32 = 16;
+
+/// Windows wheel units per line; a 120-unit notch scrolls three lines
+pub const WHEEL_UNITS_PER_LINE: i32 = 40;
+
+/// Steps `scroll_until` takes before giving up
+pub const SCROLL_UNTIL_MAX_STEPS: usize = 50;
+
+/// Time for content to move after a step, before it is looked at again
+pub const SCROLL_SETTLE_MS: u64 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollUnit {
+    #[default]
+    Lines,
+    Pixels,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scroll {
+    pub dx: i32,
+    pub dy: i32,
+    pub unit: ScrollUnit,
+}
+
+impl Scroll {
+    pub fn lines(dx: i32, dy: i32) -> Self {
+        Self {
+            dx,
+            dy,
+            unit: ScrollUnit::Lines,
+        }
+    }
+
+    pub fn pixels(dx: i32, dy: i32) -> Self {
+        Self {
+            dx,
+            dy,
+            unit: ScrollUnit::Pixels,
+        }
+    }
+
+    /// `amount` toward `up`, `down`, `left` or `right`
+    pub fn toward(direction: &str, amount: i32, unit: ScrollUnit) -> Result<Self> {
+        let (dx, dy) = match direction.trim().to_lowercase().as_str() {
+            "up" => (0, -amount),
+            "down" => (0, amount),
+            "left" => (-amount, 0),
+            "right" => (amount, 0),
+            _ => return Err(Error::selector_invalid(direction, "expected up, down, left or right")),
+        };
+        Ok(Self { dx, dy, unit })
+    }
+
+    /// Whole lines, rounding pixel amounts away from zero
+    pub fn in_lines(&self) -> (i32, i32) {
+        let lines = |px: i32| px.signum() * ((px.abs() + PIXELS_PER_LINE - 1) / PIXELS_PER_LINE);
+        match self.unit {
+            ScrollUnit::Lines => (self.dx, self.dy),
+            ScrollUnit::Pixels => (lines(self.dx), lines(self.dy)),
+        }
+    }
+
+    /// Windows wheel units, `(horizontal, vertical)`, both in content direction
+    pub fn wheel_units(&self) -> (i32, i32) {
+        match self.unit {
+            ScrollUnit::Lines => (self.dx * WHEEL_UNITS_PER_LINE, self.dy * WHEEL_UNITS_PER_LINE),
+            ScrollUnit::Pixels => (
+                self.dx * WHEEL_UNITS_PER_LINE / PIXELS_PER_LINE,
+                self.dy * WHEEL_UNITS_PER_LINE / PIXELS_PER_LINE,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScrollUntilOptions {
+    /// What one step scrolls
+    pub step: Scroll,
+    pub max_steps: usize,
+    pub settle_ms: u64,
+}
+
+impl Default for ScrollUntilOptions {
+    fn default() -> Self {
+        Self {
+            step: Scroll::lines(0, 5),
+            max_steps: SCROLL_UNTIL_MAX_STEPS,
+            settle_ms: SCROLL_SETTLE_MS,
+        }
+    }
+}
+
+/// How a `scroll_until` ended
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScrollReport {
+    pub found: bool,
+    pub steps: usize,
+    /// A step left the container unchanged
+    pub end_reached: bool,
+}
+
+/// Scroll with `step` until `probe` finds something, the container stops
+/// changing, or `max_steps` is used up
+///
+/// `state` captures whatever shows the container moved: scroll bar values,
+/// content bounds.
+pub fn scroll_until<T, S: PartialEq>(
+    options: &ScrollUntilOptions,
+    mut probe: impl FnMut() -> Option<T>,
+    mut state: impl FnMut() -> S,
+    mut step: impl FnMut(&Scroll) -> Result<()>,
+) -> Result<(Option<T>, ScrollReport)> {
+    let mut report = ScrollReport::default();
+    loop {
+        if let Some(found) = probe() {
+            report.found = true;
+            return Ok((Some(found), report));
+        }
+        if report.steps >= options.max_steps {
+            return Ok((None, report));
+        }
+        let before = state();
+        step(&options.step)?;
+        report.steps += 1;
+        thread::sleep(Duration::from_millis(options.settle_ms));
+        if state() == before {
+            report.end_reached = true;
+            let found = probe();
+            report.found = found.is_some();
+            return Ok((found, report));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn options(max_steps: usize) -> ScrollUntilOptions {
+        ScrollUntilOptions {
+            max_steps,
+            settle_ms: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn directions_and_units() {
+        assert_eq!(Scroll::toward("Up", 3, ScrollUnit::Lines).unwrap(), Scroll::lines(0, -3));
+        assert_eq!(Scroll::toward("right", 40, ScrollUnit::Pixels).unwrap(), Scroll::pixels(40, 0));
+        assert!(Scroll::toward("sideways", 1, ScrollUnit::Lines).is_err());
+
+        assert_eq!(Scroll::pixels(-20, 33).in_lines(), (-2, 3));
+        assert_eq!(Scroll::lines(1, -3).wheel_units(), (40, -120));
+        assert_eq!(Scroll::pixels(0, 48).wheel_units(), (0, 120));
+    }
+
+    #[test]
+    fn stops_when_target_shows_up() {
+        let offset = Cell::new(0);
+        let (found, report) = scroll_until(
+            &options(10),
+            || (offset.get() >= 15).then_some(offset.get()),
+            || offset.get(),
+            |s| {
+                offset.set(offset.get() + s.dy);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(found, Some(15));
+        assert_eq!(report, ScrollReport { found: true, steps: 3, end_reached: false });
+    }
+
+    #[test]
+    fn stops_at_end_of_content() {
+        let offset = Cell::new(0);
+        let (found, report) = scroll_until(
+            &options(10),
+            || None::<()>,
+            || offset.get(),
+            |s| {
+                offset.set((offset.get() + s.dy).min(12));
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert!(found.is_none());
+        assert_eq!(report, ScrollReport { found: false, steps: 4, end_reached: true });
+    }
+
+    #[test]
+    fn gives_up_after_max_steps() {
+        let offset = Cell::new(0);
+        let (_, report) = scroll_until(
+            &options(2),
+            || None::<()>,
+            || offset.get(),
+            |s| {
+                offset.set(offset.get() + s.dy);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(report, ScrollReport { found: false, steps: 2, end_reached: false });
+    }
+}
@@ -1,15 +1,19 @@
 //! Selector parsing for finding UI elements
 //!
 //! Syntax:
-//!   role:Button              - exact role match
+//!   role:button              - canonical role, matches on every platform
+//!   role:AXButton            - exact native role match
 //!   name:Submit              - exact name match
 //!   name~:screenpipe         - name contains
 //!   title:Login              - exact title match
 //!   value~:hello             - value contains
 //!   index:42                 - element by index from last tree
+//!   id:3fa9c2b1d0e4          - element by stable id (survives tree changes)
+//!   attr[AXURL]~:github      - any native attribute, compared as text
 //!   role:Button AND name:Sub - compound selector
 
 use crate::error::{Error, Result};
+use crate::role::Role;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,7 +28,7 @@ pub struct Condition {
     pub value: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Attribute {
     Role,
@@ -33,6 +37,9 @@ pub enum Attribute {
     Value,
     Description,
     Index,
+    Id,
+    /// Native attribute by name, e.g. `AXURL`
+    Custom(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -99,6 +106,16 @@ impl Selector {
         }
     }
 
+    pub fn id(id: &str) -> Self {
+        Self {
+            conditions: vec![Condition {
+                attr: Attribute::Id,
+                op: MatchOp::Equals,
+                value: id.to_string(),
+            }],
+        }
+    }
+
     pub fn and(mut self, other: Selector) -> Self {
         self.conditions.extend(other.conditions);
         self
@@ -111,12 +128,22 @@ impl Condition {
             Error::selector_invalid(s, "expected format 'attr:value' or 'attr~:value'")
         })?;
 
-        let (attr, op) = if attr_str.ends_with('~') {
-            (&attr_str[..attr_str.len() - 1], MatchOp::Contains)
-        } else {
-            (attr_str, MatchOp::Equals)
+        let (attr, op) = match attr_str.strip_suffix('~') {
+            Some(attr) => (attr, MatchOp::Contains),
+            None => (attr_str, MatchOp::Equals),
         };
 
+        if let Some(name) = attr.strip_prefix("attr[").and_then(|a| a.strip_suffix(']')) {
+            if name.is_empty() {
+                return Err(Error::selector_invalid(s, "empty attribute name in 'attr[]'"));
+            }
+            return Ok(Self {
+                attr: Attribute::Custom(name.to_string()),
+                op,
+                value: rest.to_string(),
+            });
+        }
+
         let attr = match attr.to_lowercase().as_str() {
             "role" => Attribute::Role,
             "name" => Attribute::Name,
@@ -124,6 +151,7 @@ impl Condition {
             "value" => Attribute::Value,
             "desc" | "description" => Attribute::Description,
             "index" | "idx" => Attribute::Index,
+            "id" => Attribute::Id,
             _ => {
                 return Err(Error::selector_invalid(
                     s,
@@ -141,14 +169,34 @@ impl Condition {
 
     pub fn matches(&self, role: Option<&str>, name: Option<&str>, title: Option<&str>, value: Option<&str>, desc: Option<&str>) -> bool {
         let target = match self.attr {
-            Attribute::Role => role,
+            Attribute::Role => return self.matches_role(role),
             Attribute::Name => name,
             Attribute::Title => title,
             Attribute::Value => value,
             Attribute::Description => desc,
-            Attribute::Index => return false, // handled separately
+            Attribute::Index | Attribute::Id | Attribute::Custom(_) => return false, // handled separately
+        };
+
+        self.matches_value(target)
+    }
+
+    /// Match a native role either directly or through its canonical `Role`
+    pub fn matches_role(&self, native: Option<&str>) -> bool {
+        let Some(native) = native else {
+            return false;
         };
+        if self.matches_value(Some(native)) {
+            return true;
+        }
+        let role = Role::from_native(native);
+        match self.op {
+            MatchOp::Equals => Role::from_name(&self.value) == Some(role),
+            MatchOp::Contains => self.matches_value(Some(role.as_str())),
+        }
+    }
 
+    /// Match this condition's value against an already-resolved attribute
+    pub fn matches_value(&self, target: Option<&str>) -> bool {
         match (target, &self.op) {
             (Some(t), MatchOp::Equals) => t == self.value,
             (Some(t), MatchOp::Contains) => t.to_lowercase().contains(&self.value.to_lowercase()),
@@ -170,7 +218,10 @@ impl std::fmt::Display for Condition {
             MatchOp::Equals => ":",
             MatchOp::Contains => "~:",
         };
-        write!(f, "{:?}{}{}", self.attr, op, self.value)
+        match &self.attr {
+            Attribute::Custom(name) => write!(f, "attr[{}]{}{}", name, op, self.value),
+            attr => write!(f, "{:?}{}{}", attr, op, self.value),
+        }
     }
 }
 
@@ -186,6 +237,20 @@ mod tests {
         assert_eq!(s.conditions[0].value, "Button");
     }
 
+    #[test]
+    fn canonical_role_matches_every_platform() {
+        let s = Selector::parse("role:button").unwrap();
+        let cond = &s.conditions[0];
+        assert!(cond.matches_role(Some("AXButton")));
+        assert!(cond.matches_role(Some("Button")));
+        assert!(cond.matches_role(Some("push button")));
+        assert!(!cond.matches_role(Some("AXCheckBox")));
+
+        let native = Selector::parse("role:AXButton").unwrap();
+        assert!(native.conditions[0].matches_role(Some("AXButton")));
+        assert!(!native.conditions[0].matches_role(Some("Button")));
+    }
+
     #[test]
     fn parse_contains() {
         let s = Selector::parse("name~:screenpipe").unwrap();
@@ -197,4 +262,26 @@ mod tests {
         let s = Selector::parse("role:Button AND name:Submit").unwrap();
         assert_eq!(s.conditions.len(), 2);
     }
+
+    #[test]
+    fn parse_id() {
+        let s = Selector::parse("id:3fa9c2b1d0e4").unwrap();
+        assert_eq!(s.conditions[0].attr, Attribute::Id);
+        assert!(s.conditions[0].matches_value(Some("3fa9c2b1d0e4")));
+        assert!(!s.conditions[0].matches_value(Some("000000000000")));
+    }
+
+    #[test]
+    fn parse_custom_attribute() {
+        let s = Selector::parse("role:Link AND attr[AXURL]~:github.com").unwrap();
+        let cond = &s.conditions[1];
+        assert_eq!(cond.attr, Attribute::Custom("AXURL".to_string()));
+        assert_eq!(cond.op, MatchOp::Contains);
+        assert_eq!(cond.value, "github.com");
+        assert!(!cond.matches(Some("AXLink"), None, None, None, None));
+        assert!(cond.matches_value(Some("https://GitHub.com/mediar-ai")));
+        assert_eq!(s.to_string(), "Role:Link AND attr[AXURL]~:github.com");
+
+        assert!(Selector::parse("attr[]:x").is_err());
+    }
 }
@@ -0,0 +1,266 @@
+//! Tables and grids read as rows and columns
+//!
+//! `Desktop::scrape` flattens a spreadsheet into a bag of strings. A `Table`
+//! keeps the structure: column headers, then rows of cells, each with its
+//! index. Virtualised tables only expose the rows on screen, so readers
+//! scroll and collect rows into `TableRows`, which drops the ones already seen.
+
+use crate::role::Role;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<TableRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableRow {
+    /// Row index within the table, not within what was visible
+    pub index: usize,
+    pub cells: Vec<TableCell>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableCell {
+    pub column: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableOptions {
+    /// Scroll through the table to collect rows that are not on screen
+    pub scroll: bool,
+    /// Stop after this many rows
+    pub max_rows: usize,
+    /// Wait after each scroll for the table to load new rows
+    pub settle_ms: u64,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            scroll: false,
+            max_rows: 10_000,
+            settle_ms: 150,
+        }
+    }
+}
+
+impl TableOptions {
+    pub fn scroll(mut self, scroll: bool) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    pub fn settle(mut self, ms: u64) -> Self {
+        self.settle_ms = ms;
+        self
+    }
+}
+
+/// Roles read as tables: tables, grids and outlines
+pub fn is_table_role(native: &str) -> bool {
+    matches!(Role::from_native(native), Role::Table | Role::Tree)
+}
+
+/// Rows collected across scroll positions, keyed by row index
+#[derive(Debug, Default)]
+pub struct TableRows {
+    rows: BTreeMap<usize, Vec<String>>,
+    /// Unindexed rows read before the last scroll, which may show again
+    last_page: Vec<Vec<String>>,
+    /// Unindexed rows read since the last scroll
+    page: Vec<Vec<String>>,
+}
+
+impl TableRows {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a row; false when it was already collected
+    ///
+    /// Rows are told apart by their index. Rows that don't report one go
+    /// after everything collected so far, unless they repeat a row of the
+    /// page before the last scroll: each of those stands for one repeat.
+    pub fn insert(&mut self, index: Option<usize>, cells: Vec<String>) -> bool {
+        let index = match index {
+            Some(index) if self.rows.contains_key(&index) => return false,
+            Some(index) => index,
+            None => {
+                self.page.push(cells.clone());
+                if let Some(seen) = self.last_page.iter().position(|row| *row == cells) {
+                    self.last_page.remove(seen);
+                    return false;
+                }
+                self.rows.keys().next_back().map_or(0, |last| last + 1)
+            }
+        };
+        self.rows.insert(index, cells);
+        true
+    }
+
+    /// Start a new page, after scrolling the table
+    pub fn scrolled(&mut self) {
+        self.last_page = std::mem::take(&mut self.page);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn into_table(self, headers: Vec<String>) -> Table {
+        let rows = self
+            .rows
+            .into_iter()
+            .map(|(index, cells)| TableRow {
+                index,
+                cells: cells
+                    .into_iter()
+                    .enumerate()
+                    .map(|(column, text)| TableCell { column, text })
+                    .collect(),
+            })
+            .collect();
+        Table { headers, rows }
+    }
+}
+
+impl Table {
+    /// Number of columns, the widest of the header and every row
+    pub fn columns(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|r| r.cells.len())
+            .chain(std::iter::once(self.headers.len()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// RFC 4180 CSV, with a header line when the table has headers
+    ///
+    /// Short rows are padded so every line has the same number of fields.
+    pub fn to_csv(&self) -> String {
+        let width = self.columns();
+        let mut out = String::new();
+        if !self.headers.is_empty() {
+            push_csv_line(&mut out, self.headers.iter().map(String::as_str), width);
+        }
+        for row in &self.rows {
+            let mut fields = vec![""; width];
+            for cell in &row.cells {
+                fields[cell.column] = &cell.text;
+            }
+            push_csv_line(&mut out, fields.into_iter(), width);
+        }
+        out
+    }
+}
+
+fn push_csv_line<'a>(out: &mut String, fields: impl Iterator<Item = &'a str>, width: usize) {
+    let mut fields: Vec<String> = fields.map(csv_field).collect();
+    fields.resize(width, String::new());
+    out.push_str(&fields.join(","));
+    out.push_str("\r\n");
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn rows_are_deduplicated_and_ordered() {
+        let mut rows = TableRows::new();
+        assert!(rows.insert(Some(2), cells(&["c"])));
+        assert!(rows.insert(Some(0), cells(&["a"])));
+        assert!(rows.insert(Some(1), cells(&["b"])));
+        // Scrolling brings row 2 into view again
+        assert!(!rows.insert(Some(2), cells(&["c"])));
+        assert_eq!(rows.len(), 3);
+
+        let table = rows.into_table(vec!["Name".to_string()]);
+        let indices: Vec<usize> = table.rows.iter().map(|r| r.index).collect();
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert_eq!(table.rows[1].cells[0], TableCell { column: 0, text: "b".to_string() });
+    }
+
+    #[test]
+    fn unindexed_rows_are_deduplicated_on_content() {
+        let mut rows = TableRows::new();
+        assert!(rows.insert(None, cells(&["a"])));
+        assert!(rows.insert(None, cells(&["b"])));
+        rows.scrolled();
+        // The row kept in view between two pages
+        assert!(!rows.insert(None, cells(&["b"])));
+        assert!(rows.insert(None, cells(&["c"])));
+
+        let table = rows.into_table(Vec::new());
+        let texts: Vec<&str> = table.rows.iter().map(|r| r.cells[0].text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn identical_rows_in_one_read_are_kept() {
+        let mut rows = TableRows::new();
+        assert!(rows.insert(None, cells(&["x"])));
+        assert!(rows.insert(None, cells(&["x"])));
+        assert!(rows.insert(None, cells(&["y"])));
+        rows.scrolled();
+        assert!(!rows.insert(None, cells(&["y"])));
+        assert!(rows.insert(None, cells(&["z"])));
+        rows.scrolled();
+        // Only the page before the last scroll counts as seen
+        assert!(rows.insert(None, cells(&["x"])));
+        assert_eq!(rows.len(), 5);
+    }
+
+    #[test]
+    fn csv_quotes_and_pads() {
+        let mut rows = TableRows::new();
+        rows.insert(Some(0), cells(&["Widget", "1,200", "says \"hi\""]));
+        rows.insert(Some(1), cells(&["Gadget"]));
+        let table = rows.into_table(cells(&["Item", "Price", "Note"]));
+        assert_eq!(
+            table.to_csv(),
+            "Item,Price,Note\r\nWidget,\"1,200\",\"says \"\"hi\"\"\"\r\nGadget,,\r\n"
+        );
+    }
+
+    #[test]
+    fn csv_without_headers() {
+        let mut rows = TableRows::new();
+        rows.insert(Some(0), cells(&["a", "b"]));
+        assert_eq!(rows.into_table(Vec::new()).to_csv(), "a,b\r\n");
+    }
+
+    #[test]
+    fn table_roles() {
+        assert!(is_table_role("AXTable"));
+        assert!(is_table_role("AXOutline"));
+        assert!(is_table_role("AXGrid"));
+        assert!(!is_table_role("AXList"));
+    }
+}
@@ -0,0 +1,421 @@
+//! Text entry that doesn't depend on the keyboard layout
+//!
+//! Typing through keycodes only works for characters the active layout has
+//! a key for, which leaves out accents on a US layout, emoji and CJK. Each
+//! platform instead offers a stack of strategies, best first: key events
+//! that carry the characters themselves, keysyms mapped onto a spare keycode
+//! on X11, and pasting through the clipboard. Every character goes through
+//! the first strategy that can type it; a run that fails partway goes on
+//! with the next strategy from the first character that didn't make it, so
+//! nothing is typed twice. The report says which characters went which way,
+//! and lists the ones nothing could type instead of dropping them.
+
+use crate::clipboard::{self, Selection};
+use crate::error::{Error, Result};
+use crate::keys::Key;
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+
+/// Texts longer than this are pasted in one go when the platform can paste
+pub const PASTE_OVER_CHARS: usize = 200;
+
+/// Delay between typed characters, so apps don't coalesce or drop them
+pub const KEY_DELAY_MS: u64 = 4;
+
+/// Time for the focused app to read the clipboard before it is restored
+pub const PASTE_SETTLE_MS: u64 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStrategy {
+    /// Key events carrying the characters themselves
+    Unicode,
+    /// A spare keycode remapped to the character's keysym (X11)
+    Keysym,
+    /// Put on the clipboard and pasted, the clipboard restored afterwards
+    Paste,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextEntryOptions {
+    /// Paste texts longer than this many characters; `None` never pastes
+    /// whole texts, though single characters may still fall back to pasting
+    pub paste_over: Option<usize>,
+    /// Pause after each character typed with key events
+    pub key_delay_ms: u64,
+    /// Start this many places down the platform's strategy list, the ones
+    /// passed over going to the end; a retry uses it to try another way
+    pub skip_strategies: usize,
+}
+
+impl Default for TextEntryOptions {
+    fn default() -> Self {
+        Self {
+            paste_over: Some(PASTE_OVER_CHARS),
+            key_delay_ms: KEY_DELAY_MS,
+            skip_strategies: 0,
+        }
+    }
+}
+
+/// Consecutive characters typed with the same strategy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryRun {
+    pub strategy: EntryStrategy,
+    pub text: String,
+}
+
+/// A character no strategy could type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UntypedChar {
+    /// Position in the text, in characters
+    pub index: usize,
+    pub char: char,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextEntryReport {
+    /// Characters that made it
+    pub typed: usize,
+    pub runs: Vec<EntryRun>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub untyped: Vec<UntypedChar>,
+}
+
+impl TextEntryReport {
+    pub fn is_complete(&self) -> bool {
+        self.untyped.is_empty()
+    }
+
+    /// The report, or an error carrying it when characters were left out
+    pub fn into_result(self) -> Result<Self> {
+        if self.is_complete() {
+            return Ok(self);
+        }
+        let chars: String = self.untyped.iter().map(|u| u.char).collect();
+        Err(Error::action_failed(
+            "type",
+            &format!("{} character(s) could not be typed: {:?}", self.untyped.len(), chars),
+        )
+        .with_context(serde_json::to_value(&self).unwrap_or_default()))
+    }
+
+    fn push(&mut self, strategy: EntryStrategy, text: &str) {
+        self.typed += text.chars().count();
+        match self.runs.last_mut() {
+            Some(run) if run.strategy == strategy => run.text.push_str(text),
+            _ => self.runs.push(EntryRun {
+                strategy,
+                text: text.to_string(),
+            }),
+        }
+    }
+}
+
+/// A run that stopped partway
+#[derive(Debug)]
+pub struct RunError {
+    /// Characters of the run that went through before the failure
+    pub typed: usize,
+    pub error: Error,
+}
+
+impl From<Error> for RunError {
+    /// A failure before anything was typed
+    fn from(error: Error) -> Self {
+        Self { typed: 0, error }
+    }
+}
+
+/// One platform's ways of typing text
+pub trait TextEntryBackend {
+    /// Strategies in order of preference
+    fn strategies(&self) -> Vec<EntryStrategy>;
+
+    fn can_type(&self, strategy: EntryStrategy, c: char) -> bool;
+
+    /// Type `text`; on failure, say how many of its characters went through
+    fn type_run(&mut self, strategy: EntryStrategy, text: &str) -> std::result::Result<(), RunError>;
+}
+
+type IndexedChar = (usize, char);
+
+/// Type `text` through the backend's strategy stack
+pub fn enter_with(backend: &mut impl TextEntryBackend, text: &str, options: &TextEntryOptions) -> TextEntryReport {
+    let mut strategies = backend.strategies();
+    if !strategies.is_empty() {
+        let skip = options.skip_strategies % strategies.len();
+        strategies.rotate_left(skip);
+    }
+    let mut report = TextEntryReport::default();
+
+    let long = options.paste_over.is_some_and(|n| text.chars().count() > n);
+    if long && strategies.contains(&EntryStrategy::Paste) && backend.type_run(EntryStrategy::Paste, text).is_ok() {
+        report.push(EntryStrategy::Paste, text);
+        return report;
+    }
+
+    // Group characters by the first strategy that can type them
+    let mut planned: Vec<(Option<EntryStrategy>, Vec<IndexedChar>)> = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        let strategy = strategies.iter().copied().find(|&s| backend.can_type(s, c));
+        match planned.last_mut() {
+            Some((s, chars)) if *s == strategy => chars.push((i, c)),
+            _ => planned.push((strategy, vec![(i, c)])),
+        }
+    }
+
+    for (strategy, chars) in planned {
+        let Some(first) = strategy else {
+            report.untyped.extend(chars.into_iter().map(|(index, char)| UntypedChar {
+                index,
+                char,
+                reason: "no text entry strategy on this platform can type it".to_string(),
+            }));
+            continue;
+        };
+
+        let mut rest: &[IndexedChar] = &chars;
+        let mut last_error = None;
+        let fallbacks = strategies.iter().copied().skip_while(|&s| s != first);
+        let candidates: Vec<EntryStrategy> =
+            fallbacks.filter(|&s| chars.iter().all(|&(_, c)| backend.can_type(s, c))).collect();
+        for strategy in candidates {
+            let run: String = rest.iter().map(|&(_, c)| c).collect();
+            match backend.type_run(strategy, &run) {
+                Ok(()) => {
+                    report.push(strategy, &run);
+                    rest = &[];
+                }
+                Err(e) => {
+                    // Go on from the first character that didn't make it
+                    let typed = e.typed.min(rest.len());
+                    if typed > 0 {
+                        let done: String = rest[..typed].iter().map(|&(_, c)| c).collect();
+                        report.push(strategy, &done);
+                        rest = &rest[typed..];
+                    }
+                    last_error = Some(format!("{:?}: {}", strategy, e.error.message));
+                }
+            }
+            if rest.is_empty() {
+                break;
+            }
+        }
+        if let Some(reason) = last_error.filter(|_| !rest.is_empty()) {
+            report.untyped.extend(rest.iter().map(|&(index, char)| UntypedChar {
+                index,
+                char,
+                reason: reason.clone(),
+            }));
+        }
+    }
+    report
+}
+
+/// Paste `text` with `press_paste`, the platform's paste shortcut, and put
+/// back what was on the clipboard
+///
+/// Only the formats `clipboard` reads are put back, so backends whose
+/// clipboard can hold others check before pasting. On X11 the restored
+/// content is served by the detached process `clipboard::set` leaves behind.
+pub(crate) fn paste_text(text: &str, press_paste: impl FnOnce() -> Result<()>) -> Result<()> {
+    let previous = clipboard::get_all(Selection::Clipboard)?;
+    clipboard::set_text(text)?;
+    let pasted = press_paste();
+    thread::sleep(Duration::from_millis(PASTE_SETTLE_MS));
+    if !previous.is_empty() {
+        clipboard::set(Selection::Clipboard, &previous)?;
+    }
+    pasted
+}
+
+/// X11 keysym that types `c`
+pub fn char_keysym(c: char) -> Option<u32> {
+    if let Some(key) = Key::from_control_char(c) {
+        return Some(key.to_x11_keysym());
+    }
+    match c {
+        c if c.is_control() => None,
+        // Latin-1 keysyms are the code points themselves
+        '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => Some(c as u32),
+        c => Some(0x0100_0000 + c as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    /// Types ASCII with Unicode events, anything but control chars by paste
+    #[derive(Default)]
+    struct Fake {
+        typed: Vec<(EntryStrategy, String)>,
+        unicode_broken: bool,
+        /// Unicode events stop working after this many characters
+        unicode_fails_after: Option<usize>,
+        paste: bool,
+    }
+
+    impl TextEntryBackend for Fake {
+        fn strategies(&self) -> Vec<EntryStrategy> {
+            let mut strategies = vec![EntryStrategy::Unicode];
+            if self.paste {
+                strategies.push(EntryStrategy::Paste);
+            }
+            strategies
+        }
+
+        fn can_type(&self, strategy: EntryStrategy, c: char) -> bool {
+            match strategy {
+                EntryStrategy::Unicode => c.is_ascii() && !c.is_ascii_control(),
+                _ => !c.is_control(),
+            }
+        }
+
+        fn type_run(&mut self, strategy: EntryStrategy, text: &str) -> std::result::Result<(), RunError> {
+            if strategy == EntryStrategy::Unicode && self.unicode_broken {
+                return Err(Error::new(ErrorCode::ActionFailed, "no event source".to_string()).into());
+            }
+            if let (EntryStrategy::Unicode, Some(limit)) = (strategy, self.unicode_fails_after) {
+                if text.chars().count() > limit {
+                    self.typed.push((strategy, text.chars().take(limit).collect()));
+                    return Err(RunError {
+                        typed: limit,
+                        error: Error::new(ErrorCode::ActionFailed, "event queue full".to_string()),
+                    });
+                }
+            }
+            self.typed.push((strategy, text.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn characters_fall_back_per_run() {
+        let mut fake = Fake { paste: true, ..Default::default() };
+        let report = enter_with(&mut fake, "café 😀!", &TextEntryOptions::default());
+        assert!(report.is_complete());
+        assert_eq!(report.typed, 7);
+        let runs: Vec<(EntryStrategy, &str)> = report.runs.iter().map(|r| (r.strategy, r.text.as_str())).collect();
+        assert_eq!(
+            runs,
+            vec![
+                (EntryStrategy::Unicode, "caf"),
+                (EntryStrategy::Paste, "é"),
+                (EntryStrategy::Unicode, " "),
+                (EntryStrategy::Paste, "😀"),
+                (EntryStrategy::Unicode, "!"),
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_is_dropped_silently() {
+        let mut fake = Fake::default();
+        let report = enter_with(&mut fake, "añb\u{7}", &TextEntryOptions::default());
+        assert_eq!(report.typed, 2);
+        let untyped: Vec<(usize, char)> = report.untyped.iter().map(|u| (u.index, u.char)).collect();
+        assert_eq!(untyped, vec![(1, 'ñ'), (3, '\u{7}')]);
+        let err = report.into_result().unwrap_err();
+        assert_eq!(err.code, ErrorCode::ActionFailed);
+        assert!(err.context.is_some());
+    }
+
+    #[test]
+    fn failed_runs_retry_with_the_next_strategy() {
+        let mut fake = Fake {
+            paste: true,
+            unicode_broken: true,
+            ..Default::default()
+        };
+        let report = enter_with(&mut fake, "hi", &TextEntryOptions::default());
+        assert_eq!(report.runs, vec![EntryRun { strategy: EntryStrategy::Paste, text: "hi".into() }]);
+
+        fake.paste = false;
+        let report = enter_with(&mut fake, "hi", &TextEntryOptions::default());
+        assert_eq!(report.untyped.len(), 2);
+        assert!(report.untyped[0].reason.contains("no event source"));
+    }
+
+    #[test]
+    fn failed_runs_resume_where_they_stopped() {
+        let mut fake = Fake {
+            paste: true,
+            unicode_fails_after: Some(3),
+            ..Default::default()
+        };
+        let report = enter_with(&mut fake, "hello", &TextEntryOptions::default());
+        assert!(report.is_complete());
+        assert_eq!(report.typed, 5);
+        let typed: String = fake.typed.iter().map(|(_, text)| text.as_str()).collect();
+        assert_eq!(typed, "hello");
+        assert_eq!(
+            report.runs,
+            vec![
+                EntryRun { strategy: EntryStrategy::Unicode, text: "hel".into() },
+                EntryRun { strategy: EntryStrategy::Paste, text: "lo".into() },
+            ]
+        );
+
+        // Nothing to fall back on: only the rest is untyped
+        let mut fake = Fake {
+            unicode_fails_after: Some(3),
+            ..Default::default()
+        };
+        let report = enter_with(&mut fake, "hello", &TextEntryOptions::default());
+        assert_eq!(report.typed, 3);
+        let untyped: Vec<usize> = report.untyped.iter().map(|u| u.index).collect();
+        assert_eq!(untyped, vec![3, 4]);
+    }
+
+    #[test]
+    fn skipped_strategies_go_last() {
+        let mut fake = Fake { paste: true, ..Default::default() };
+        let options = TextEntryOptions {
+            skip_strategies: 1,
+            ..Default::default()
+        };
+        let report = enter_with(&mut fake, "hi", &options);
+        assert_eq!(report.runs, vec![EntryRun { strategy: EntryStrategy::Paste, text: "hi".into() }]);
+
+        // Once around the list is back at the start
+        let options = TextEntryOptions {
+            skip_strategies: 2,
+            ..Default::default()
+        };
+        let report = enter_with(&mut fake, "hi", &options);
+        assert_eq!(report.runs[0].strategy, EntryStrategy::Unicode);
+    }
+
+    #[test]
+    fn long_texts_are_pasted_whole() {
+        let mut fake = Fake { paste: true, ..Default::default() };
+        let text = "x".repeat(PASTE_OVER_CHARS + 1);
+        let report = enter_with(&mut fake, &text, &TextEntryOptions::default());
+        assert_eq!(report.runs.len(), 1);
+        assert_eq!(report.runs[0].strategy, EntryStrategy::Paste);
+
+        let report = enter_with(
+            &mut fake,
+            &text,
+            &TextEntryOptions {
+                paste_over: None,
+                ..Default::default()
+            },
+        );
+        assert_eq!(report.runs[0].strategy, EntryStrategy::Unicode);
+    }
+
+    #[test]
+    fn keysyms() {
+        assert_eq!(char_keysym('a'), Some(0x61));
+        assert_eq!(char_keysym('é'), Some(0xe9));
+        assert_eq!(char_keysym('€'), Some(0x0100_20ac));
+        assert_eq!(char_keysym('\n'), Some(0xff0d));
+        assert_eq!(char_keysym('\u{7}'), None);
+    }
+}
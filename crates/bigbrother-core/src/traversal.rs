@@ -0,0 +1,309 @@
+//! Budgets for accessibility tree walks
+//!
+//! Electron apps and large web views expose tens of thousands of nodes and
+//! answer each attribute query slowly, so an unbounded walk can take seconds.
+//! A `TraversalPolicy` caps a walk; a `Traversal` tracks one walk against the
+//! policy and records every subtree it left out, and why.
+
+use crate::tree::Bounds;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct TraversalPolicy {
+    /// Stop after visiting this many nodes
+    pub max_nodes: Option<usize>,
+    /// Stop once the walk has run this long
+    pub timeout_ms: Option<u64>,
+    /// Walk at most N children of elements with this role
+    pub child_limits: Vec<(String, usize)>,
+    /// Skip subtrees whose bounds lie entirely outside every screen
+    pub skip_offscreen: bool,
+}
+
+impl Default for TraversalPolicy {
+    fn default() -> Self {
+        Self {
+            max_nodes: Some(10_000),
+            timeout_ms: Some(5000),
+            child_limits: Vec::new(),
+            skip_offscreen: false,
+        }
+    }
+}
+
+impl TraversalPolicy {
+    /// No limits besides depth, the behavior before budgets existed
+    pub fn unlimited() -> Self {
+        Self {
+            max_nodes: None,
+            timeout_ms: None,
+            child_limits: Vec::new(),
+            skip_offscreen: false,
+        }
+    }
+
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn timeout(mut self, ms: u64) -> Self {
+        self.timeout_ms = Some(ms);
+        self
+    }
+
+    pub fn child_limit(mut self, role: &str, max_children: usize) -> Self {
+        self.child_limits.retain(|(r, _)| r != role);
+        self.child_limits.push((role.to_string(), max_children));
+        self
+    }
+
+    pub fn skip_offscreen(mut self, skip: bool) -> Self {
+        self.skip_offscreen = skip;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationReason {
+    MaxDepth,
+    MaxNodes,
+    Deadline,
+    ChildLimit,
+    Offscreen,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Truncation {
+    pub reason: TruncationReason,
+    /// Subtrees left unvisited for this reason
+    pub skipped: usize,
+}
+
+/// One walk in progress against a policy
+pub struct Traversal<'a> {
+    policy: &'a TraversalPolicy,
+    max_depth: usize,
+    screens: Vec<Bounds>,
+    started: Instant,
+    visited: usize,
+    exhausted: Option<TruncationReason>,
+    truncated: Vec<Truncation>,
+}
+
+impl<'a> Traversal<'a> {
+    pub fn new(policy: &'a TraversalPolicy, max_depth: usize) -> Self {
+        Self {
+            policy,
+            max_depth,
+            screens: Vec::new(),
+            started: Instant::now(),
+            visited: 0,
+            exhausted: None,
+            truncated: Vec::new(),
+        }
+    }
+
+    /// Count the deadline from an earlier start, so parallel walks share one budget
+    pub fn with_start(mut self, started: Instant) -> Self {
+        self.started = started;
+        self
+    }
+
+    /// Screens used for the offscreen check; without any, nothing counts as offscreen
+    pub fn with_screens(mut self, screens: Vec<Bounds>) -> Self {
+        self.screens = screens;
+        self
+    }
+
+    /// Whether the walk may visit one more node; counts it if so
+    ///
+    /// Once the node or time budget runs out every later call returns false.
+    pub fn visit(&mut self) -> bool {
+        if self.exhausted.is_none() {
+            if self.policy.max_nodes.is_some_and(|max| self.visited >= max) {
+                self.exhausted = Some(TruncationReason::MaxNodes);
+            } else if self
+                .policy
+                .timeout_ms
+                .is_some_and(|ms| self.started.elapsed() >= Duration::from_millis(ms))
+            {
+                self.exhausted = Some(TruncationReason::Deadline);
+            }
+        }
+
+        if let Some(reason) = self.exhausted {
+            self.record(reason, 1);
+            return false;
+        }
+        self.visited += 1;
+        true
+    }
+
+    /// Whether the policy wants bounds for the offscreen check
+    pub fn checks_offscreen(&self) -> bool {
+        self.policy.skip_offscreen && !self.screens.is_empty()
+    }
+
+    /// True when the subtree at these bounds should be skipped as offscreen
+    pub fn is_offscreen(&mut self, bounds: Option<&Bounds>) -> bool {
+        if !self.checks_offscreen() {
+            return false;
+        }
+        let offscreen = bounds.is_some_and(|b| {
+            !b.is_empty() && !self.screens.iter().any(|s| s.intersects(b))
+        });
+        if offscreen {
+            self.record(TruncationReason::Offscreen, 1);
+        }
+        offscreen
+    }
+
+    /// How many of a node's children to walk
+    pub fn children_to_walk(&mut self, depth: usize, role: &str, children: usize) -> usize {
+        if children == 0 {
+            return 0;
+        }
+        if depth >= self.max_depth {
+            self.record(TruncationReason::MaxDepth, 1);
+            return 0;
+        }
+        let limit = self
+            .policy
+            .child_limits
+            .iter()
+            .find(|(r, _)| r == role)
+            .map(|(_, n)| *n);
+        match limit {
+            Some(limit) if children > limit => {
+                self.record(TruncationReason::ChildLimit, children - limit);
+                limit
+            }
+            _ => children,
+        }
+    }
+
+    pub fn visited(&self) -> usize {
+        self.visited
+    }
+
+    /// What was left out, in the order each reason was first hit
+    pub fn finish(self) -> Vec<Truncation> {
+        self.truncated
+    }
+
+    fn record(&mut self, reason: TruncationReason, skipped: usize) {
+        merge_truncations(&mut self.truncated, [Truncation { reason, skipped }]);
+    }
+}
+
+/// Fold truncation reports from several walks into one, summing per reason
+pub fn merge_truncations(into: &mut Vec<Truncation>, more: impl IntoIterator<Item = Truncation>) {
+    for t in more {
+        match into.iter_mut().find(|i| i.reason == t.reason) {
+            Some(i) => i.skipped += t.skipped,
+            None => into.push(t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal tree for driving a walk without an accessibility API
+    struct FakeNode {
+        role: &'static str,
+        bounds: Option<Bounds>,
+        children: Vec<FakeNode>,
+    }
+
+    fn fake(role: &'static str, children: Vec<FakeNode>) -> FakeNode {
+        FakeNode { role, bounds: None, children }
+    }
+
+    fn walk(t: &mut Traversal, node: &FakeNode, depth: usize, out: &mut Vec<&'static str>) {
+        if !t.visit() || t.is_offscreen(node.bounds.as_ref()) {
+            return;
+        }
+        out.push(node.role);
+        let n = t.children_to_walk(depth, node.role, node.children.len());
+        for child in &node.children[..n] {
+            walk(t, child, depth + 1, out);
+        }
+    }
+
+    fn rows(n: usize) -> Vec<FakeNode> {
+        (0..n).map(|_| fake("AXRow", vec![])).collect()
+    }
+
+    #[test]
+    fn max_nodes_stops_the_walk() {
+        let tree = fake("AXWindow", rows(10));
+        let policy = TraversalPolicy::unlimited().max_nodes(4);
+        let mut t = Traversal::new(&policy, 10);
+        let mut out = Vec::new();
+        walk(&mut t, &tree, 0, &mut out);
+        assert_eq!(out.len(), 4);
+        assert_eq!(
+            t.finish(),
+            vec![Truncation { reason: TruncationReason::MaxNodes, skipped: 7 }]
+        );
+    }
+
+    #[test]
+    fn child_limit_applies_per_role() {
+        let tree = fake(
+            "AXWindow",
+            vec![fake("AXTable", rows(50)), fake("AXGroup", rows(3))],
+        );
+        let policy = TraversalPolicy::unlimited().child_limit("AXTable", 5);
+        let mut t = Traversal::new(&policy, 10);
+        let mut out = Vec::new();
+        walk(&mut t, &tree, 0, &mut out);
+        assert_eq!(out.iter().filter(|r| **r == "AXRow").count(), 8);
+        assert_eq!(
+            t.finish(),
+            vec![Truncation { reason: TruncationReason::ChildLimit, skipped: 45 }]
+        );
+    }
+
+    #[test]
+    fn offscreen_subtrees_are_skipped() {
+        let screen = Bounds { x: 0.0, y: 0.0, width: 100.0, height: 100.0 };
+        let mut tree = fake("AXWindow", vec![fake("AXGroup", rows(3)), fake("AXGroup", rows(3))]);
+        tree.children[0].bounds = Some(Bounds { x: 10.0, y: 10.0, width: 50.0, height: 50.0 });
+        tree.children[1].bounds = Some(Bounds { x: 10.0, y: 500.0, width: 50.0, height: 50.0 });
+
+        let policy = TraversalPolicy::unlimited().skip_offscreen(true);
+        let mut t = Traversal::new(&policy, 10).with_screens(vec![screen]);
+        let mut out = Vec::new();
+        walk(&mut t, &tree, 0, &mut out);
+        assert_eq!(out.len(), 5);
+        assert_eq!(
+            t.finish(),
+            vec![Truncation { reason: TruncationReason::Offscreen, skipped: 1 }]
+        );
+    }
+
+    #[test]
+    fn deadline_and_depth_are_reported() {
+        let tree = fake("AXWindow", vec![fake("AXGroup", rows(2))]);
+
+        let policy = TraversalPolicy::unlimited().timeout(0);
+        let mut t = Traversal::new(&policy, 10);
+        let mut out = Vec::new();
+        walk(&mut t, &tree, 0, &mut out);
+        assert!(out.is_empty());
+        assert_eq!(t.finish()[0].reason, TruncationReason::Deadline);
+
+        let policy = TraversalPolicy::unlimited();
+        let mut t = Traversal::new(&policy, 1);
+        let mut out = Vec::new();
+        walk(&mut t, &tree, 0, &mut out);
+        assert_eq!(out, vec!["AXWindow", "AXGroup"]);
+        assert_eq!(t.finish()[0].reason, TruncationReason::MaxDepth);
+    }
+}
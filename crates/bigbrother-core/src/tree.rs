@@ -0,0 +1,319 @@
+//! Accessibility tree snapshots
+//!
+//! Platform-neutral so snapshots can be saved, loaded and compared anywhere.
+
+use crate::role::Role;
+use crate::traversal::Truncation;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub index: usize,
+    /// Stable id, see `element_id`
+    #[serde(default)]
+    pub id: String,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub focused: bool,
+    /// Screen rectangle in points, top-left origin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<Bounds>,
+    pub depth: usize,
+    pub children_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeResult {
+    pub app: String,
+    pub element_count: usize,
+    pub nodes: Vec<TreeNode>,
+    /// Subtrees the walk left out, see `TraversalPolicy`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated: Vec<Truncation>,
+}
+
+impl Bounds {
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.height <= 0.0
+    }
+
+    pub fn contains(&self, (x, y): (f64, f64)) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+impl TreeNode {
+    pub fn canonical_role(&self) -> Role {
+        Role::from_native(&self.role)
+    }
+
+    /// Label shown for the node: its title, else its accessible name
+    pub fn label(&self) -> Option<&str> {
+        self.title.as_deref().or(self.name.as_deref()).filter(|s| !s.is_empty())
+    }
+
+    /// Label used to tell siblings of the same role apart, see `identity_label`
+    pub fn identity(&self) -> Option<&str> {
+        identity_label(
+            self.title.as_deref(),
+            self.identifier.as_deref(),
+            self.description.as_deref(),
+        )
+    }
+}
+
+impl TreeResult {
+    /// Parent position (into `nodes`) for every node, rebuilt from the DFS depths
+    pub fn parents(&self) -> Vec<Option<usize>> {
+        let mut parents = Vec::with_capacity(self.nodes.len());
+        let mut stack: Vec<usize> = Vec::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            while stack.len() > node.depth {
+                stack.pop();
+            }
+            parents.push(stack.last().copied());
+            stack.push(i);
+        }
+
+        parents
+    }
+
+    /// Identity path for every node: `role(label)[n]` segments from the root down,
+    /// where `n` counts earlier siblings sharing the same role and label
+    pub fn paths(&self) -> Vec<String> {
+        let parents = self.parents();
+        let mut siblings: HashMap<Option<usize>, SiblingPaths> = HashMap::new();
+        let mut paths: Vec<String> = Vec::with_capacity(self.nodes.len());
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let parent = parents[i];
+            let parent_path = parent.map(|p| paths[p].as_str());
+            let path = siblings
+                .entry(parent)
+                .or_default()
+                .child(parent_path, &node.role, node.identity());
+            paths.push(path);
+        }
+
+        paths
+    }
+}
+
+/// Attribute that identifies an element among same-role siblings
+///
+/// Only attributes that stay put while the element is used count: the
+/// accessible name can come from a field's value or its children's text,
+/// and an id derived from it would change as the user types.
+pub fn identity_label<'a>(
+    title: Option<&'a str>,
+    identifier: Option<&'a str>,
+    description: Option<&'a str>,
+) -> Option<&'a str> {
+    [title, identifier, description]
+        .into_iter()
+        .flatten()
+        .find(|s| !s.is_empty())
+}
+
+/// Longest label kept in a path segment
+const SEGMENT_LABEL_CHARS: usize = 40;
+
+/// Path segment for one node: `role(label)[n]`, with `[n]` omitted for the first occurrence
+pub fn path_segment(role: &str, label: Option<&str>, occurrence: usize) -> String {
+    let mut segment = role.to_string();
+    if let Some(label) = label {
+        segment.push_str(&format!("({})", segment_label(label).replace('/', "\\/")));
+    }
+    if occurrence > 0 {
+        segment.push_str(&format!("[{}]", occurrence));
+    }
+    segment
+}
+
+fn segment_label(label: &str) -> String {
+    label.chars().take(SEGMENT_LABEL_CHARS).collect()
+}
+
+/// Builds identity paths for the children of one parent during a live walk
+#[derive(Debug, Default)]
+pub struct SiblingPaths {
+    seen: HashMap<(String, Option<String>), usize>,
+}
+
+impl SiblingPaths {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path of the next child with this role and label
+    ///
+    /// Occurrences are counted on the label as it appears in the path, so
+    /// labels that only differ past the cut still get distinct segments.
+    pub fn child(&mut self, parent_path: Option<&str>, role: &str, label: Option<&str>) -> String {
+        let occurrence = self
+            .seen
+            .entry((role.to_string(), label.map(segment_label)))
+            .or_insert(0);
+        let segment = path_segment(role, label, *occurrence);
+        *occurrence += 1;
+
+        match parent_path {
+            Some(parent) => format!("{}/{}", parent, segment),
+            None => segment,
+        }
+    }
+}
+
+/// Deterministic element id derived from the identity path (FNV-1a, 48 bits)
+///
+/// Unlike `index`, the id survives unrelated changes elsewhere in the tree.
+pub fn element_id(path: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in path.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:012x}", hash & 0xffff_ffff_ffff)
+}
+
+#[cfg(test)]
+impl TreeNode {
+    /// A bare node at `depth`, for tests to fill in with the `with_*` methods
+    pub(crate) fn test(depth: usize, role: &str) -> Self {
+        Self {
+            index: 0,
+            id: String::new(),
+            role: role.to_string(),
+            name: None,
+            title: None,
+            value: None,
+            identifier: None,
+            description: None,
+            focused: false,
+            bounds: None,
+            depth,
+            children_count: 0,
+        }
+    }
+
+    pub(crate) fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub(crate) fn with_value(mut self, value: &str) -> Self {
+        self.value = Some(value.to_string());
+        self
+    }
+
+    pub(crate) fn with_bounds(mut self, bounds: Bounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_disambiguate_siblings() {
+        let tree = TreeResult {
+            app: "Test".to_string(),
+            element_count: 4,
+            nodes: vec![
+                TreeNode::test(0, "AXWindow").with_title("Main"),
+                TreeNode::test(1, "AXButton").with_title("OK"),
+                TreeNode::test(1, "AXButton"),
+                TreeNode::test(1, "AXButton"),
+            ],
+            truncated: Vec::new(),
+        };
+        assert_eq!(
+            tree.paths(),
+            vec![
+                "AXWindow(Main)",
+                "AXWindow(Main)/AXButton(OK)",
+                "AXWindow(Main)/AXButton",
+                "AXWindow(Main)/AXButton[1]",
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_ignore_the_accessible_name() {
+        let mut field = TreeNode::test(1, "AXTextField");
+        let before = TreeResult {
+            app: "Test".to_string(),
+            element_count: 2,
+            nodes: vec![TreeNode::test(0, "AXWindow").with_title("Main"), field.clone()],
+            truncated: Vec::new(),
+        };
+        field.name = Some("typed text".to_string());
+        let after = TreeResult {
+            nodes: vec![TreeNode::test(0, "AXWindow").with_title("Main"), field],
+            ..before.clone()
+        };
+        assert_eq!(before.paths(), after.paths());
+        assert_eq!(identity_label(None, Some(""), Some("Search")), Some("Search"));
+    }
+
+    #[test]
+    fn live_walk_matches_snapshot_paths() {
+        let mut root = SiblingPaths::new();
+        let window = root.child(None, "AXWindow", Some("Main"));
+        let mut children = SiblingPaths::new();
+        children.child(Some(&window), "AXButton", None);
+        let second = children.child(Some(&window), "AXButton", None);
+        assert_eq!(second, "AXWindow(Main)/AXButton[1]");
+    }
+
+    #[test]
+    fn long_labels_that_share_a_prefix_stay_distinct() {
+        let prefix = "x".repeat(SEGMENT_LABEL_CHARS);
+        let mut siblings = SiblingPaths::new();
+        let first = siblings.child(None, "AXStaticText", Some(&format!("{}one", prefix)));
+        let second = siblings.child(None, "AXStaticText", Some(&format!("{}two", prefix)));
+        assert_eq!(first, format!("AXStaticText({})", prefix));
+        assert_eq!(second, format!("AXStaticText({})[1]", prefix));
+    }
+
+    #[test]
+    fn ids_are_stable_and_distinct() {
+        let a = element_id("AXWindow(Main)/AXButton(OK)");
+        assert_eq!(a, element_id("AXWindow(Main)/AXButton(OK)"));
+        assert_ne!(a, element_id("AXWindow(Main)/AXButton(Cancel)"));
+        assert_eq!(a.len(), 12);
+    }
+}
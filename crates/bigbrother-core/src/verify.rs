@@ -0,0 +1,454 @@
+//! Typing that checks the field afterwards
+//!
+//! Typed text doesn't always land as typed: slow apps drop characters,
+//! auto-correct swaps quotes and words, and focus can move mid-way. Verified
+//! typing reads the field back and compares it with what was meant, after
+//! normalizing both. On a mismatch the field is cleared and the text typed
+//! again, slower each time, key by key rather than pasted whole and starting
+//! further down the platform's text entry strategies, see
+//! `crate::text_entry`; when the last attempt still reads wrong, the
+//! `ValueMismatch` error carries expected and actual side by side. Fields
+//! that hide their value, such as password fields, can't be checked and
+//! fail with `ValueUnreadable` after the first attempt.
+//!
+//! A field that wasn't cleared first is read before typing too, and has to
+//! read what it held with the text inserted at some point, since typing goes
+//! in at the cursor; after a clear it has to match exactly.
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::text_entry::TextEntryOptions;
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Attempts before giving up, the first one included
+pub const VERIFY_ATTEMPTS: usize = 3;
+
+/// How long a field gets to show the typed text before it counts as wrong
+pub const READ_BACK_TIMEOUT_MS: u64 = 300;
+
+const READ_BACK_POLL_MS: u64 = 25;
+
+/// Key delay multiplier for each retry
+const RETRY_SLOWDOWN: u64 = 4;
+
+/// Slowest key delay retries go to
+const MAX_RETRY_KEY_DELAY_MS: u64 = 50;
+
+/// What differences between typed and read-back text don't count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Normalization {
+    /// Ignore leading and trailing whitespace
+    pub trim: bool,
+    /// Treat `\r\n` and `\r` as `\n`
+    pub unify_newlines: bool,
+    /// Treat any run of whitespace, newlines included, as one space
+    pub collapse_whitespace: bool,
+    pub ignore_case: bool,
+    /// Read curly quotes, dashes and ellipses that auto-correct puts in as
+    /// their ASCII originals
+    pub plain_punctuation: bool,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            trim: true,
+            unify_newlines: true,
+            collapse_whitespace: false,
+            ignore_case: false,
+            plain_punctuation: false,
+        }
+    }
+}
+
+impl Normalization {
+    /// Compare byte for byte
+    pub fn exact() -> Self {
+        Self {
+            trim: false,
+            unify_newlines: false,
+            ..Default::default()
+        }
+    }
+
+    /// As lenient as it gets, for fields that reformat what they're given
+    pub fn loose() -> Self {
+        Self {
+            trim: true,
+            unify_newlines: true,
+            collapse_whitespace: true,
+            ignore_case: true,
+            plain_punctuation: true,
+        }
+    }
+
+    /// `default`, `exact` or `loose`
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "default" => Ok(Self::default()),
+            "exact" => Ok(Self::exact()),
+            "loose" => Ok(Self::loose()),
+            _ => Err(Error::invalid_argument("normalization", name, "expected default, exact or loose")),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.unify_newlines {
+            text = text.replace("\r\n", "\n").replace('\r', "\n");
+        }
+        if self.plain_punctuation {
+            text = text
+                .chars()
+                .map(|c| match c {
+                    '\u{2018}' | '\u{2019}' => "'".to_string(),
+                    '\u{201c}' | '\u{201d}' => "\"".to_string(),
+                    '\u{2013}' | '\u{2014}' => "-".to_string(),
+                    '\u{2026}' => "...".to_string(),
+                    '\u{a0}' => " ".to_string(),
+                    c => c.to_string(),
+                })
+                .collect();
+        }
+        if self.collapse_whitespace {
+            text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        } else if self.trim {
+            text = text.trim().to_string();
+        }
+        if self.ignore_case {
+            text = text.to_lowercase();
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    pub normalization: Normalization,
+    /// Attempts before giving up, the first one included
+    pub attempts: usize,
+    /// Clear the field before the first attempt too, so it must match exactly
+    pub clear_first: bool,
+    /// How long the field gets to show the text after each attempt
+    pub read_timeout_ms: u64,
+    /// How the first attempt types; retries slow its key delay down, type
+    /// instead of pasting long texts and move down the strategy list
+    pub entry: TextEntryOptions,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            normalization: Normalization::default(),
+            attempts: VERIFY_ATTEMPTS,
+            clear_first: false,
+            read_timeout_ms: READ_BACK_TIMEOUT_MS,
+            entry: TextEntryOptions::default(),
+        }
+    }
+}
+
+impl VerifyOptions {
+    /// Text entry options for attempt `n`, counting from 0
+    pub fn attempt_entry(&self, n: usize) -> TextEntryOptions {
+        if n == 0 {
+            return self.entry.clone();
+        }
+        let slower = self.entry.key_delay_ms.max(1) * RETRY_SLOWDOWN.pow(n as u32);
+        TextEntryOptions {
+            paste_over: None,
+            key_delay_ms: slower.min(MAX_RETRY_KEY_DELAY_MS).max(self.entry.key_delay_ms),
+            skip_strategies: self.entry.skip_strategies + n,
+        }
+    }
+}
+
+/// One round of typing and reading back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypingAttempt {
+    pub key_delay_ms: u64,
+    /// Whether the field was cleared before typing
+    pub cleared: bool,
+    /// What the field read afterwards; `None` when it has no readable value
+    pub actual: Option<String>,
+    pub matched: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerifiedTyping {
+    pub expected: String,
+    pub attempts: Vec<TypingAttempt>,
+}
+
+impl VerifiedTyping {
+    pub fn matched(&self) -> bool {
+        self.attempts.last().is_some_and(|a| a.matched)
+    }
+}
+
+/// A field that can be typed into, read back and cleared
+pub trait TypingTarget {
+    fn type_text(&mut self, text: &str, options: &TextEntryOptions) -> Result<()>;
+
+    /// The field's value, `None` when it doesn't expose one
+    fn read(&mut self) -> Option<String>;
+
+    fn clear(&mut self) -> Result<()>;
+}
+
+/// Type `text` into `target` until it reads back right or attempts run out
+pub fn type_verified(target: &mut impl TypingTarget, text: &str, options: &VerifyOptions) -> Result<VerifiedTyping> {
+    let expected = options.normalization.apply(text);
+    let mut report = VerifiedTyping {
+        expected: text.to_string(),
+        attempts: Vec::new(),
+    };
+
+    for n in 0..options.attempts.max(1) {
+        let cleared = n > 0 || options.clear_first;
+        let before = if cleared {
+            target.clear()?;
+            None
+        } else {
+            target.read()
+        };
+        let entry = options.attempt_entry(n);
+        target.type_text(text, &entry)?;
+
+        let matches = |actual: &str| {
+            let actual = options.normalization.apply(actual);
+            match &before {
+                Some(before) => inserted_anywhere(before, text)
+                    .any(|candidate| options.normalization.apply(&candidate) == actual),
+                None => actual == expected,
+            }
+        };
+        let actual = read_back(target, options.read_timeout_ms, matches);
+        let matched = actual.as_deref().is_some_and(matches);
+        report.attempts.push(TypingAttempt {
+            key_delay_ms: entry.key_delay_ms,
+            cleared,
+            actual,
+            matched,
+        });
+        if matched {
+            return Ok(report);
+        }
+        if report.attempts.last().is_some_and(|a| a.actual.is_none()) {
+            // Nothing to compare against; retrying won't change that
+            return Err(unreadable(&report));
+        }
+    }
+    Err(mismatch(&report, &options.normalization))
+}
+
+/// `before` with `text` inserted at each character boundary in turn
+fn inserted_anywhere<'a>(before: &'a str, text: &'a str) -> impl Iterator<Item = String> + 'a {
+    before
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(before.len()))
+        .map(move |i| format!("{}{}{}", &before[..i], text, &before[i..]))
+}
+
+/// Poll the field until it matches or the timeout passes, returning the last read
+fn read_back(target: &mut impl TypingTarget, timeout_ms: u64, matches: impl Fn(&str) -> bool) -> Option<String> {
+    let start = Instant::now();
+    loop {
+        let actual = target.read();
+        let done = actual.as_deref().is_none_or(&matches);
+        if done || start.elapsed() >= Duration::from_millis(timeout_ms) {
+            return actual;
+        }
+        thread::sleep(Duration::from_millis(READ_BACK_POLL_MS));
+    }
+}
+
+fn mismatch(report: &VerifiedTyping, normalization: &Normalization) -> Error {
+    let last = report.attempts.last();
+    let actual = last.and_then(|a| a.actual.clone());
+    let expected_norm = normalization.apply(&report.expected);
+    let actual_norm = actual.as_deref().map(|a| normalization.apply(a));
+    let first_difference = actual_norm.as_deref().map(|a| {
+        expected_norm
+            .chars()
+            .zip(a.chars())
+            .position(|(e, a)| e != a)
+            .unwrap_or_else(|| expected_norm.chars().count().min(a.chars().count()))
+    });
+
+    Error::new(
+        ErrorCode::ValueMismatch,
+        format!(
+            "Field reads {:?} instead of {:?} after {} attempt(s)",
+            actual.as_deref().unwrap_or_default(),
+            report.expected,
+            report.attempts.len()
+        ),
+    )
+    .with_suggestions(vec![
+        "Loosen the normalization if the field reformats input".to_string(),
+        "Check that the field has focus and accepts this text".to_string(),
+    ])
+    .with_context(serde_json::json!({
+            "expected": report.expected,
+            "actual": actual,
+            "normalized_expected": expected_norm,
+            "normalized_actual": actual_norm,
+            "first_difference": first_difference,
+            "attempts": report.attempts,
+        }))
+}
+
+/// The text went in, but the field won't show it
+fn unreadable(report: &VerifiedTyping) -> Error {
+    Error::new(
+        ErrorCode::ValueUnreadable,
+        "Field has no readable value to check the typed text against".to_string(),
+    )
+    .with_suggestions(vec!["Secure fields hide their value; type without verification".to_string()])
+    .with_context(serde_json::json!({
+        "expected": report.expected,
+        "attempts": report.attempts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_entry::PASTE_OVER_CHARS;
+
+    /// A field that drops every character after `keeps` unless typed slowly
+    struct Field {
+        value: Option<String>,
+        keeps: usize,
+        slow_enough_ms: u64,
+        typed: Vec<u64>,
+    }
+
+    impl Field {
+        fn new(keeps: usize, slow_enough_ms: u64) -> Self {
+            Self {
+                value: Some(String::new()),
+                keeps,
+                slow_enough_ms,
+                typed: Vec::new(),
+            }
+        }
+    }
+
+    impl TypingTarget for Field {
+        fn type_text(&mut self, text: &str, options: &TextEntryOptions) -> Result<()> {
+            self.typed.push(options.key_delay_ms);
+            let landed: String = if options.key_delay_ms >= self.slow_enough_ms {
+                text.to_string()
+            } else {
+                text.chars().take(self.keeps).collect()
+            };
+            if let Some(value) = &mut self.value {
+                value.push_str(&landed);
+            }
+            Ok(())
+        }
+
+        fn read(&mut self) -> Option<String> {
+            self.value.clone()
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            if let Some(value) = &mut self.value {
+                value.clear();
+            }
+            Ok(())
+        }
+    }
+
+    fn options() -> VerifyOptions {
+        VerifyOptions {
+            read_timeout_ms: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalization() {
+        let n = Normalization::default();
+        assert_eq!(n.apply("  a\r\nb \n"), "a\nb");
+        assert_eq!(Normalization::exact().apply(" a\r\n"), " a\r\n");
+        assert_eq!(Normalization::loose().apply("It\u{2019}s  a\n\u{201c}Test\u{201d}\u{2026}"), "it's a \"test\"...");
+        assert_eq!(Normalization::parse("Exact").unwrap(), Normalization::exact());
+        assert_eq!(Normalization::parse("fuzzy").unwrap_err().code, ErrorCode::InvalidArgument);
+    }
+
+    #[test]
+    fn retries_slower_after_clearing() {
+        let mut field = Field::new(3, 16);
+        let report = type_verified(&mut field, "hello", &options()).unwrap();
+        assert!(report.matched());
+        assert_eq!(field.typed, vec![4, 16]);
+        assert_eq!(field.value.as_deref(), Some("hello"));
+        let cleared: Vec<bool> = report.attempts.iter().map(|a| a.cleared).collect();
+        assert_eq!(cleared, vec![false, true]);
+        assert_eq!(report.attempts[0].actual.as_deref(), Some("hel"));
+    }
+
+    #[test]
+    fn retries_try_another_way() {
+        let options = options();
+        let first = options.attempt_entry(0);
+        assert_eq!((first.paste_over, first.skip_strategies), (Some(PASTE_OVER_CHARS), 0));
+        let retry = options.attempt_entry(2);
+        assert_eq!((retry.paste_over, retry.skip_strategies), (None, 2));
+    }
+
+    #[test]
+    fn uncleared_fields_need_the_text_added_to_what_they_held() {
+        let mut field = Field::new(usize::MAX, 0);
+        field.value = Some("Dear ".to_string());
+        let report = type_verified(&mut field, "Sir", &options()).unwrap();
+        assert_eq!(report.attempts.len(), 1);
+
+        // "hello" already in the field doesn't pass for a typed "hello"
+        // that only partly landed
+        let mut field = Field::new(2, 16);
+        field.value = Some("hello".to_string());
+        let report = type_verified(&mut field, "hello", &options()).unwrap();
+        assert_eq!(report.attempts[0].actual.as_deref(), Some("hellohe"));
+        assert!(!report.attempts[0].matched);
+        assert_eq!(field.value.as_deref(), Some("hello"));
+        assert_eq!(report.attempts.len(), 2);
+
+        let mut field = Field::new(usize::MAX, 0);
+        field.value = Some("Dear ".to_string());
+        let cleared = VerifyOptions {
+            clear_first: true,
+            ..options()
+        };
+        type_verified(&mut field, "Sir", &cleared).unwrap();
+        assert_eq!(field.value.as_deref(), Some("Sir"));
+    }
+
+    #[test]
+    fn mismatch_shows_expected_and_actual() {
+        let mut field = Field::new(2, u64::MAX);
+        let err = type_verified(&mut field, "hello", &options()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ValueMismatch);
+        assert_eq!(field.typed, vec![4, 16, 50]);
+        let context = err.context.unwrap();
+        assert_eq!(context["expected"], "hello");
+        assert_eq!(context["actual"], "he");
+        assert_eq!(context["first_difference"], 2);
+        assert_eq!(context["attempts"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn unreadable_fields_fail_without_retrying() {
+        let mut field = Field::new(usize::MAX, 0);
+        field.value = None;
+        let err = type_verified(&mut field, "secret", &options()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ValueUnreadable);
+        assert_eq!(field.typed.len(), 1);
+        assert!(err.context.unwrap()["attempts"][0]["actual"].is_null());
+    }
+}
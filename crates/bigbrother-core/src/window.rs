@@ -0,0 +1,79 @@
+//! Top-level windows
+//!
+//! `Window` itself is per platform: EWMH over X11 on Linux, AX windows on
+//! macOS. Both offer the same calls (listing per app, title, bounds, state,
+//! move/resize/minimize/maximize/restore/close/raise); this module holds the
+//! snapshot type and the lookup rules they share.
+
+use crate::error::{Error, ErrorCode};
+use crate::tree::Bounds;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Snapshot of one window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowInfo {
+    /// Native window id (X11 window), when the platform has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<i32>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<Bounds>,
+    pub focused: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+}
+
+/// Index of the window a title refers to
+///
+/// An exact title wins, then a case-insensitive substring, so `"Untitled"`
+/// finds `"Untitled 2 — Edited"` when nothing is called exactly that.
+pub fn pick_window(titles: &[String], wanted: &str) -> Option<usize> {
+    titles.iter().position(|t| t == wanted).or_else(|| {
+        let wanted = wanted.to_lowercase();
+        titles.iter().position(|t| t.to_lowercase().contains(&wanted))
+    })
+}
+
+/// Error for a title that matched none of an app's windows
+pub fn window_not_found(app: &str, wanted: &str, titles: &[String]) -> Error {
+    Error::new(
+        ErrorCode::ElementNotFound,
+        format!("No window of '{}' titled '{}'", app, wanted),
+    )
+    .with_suggestions(vec![if titles.is_empty() {
+        format!("'{}' has no open windows", app)
+    } else {
+        format!("Open windows: {}", titles.join(", "))
+    }])
+    .with_context(json!({ "app": app, "title": wanted, "windows": titles }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(items: &[&str]) -> Vec<String> {
+        items.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn exact_title_beats_substring() {
+        let windows = titles(&["Notes — Draft", "Notes", "Inbox"]);
+        assert_eq!(pick_window(&windows, "Notes"), Some(1));
+        assert_eq!(pick_window(&windows, "draft"), Some(0));
+        assert_eq!(pick_window(&windows, "Trash"), None);
+    }
+
+    #[test]
+    fn not_found_lists_open_windows() {
+        let err = window_not_found("Mail", "Trash", &titles(&["Inbox", "Sent"]));
+        assert_eq!(err.code, ErrorCode::ElementNotFound);
+        assert_eq!(err.suggestions, vec!["Open windows: Inbox, Sent".to_string()]);
+        assert_eq!(err.context.unwrap()["windows"], json!(["Inbox", "Sent"]));
+    }
+}
@@ -0,0 +1,109 @@
+//! Drag detection shared by the recorders
+//!
+//! A press is recorded as a `Click` straight away, before anyone knows what
+//! comes next. When the pointer then moves far enough with the button still
+//! held, the press turns out to start a drag: a `MouseDown` at the press
+//! point follows, then `Drag` samples and a `MouseUp` where the button comes
+//! up. On replay the `MouseDown` takes over that press, see
+//! `click_starts_drag`.
+
+use crate::events::{Event, EventData};
+
+/// How far the pointer has to move with a button held to count as a drag
+pub const DRAG_START_PX: f64 = 4.0;
+
+#[derive(Debug, Default)]
+pub struct DragTracker {
+    pressed: Option<(i32, i32, u8)>,
+    dragging: bool,
+}
+
+impl DragTracker {
+    pub fn press(&mut self, x: i32, y: i32, b: u8) {
+        self.pressed = Some((x, y, b));
+        self.dragging = false;
+    }
+
+    /// Events for the pointer moving to (x, y), or `None` when no button is
+    /// held and this is a plain move
+    pub fn motion(&mut self, x: i32, y: i32) -> Option<Vec<EventData>> {
+        let (px, py, b) = self.pressed?;
+        let mut events = Vec::new();
+        if !self.dragging {
+            if ((x - px) as f64).hypot((y - py) as f64) < DRAG_START_PX {
+                return Some(events);
+            }
+            self.dragging = true;
+            events.push(EventData::MouseDown { x: px, y: py, b });
+        }
+        events.push(EventData::Drag { x, y, b });
+        Some(events)
+    }
+
+    /// `MouseUp` when the press turned into a drag; a plain click needs nothing
+    pub fn release(&mut self, x: i32, y: i32) -> Option<EventData> {
+        let (_, _, b) = self.pressed.take()?;
+        std::mem::take(&mut self.dragging).then_some(EventData::MouseUp { x, y, b })
+    }
+}
+
+/// Whether the `Click` at `i` is the press of a drag that a later
+/// `MouseDown` replays, so the click itself should be skipped
+pub fn click_starts_drag(events: &[Event], i: usize) -> bool {
+    let EventData::Click { x, y, b, .. } = events[i].data else {
+        return false;
+    };
+    events[i + 1..]
+        .iter()
+        .map(|e| &e.data)
+        .find(|d| {
+            !matches!(
+                d,
+                EventData::Context { .. } | EventData::App { .. } | EventData::Window { .. } | EventData::Paste { .. }
+            )
+        })
+        .is_some_and(|d| *d == EventData::MouseDown { x, y, b })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_wiggle_is_still_a_click() {
+        let mut tracker = DragTracker::default();
+        assert!(tracker.motion(10, 10).is_none());
+        tracker.press(10, 10, 0);
+        assert_eq!(tracker.motion(12, 11), Some(vec![]));
+        assert_eq!(tracker.release(12, 11), None);
+        assert!(tracker.motion(40, 40).is_none());
+    }
+
+    #[test]
+    fn press_and_move_becomes_drag() {
+        let mut tracker = DragTracker::default();
+        tracker.press(10, 10, 1);
+        assert_eq!(
+            tracker.motion(20, 10),
+            Some(vec![EventData::MouseDown { x: 10, y: 10, b: 1 }, EventData::Drag { x: 20, y: 10, b: 1 }])
+        );
+        assert_eq!(tracker.motion(30, 12), Some(vec![EventData::Drag { x: 30, y: 12, b: 1 }]));
+        assert_eq!(tracker.release(31, 12), Some(EventData::MouseUp { x: 31, y: 12, b: 1 }));
+    }
+
+    #[test]
+    fn clicks_that_start_drags_are_skipped() {
+        let event = |data| Event { t: 0, data };
+        let events = vec![
+            event(EventData::Click { x: 5, y: 5, b: 0, n: 1, m: 0 }),
+            event(EventData::Context { r: "AXCell".into(), n: None, v: None }),
+            event(EventData::MouseDown { x: 5, y: 5, b: 0 }),
+            event(EventData::Drag { x: 50, y: 5, b: 0 }),
+            event(EventData::MouseUp { x: 50, y: 5, b: 0 }),
+            event(EventData::Click { x: 50, y: 5, b: 0, n: 1, m: 0 }),
+        ];
+        assert!(click_starts_drag(&events, 0));
+        assert!(!click_starts_drag(&events, 5));
+        assert!(!click_starts_drag(&events, 2));
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Events are stored as simple structs that serialize to compact JSON lines.
 
+use bigbrother_core::{Chord, Key, Modifier, PacingPolicy};
 use serde::{Deserialize, Serialize};
 
 /// A recorded workflow - just a list of events
@@ -31,7 +32,7 @@ pub struct Event {
 }
 
 /// Event data - simple tagged union
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "e")]
 pub enum EventData {
     /// Mouse click: x, y, button (0=left, 1=right, 2=middle), clicks (1=single, 2=double)
@@ -42,13 +43,37 @@ pub enum EventData {
     #[serde(rename = "m")]
     Move { x: i32, y: i32 },
 
+    /// Button pressed to start a drag: x, y, button
+    #[serde(rename = "d")]
+    MouseDown { x: i32, y: i32, b: u8 },
+
+    /// Pointer moved with the button held: x, y, button
+    #[serde(rename = "g")]
+    Drag { x: i32, y: i32, b: u8 },
+
+    /// Button released at the end of a drag: x, y, button
+    #[serde(rename = "u")]
+    MouseUp { x: i32, y: i32, b: u8 },
+
     /// Mouse scroll: x, y, dx, dy
     #[serde(rename = "s")]
     Scroll { x: i32, y: i32, dx: i16, dy: i16 },
 
-    /// Key down: keycode, modifiers
+    /// Key down: raw keycode, modifiers, logical chord, recording platform
+    ///
+    /// `k` and `m` are exactly what the recording platform reported. `n` is
+    /// the same key as a platform-neutral chord such as `cmd+c`, which is what
+    /// replay on another platform goes by. Recordings from before `n` and `o`
+    /// existed have neither, and were made on macOS.
     #[serde(rename = "k")]
-    Key { k: u16, m: u8 },
+    Key {
+        k: u16,
+        m: u8,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        n: Option<Chord>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        o: Option<String>,
+    },
 
     /// Text input (aggregated keystrokes)
     #[serde(rename = "t")]
@@ -81,6 +106,93 @@ pub enum EventData {
     },
 }
 
+impl EventData {
+    /// Where the pointer is once this event has happened
+    pub fn position(&self) -> Option<(i32, i32)> {
+        match *self {
+            EventData::Click { x, y, .. }
+            | EventData::Move { x, y }
+            | EventData::MouseDown { x, y, .. }
+            | EventData::Drag { x, y, .. }
+            | EventData::MouseUp { x, y, .. }
+            | EventData::Scroll { x, y, .. } => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    /// Where a replayer may glide the pointer to before this event; moves
+    /// with a button held are already sampled and play back as recorded
+    pub fn glide_target(&self) -> Option<(i32, i32)> {
+        match self {
+            EventData::Drag { .. } | EventData::MouseUp { .. } => None,
+            _ => self.position(),
+        }
+    }
+
+    /// Key event as recorded on this platform, with its logical chord when known
+    pub fn key(k: u16, m: Modifiers, key: Option<Key>) -> Self {
+        EventData::Key {
+            k,
+            m: m.0,
+            n: key.map(|key| m.chord(key)),
+            o: Some(std::env::consts::OS.to_string()),
+        }
+    }
+}
+
+/// Platform recordings without `o` were made on
+const LEGACY_OS: &str = "macos";
+
+/// Keycode and modifiers to send for a recorded key on this platform
+///
+/// The logical chord wins when there is one; Cmd in a chord recorded on
+/// macOS becomes Ctrl elsewhere, so `cmd+c` still copies. The raw code is
+/// only trusted when it was recorded here. `None` means the key can't be
+/// replayed here.
+pub fn replay_key(
+    k: u16,
+    m: u8,
+    n: Option<&Chord>,
+    o: Option<&str>,
+    native: impl Fn(Key) -> u16,
+) -> Option<(u16, Modifiers)> {
+    replay_key_on(std::env::consts::OS, k, m, n, o, native)
+}
+
+fn replay_key_on(
+    here: &str,
+    k: u16,
+    m: u8,
+    n: Option<&Chord>,
+    o: Option<&str>,
+    native: impl Fn(Key) -> u16,
+) -> Option<(u16, Modifiers)> {
+    let recorded_on = o.unwrap_or(LEGACY_OS);
+    match n {
+        Some(chord) => {
+            let mut mods = Modifiers::from_chord(chord);
+            if recorded_on == "macos" && here != "macos" && mods.has_cmd() {
+                mods = Modifiers((mods.0 & !Modifiers::CMD) | Modifiers::CTRL);
+            }
+            Some((native(chord.key), mods))
+        }
+        None if recorded_on == here => Some((k, Modifiers(m))),
+        None => None,
+    }
+}
+
+/// Pause after each replayed click, key, scroll or text, unless the
+/// replayer is given another pacing policy
+pub const REPLAY_SETTLE_MS: u64 = 10;
+
+/// Pacing replays use by default
+pub fn replay_pacing() -> PacingPolicy {
+    PacingPolicy {
+        settle_ms: REPLAY_SETTLE_MS,
+        ..Default::default()
+    }
+}
+
 /// Modifier flags packed into a single byte
 /// Bit 0: shift, 1: ctrl, 2: option/alt, 3: command, 4: capslock, 5: fn
 #[derive(Debug, Clone, Copy, Default)]
@@ -108,4 +220,98 @@ impl Modifiers {
     pub fn has_cmd(&self) -> bool { self.0 & Self::CMD != 0 }
     pub fn has_ctrl(&self) -> bool { self.0 & Self::CTRL != 0 }
     pub fn any_modifier(&self) -> bool { self.0 & (Self::CMD | Self::CTRL) != 0 }
+
+    /// `key` with these modifiers held; caps lock and fn are dropped
+    pub fn chord(&self, key: Key) -> Chord {
+        self.held().into_iter().fold(Chord::new(key), Chord::with)
+    }
+
+    pub fn from_chord(chord: &Chord) -> Self {
+        let mut m = 0u8;
+        for modifier in &chord.modifiers {
+            m |= match modifier {
+                Modifier::Shift => Self::SHIFT,
+                Modifier::Control => Self::CTRL,
+                Modifier::Alt => Self::OPT,
+                Modifier::Meta => Self::CMD,
+            };
+        }
+        Self(m)
+    }
+
+    /// Modifiers a replayer has to hold down, in chord order
+    pub fn held(&self) -> Vec<Modifier> {
+        [
+            (Self::SHIFT, Modifier::Shift),
+            (Self::CTRL, Modifier::Control),
+            (Self::OPT, Modifier::Alt),
+            (Self::CMD, Modifier::Meta),
+        ]
+        .into_iter()
+        .filter(|(bit, _)| self.0 & bit != 0)
+        .map(|(_, modifier)| modifier)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_events_carry_logical_chord() {
+        let event = EventData::key(8, Modifiers(Modifiers::CMD), Some(Key::C));
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["k"], 8);
+        assert_eq!(json["n"], "cmd+c");
+        assert_eq!(json["o"], std::env::consts::OS);
+
+        let EventData::Key { n, .. } = serde_json::from_value(json).unwrap() else {
+            panic!("not a key event");
+        };
+        assert_eq!(n, Some(Chord::parse("cmd+c").unwrap()));
+    }
+
+    #[test]
+    fn glide_targets_skip_held_buttons() {
+        assert_eq!(EventData::Move { x: 3, y: 4 }.glide_target(), Some((3, 4)));
+        assert_eq!(EventData::Click { x: 1, y: 2, b: 0, n: 1, m: 0 }.glide_target(), Some((1, 2)));
+        assert_eq!(EventData::Drag { x: 5, y: 6, b: 0 }.glide_target(), None);
+        assert_eq!(EventData::Drag { x: 5, y: 6, b: 0 }.position(), Some((5, 6)));
+        assert_eq!(EventData::Text { s: "hi".into() }.position(), None);
+    }
+
+    #[test]
+    fn legacy_key_events_still_parse() {
+        let event: EventData = serde_json::from_str(r#"{"e":"k","k":0,"m":8}"#).unwrap();
+        assert!(matches!(event, EventData::Key { k: 0, m: 8, n: None, o: None }));
+    }
+
+    #[test]
+    fn replay_prefers_logical_chord() {
+        let chord = Chord::parse("cmd+shift+c").unwrap();
+        let (code, mods) = replay_key_on("macos", 8, 0, Some(&chord), Some("macos"), Key::to_mac).unwrap();
+        assert_eq!(code, Key::C.to_mac());
+        assert_eq!(mods.0, Modifiers::CMD | Modifiers::SHIFT);
+
+        let raw = |here, o| replay_key_on(here, 36, 1, None, o, Key::to_vk).map(|(k, m)| (k, m.0));
+        assert_eq!(raw("windows", Some("windows")), Some((36, 1)));
+        assert!(raw("windows", Some("linux")).is_none());
+        // Recordings from before `o` were made on macOS
+        assert_eq!(raw("macos", None), Some((36, 1)));
+        assert!(raw("windows", None).is_none());
+    }
+
+    #[test]
+    fn cmd_becomes_ctrl_off_macos() {
+        let chord = Chord::parse("cmd+shift+c").unwrap();
+        let (code, mods) = replay_key_on("windows", 8, 0, Some(&chord), None, Key::to_vk).unwrap();
+        assert_eq!(code, Key::C.to_vk());
+        assert_eq!(mods.0, Modifiers::CTRL | Modifiers::SHIFT);
+
+        // The Windows key recorded on Windows stays the Windows key
+        let chord = Chord::parse("win+e").unwrap();
+        let (_, mods) = replay_key_on("windows", 0x45, 0, Some(&chord), Some("windows"), Key::to_vk).unwrap();
+        assert_eq!(mods.0, Modifiers::CMD);
+    }
 }
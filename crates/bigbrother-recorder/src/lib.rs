@@ -9,6 +9,7 @@
 //! - **Windows**: Full support via rdev + SendInput
 //! - **Linux**: Coming soon (libevdev)
 
+pub mod drag;
 pub mod events;
 pub mod platform;
 pub mod storage;
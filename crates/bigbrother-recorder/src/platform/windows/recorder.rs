@@ -2,8 +2,10 @@
 //!
 //! Captures global keyboard and mouse events.
 
+use crate::drag::DragTracker;
 use crate::events::*;
 use anyhow::Result;
+use bigbrother_core::Key;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -233,6 +235,7 @@ fn run_rdev_listener(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, c
         start: Instant,
         config: RecorderConfig,
         last_mouse: (f64, f64),
+        drag: DragTracker,
         text_buf: String,
         last_text_time: Option<Instant>,
     }
@@ -242,6 +245,7 @@ fn run_rdev_listener(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, c
         start,
         config,
         last_mouse: (0.0, 0.0),
+        drag: DragTracker::default(),
         text_buf: String::new(),
         last_text_time: None,
     }));
@@ -267,6 +271,7 @@ fn run_rdev_listener(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, c
                     rdev::Button::Middle => 2,
                     _ => 0,
                 };
+                s.drag.press(x as i32, y as i32, b);
                 let _ = s.tx.try_send(Event {
                     t,
                     data: EventData::Click {
@@ -285,13 +290,26 @@ fn run_rdev_listener(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, c
 
                 if dist >= s.config.mouse_move_threshold {
                     s.last_mouse = (x, y);
-                    let _ = s.tx.try_send(Event {
-                        t,
-                        data: EventData::Move {
-                            x: x as i32,
-                            y: y as i32,
-                        },
-                    });
+                    let (x, y) = (x as i32, y as i32);
+                    match s.drag.motion(x, y) {
+                        Some(events) => {
+                            for data in events {
+                                let _ = s.tx.try_send(Event { t, data });
+                            }
+                        }
+                        None => {
+                            let _ = s.tx.try_send(Event {
+                                t,
+                                data: EventData::Move { x, y },
+                            });
+                        }
+                    }
+                }
+            }
+            EventType::ButtonRelease(_) => {
+                let (x, y) = s.last_mouse;
+                if let Some(data) = s.drag.release(x as i32, y as i32) {
+                    let _ = s.tx.try_send(Event { t, data });
                 }
             }
             EventType::Wheel { delta_x, delta_y } => {
@@ -306,18 +324,23 @@ fn run_rdev_listener(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, c
                     },
                 });
             }
-            EventType::KeyPress(key) => {
-                let keycode = key_to_code(&key);
+            EventType::KeyPress(raw) => {
+                let key = to_key(&raw);
+                let keycode = match (key, raw) {
+                    (Some(key), _) => key.to_vk(),
+                    (None, rdev::Key::Unknown(code)) => code as u16,
+                    (None, _) => 0,
+                };
 
                 // Check for Ctrl+C/X/V
                 // For now, just record key events
                 let _ = s.tx.try_send(Event {
                     t,
-                    data: EventData::Key { k: keycode, m: 0 },
+                    data: EventData::key(keycode, Modifiers::default(), key),
                 });
 
                 // Try to get character for text aggregation
-                if let Some(c) = key_to_char(&key) {
+                if let Some(c) = key.and_then(|k| k.to_char(false)) {
                     s.text_buf.push(c);
                     s.last_text_time = Some(Instant::now());
                 }
@@ -346,125 +369,90 @@ fn run_rdev_listener(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, c
     }
 }
 
-fn key_to_code(key: &rdev::Key) -> u16 {
-    use rdev::Key;
-    match key {
-        Key::Alt => 0x12,
-        Key::AltGr => 0x12,
-        Key::Backspace => 0x08,
-        Key::CapsLock => 0x14,
-        Key::ControlLeft | Key::ControlRight => 0x11,
-        Key::Delete => 0x2E,
-        Key::DownArrow => 0x28,
-        Key::End => 0x23,
-        Key::Escape => 0x1B,
-        Key::F1 => 0x70,
-        Key::F2 => 0x71,
-        Key::F3 => 0x72,
-        Key::F4 => 0x73,
-        Key::F5 => 0x74,
-        Key::F6 => 0x75,
-        Key::F7 => 0x76,
-        Key::F8 => 0x77,
-        Key::F9 => 0x78,
-        Key::F10 => 0x79,
-        Key::F11 => 0x7A,
-        Key::F12 => 0x7B,
-        Key::Home => 0x24,
-        Key::LeftArrow => 0x25,
-        Key::MetaLeft | Key::MetaRight => 0x5B,
-        Key::PageDown => 0x22,
-        Key::PageUp => 0x21,
-        Key::Return => 0x0D,
-        Key::RightArrow => 0x27,
-        Key::ShiftLeft | Key::ShiftRight => 0x10,
-        Key::Space => 0x20,
-        Key::Tab => 0x09,
-        Key::UpArrow => 0x26,
-        Key::Num0 => 0x30,
-        Key::Num1 => 0x31,
-        Key::Num2 => 0x32,
-        Key::Num3 => 0x33,
-        Key::Num4 => 0x34,
-        Key::Num5 => 0x35,
-        Key::Num6 => 0x36,
-        Key::Num7 => 0x37,
-        Key::Num8 => 0x38,
-        Key::Num9 => 0x39,
-        Key::KeyA => 0x41,
-        Key::KeyB => 0x42,
-        Key::KeyC => 0x43,
-        Key::KeyD => 0x44,
-        Key::KeyE => 0x45,
-        Key::KeyF => 0x46,
-        Key::KeyG => 0x47,
-        Key::KeyH => 0x48,
-        Key::KeyI => 0x49,
-        Key::KeyJ => 0x4A,
-        Key::KeyK => 0x4B,
-        Key::KeyL => 0x4C,
-        Key::KeyM => 0x4D,
-        Key::KeyN => 0x4E,
-        Key::KeyO => 0x4F,
-        Key::KeyP => 0x50,
-        Key::KeyQ => 0x51,
-        Key::KeyR => 0x52,
-        Key::KeyS => 0x53,
-        Key::KeyT => 0x54,
-        Key::KeyU => 0x55,
-        Key::KeyV => 0x56,
-        Key::KeyW => 0x57,
-        Key::KeyX => 0x58,
-        Key::KeyY => 0x59,
-        Key::KeyZ => 0x5A,
-        _ => 0,
-    }
-}
-
-fn key_to_char(key: &rdev::Key) -> Option<char> {
-    use rdev::Key;
-    match key {
-        Key::KeyA => Some('a'),
-        Key::KeyB => Some('b'),
-        Key::KeyC => Some('c'),
-        Key::KeyD => Some('d'),
-        Key::KeyE => Some('e'),
-        Key::KeyF => Some('f'),
-        Key::KeyG => Some('g'),
-        Key::KeyH => Some('h'),
-        Key::KeyI => Some('i'),
-        Key::KeyJ => Some('j'),
-        Key::KeyK => Some('k'),
-        Key::KeyL => Some('l'),
-        Key::KeyM => Some('m'),
-        Key::KeyN => Some('n'),
-        Key::KeyO => Some('o'),
-        Key::KeyP => Some('p'),
-        Key::KeyQ => Some('q'),
-        Key::KeyR => Some('r'),
-        Key::KeyS => Some('s'),
-        Key::KeyT => Some('t'),
-        Key::KeyU => Some('u'),
-        Key::KeyV => Some('v'),
-        Key::KeyW => Some('w'),
-        Key::KeyX => Some('x'),
-        Key::KeyY => Some('y'),
-        Key::KeyZ => Some('z'),
-        Key::Num0 => Some('0'),
-        Key::Num1 => Some('1'),
-        Key::Num2 => Some('2'),
-        Key::Num3 => Some('3'),
-        Key::Num4 => Some('4'),
-        Key::Num5 => Some('5'),
-        Key::Num6 => Some('6'),
-        Key::Num7 => Some('7'),
-        Key::Num8 => Some('8'),
-        Key::Num9 => Some('9'),
-        Key::Space => Some(' '),
-        Key::Return => Some('\n'),
-        Key::Tab => Some('\t'),
-        _ => None,
-    }
+fn to_key(key: &rdev::Key) -> Option<Key> {
+    use rdev::Key as K;
+    Some(match key {
+        K::Alt | K::AltGr => Key::Alt,
+        K::Backspace => Key::Backspace,
+        K::CapsLock => Key::CapsLock,
+        K::ControlLeft | K::ControlRight => Key::Control,
+        K::Delete => Key::ForwardDelete,
+        K::Insert => Key::Insert,
+        K::DownArrow => Key::Down,
+        K::UpArrow => Key::Up,
+        K::LeftArrow => Key::Left,
+        K::RightArrow => Key::Right,
+        K::End => Key::End,
+        K::Home => Key::Home,
+        K::PageDown => Key::PageDown,
+        K::PageUp => Key::PageUp,
+        K::Escape => Key::Escape,
+        K::F1 => Key::F1,
+        K::F2 => Key::F2,
+        K::F3 => Key::F3,
+        K::F4 => Key::F4,
+        K::F5 => Key::F5,
+        K::F6 => Key::F6,
+        K::F7 => Key::F7,
+        K::F8 => Key::F8,
+        K::F9 => Key::F9,
+        K::F10 => Key::F10,
+        K::F11 => Key::F11,
+        K::F12 => Key::F12,
+        K::MetaLeft | K::MetaRight => Key::Meta,
+        K::ShiftLeft | K::ShiftRight => Key::Shift,
+        K::Return => Key::Return,
+        K::Space => Key::Space,
+        K::Tab => Key::Tab,
+        K::Num0 => Key::Num0,
+        K::Num1 => Key::Num1,
+        K::Num2 => Key::Num2,
+        K::Num3 => Key::Num3,
+        K::Num4 => Key::Num4,
+        K::Num5 => Key::Num5,
+        K::Num6 => Key::Num6,
+        K::Num7 => Key::Num7,
+        K::Num8 => Key::Num8,
+        K::Num9 => Key::Num9,
+        K::Minus => Key::Minus,
+        K::Equal => Key::Equal,
+        K::LeftBracket => Key::LeftBracket,
+        K::RightBracket => Key::RightBracket,
+        K::BackSlash => Key::Backslash,
+        K::SemiColon => Key::Semicolon,
+        K::Quote => Key::Quote,
+        K::Comma => Key::Comma,
+        K::Dot => Key::Period,
+        K::Slash => Key::Slash,
+        K::BackQuote => Key::Grave,
+        K::KeyA => Key::A,
+        K::KeyB => Key::B,
+        K::KeyC => Key::C,
+        K::KeyD => Key::D,
+        K::KeyE => Key::E,
+        K::KeyF => Key::F,
+        K::KeyG => Key::G,
+        K::KeyH => Key::H,
+        K::KeyI => Key::I,
+        K::KeyJ => Key::J,
+        K::KeyK => Key::K,
+        K::KeyL => Key::L,
+        K::KeyM => Key::M,
+        K::KeyN => Key::N,
+        K::KeyO => Key::O,
+        K::KeyP => Key::P,
+        K::KeyQ => Key::Q,
+        K::KeyR => Key::R,
+        K::KeyS => Key::S,
+        K::KeyT => Key::T,
+        K::KeyU => Key::U,
+        K::KeyV => Key::V,
+        K::KeyW => Key::W,
+        K::KeyX => Key::X,
+        K::KeyY => Key::Y,
+        K::KeyZ => Key::Z,
+        _ => return None,
+    })
 }
 
 // ============================================================================
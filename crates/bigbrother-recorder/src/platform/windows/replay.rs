@@ -2,26 +2,38 @@
 //!
 //! Uses SendInput for input injection.
 
+use crate::drag::click_starts_drag;
 use crate::events::*;
 use anyhow::Result;
+use bigbrother_core::{
+    InputLease, Key, LeaseOptions, MotionProfile, Pacer, PacingPolicy, Scroll, TextEntryOptions,
+};
 use std::time::Duration;
 
 use windows::Win32::UI::Input::KeyboardAndMouse::{
     SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT, MOUSEINPUT,
-    KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP,
+    KEYEVENTF_KEYUP, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP,
     MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP,
-    MOUSEEVENTF_WHEEL, VIRTUAL_KEY,
+    MOUSE_EVENT_FLAGS, VIRTUAL_KEY,
 };
 use windows::Win32::UI::WindowsAndMessaging::SetCursorPos;
 
 /// Replay recorded workflows
 pub struct Replayer {
     speed: f64,
+    motion: MotionProfile,
+    pacer: Pacer,
+    lease: LeaseOptions,
 }
 
 impl Replayer {
     pub fn new() -> Self {
-        Self { speed: 1.0 }
+        Self {
+            speed: 1.0,
+            motion: MotionProfile::instant(),
+            pacer: Pacer::new(replay_pacing()),
+            lease: LeaseOptions::default(),
+        }
     }
 
     pub fn speed(mut self, speed: f64) -> Self {
@@ -29,39 +41,94 @@ impl Replayer {
         self
     }
 
+    /// Glide the pointer between recorded positions instead of jumping,
+    /// using the time until each event is due
+    pub fn motion(mut self, profile: MotionProfile) -> Self {
+        self.motion = profile;
+        self
+    }
+
+    /// Pace clicks, keys, scrolls and text; the recorded timing still
+    /// decides when each event is due, see `bigbrother_core::pacing`.
+    /// Nothing is read back during a replay, so the pace never adapts.
+    pub fn pacing(mut self, policy: PacingPolicy) -> Self {
+        self.pacer = Pacer::new(policy);
+        self
+    }
+
+    /// How playback waits for the input lease, see `bigbrother_core::lease`;
+    /// it is held for the whole workflow
+    pub fn input_lease(mut self, options: LeaseOptions) -> Self {
+        self.lease = options;
+        self
+    }
+
     pub fn play(&self, workflow: &RecordedWorkflow) -> Result<ReplayStats> {
+        let _lease = InputLease::acquire(&self.lease)?;
         let mut stats = ReplayStats::default();
         let mut last_t = 0u64;
+        let mut pointer = bigbrother_core::mouse_position().ok();
 
-        for event in &workflow.events {
-            if event.t > last_t {
-                let delay_ms = ((event.t - last_t) as f64 / self.speed) as u64;
-                if delay_ms > 0 {
-                    std::thread::sleep(Duration::from_millis(delay_ms));
-                }
-            }
+        for (i, event) in workflow.events.iter().enumerate() {
+            let mut delay_ms = (event.t.saturating_sub(last_t) as f64 / self.speed) as u64;
             last_t = event.t;
 
+            // Spend the end of the wait gliding to where the event happens
+            let glide = match (pointer, event.data.glide_target()) {
+                (Some(from), Some(to)) if !self.motion.is_instant() => Some((from, to, self.motion.within(delay_ms))),
+                _ => None,
+            };
+            if let Some((_, _, profile)) = &glide {
+                delay_ms -= profile.duration_ms;
+            }
+            if delay_ms > 0 {
+                std::thread::sleep(Duration::from_millis(delay_ms));
+            }
+            if let Some((from, to, profile)) = glide {
+                self.glide(from, to, &profile)?;
+            }
+            pointer = event.data.position().or(pointer);
+
             match &event.data {
                 EventData::Click { x, y, b, n, .. } => {
-                    self.click(*x, *y, *b, *n)?;
-                    stats.clicks += 1;
+                    // The MouseDown that follows presses instead
+                    if !click_starts_drag(&workflow.events, i) {
+                        self.pacer.action(|| self.click(*x, *y, *b, *n))?;
+                        stats.clicks += 1;
+                    }
+                }
+                EventData::MouseDown { x, y, b } => {
+                    self.move_to(*x, *y)?;
+                    self.button(*b, true)?;
+                    stats.drags += 1;
+                }
+                EventData::Drag { x, y, .. } => self.move_to(*x, *y)?,
+                EventData::MouseUp { x, y, b } => {
+                    self.move_to(*x, *y)?;
+                    self.button(*b, false)?;
                 }
                 EventData::Move { x, y } => {
                     self.move_to(*x, *y)?;
                     stats.moves += 1;
                 }
-                EventData::Scroll { x, y, dy, .. } => {
-                    self.scroll(*x, *y, *dy)?;
+                EventData::Scroll { x, y, dx, dy } => {
+                    self.pacer.action(|| self.scroll(*x, *y, *dx, *dy))?;
                     stats.scrolls += 1;
                 }
-                EventData::Key { k, .. } => {
-                    self.key(*k)?;
-                    stats.keys += 1;
+                EventData::Key { k, m, n, o } => {
+                    match replay_key(*k, *m, n.as_ref(), o.as_deref(), Key::to_vk) {
+                        Some((code, mods)) => {
+                            self.pacer.action(|| self.key(code, mods))?;
+                            stats.keys += 1;
+                        }
+                        None => stats.skipped_keys += 1,
+                    }
                 }
                 EventData::Text { s } => {
-                    self.type_text(s)?;
-                    stats.text_chars += s.len();
+                    let options = self.pacer.text_entry(&TextEntryOptions::default());
+                    let report = self.pacer.action(|| bigbrother_core::enter_text(s, &options));
+                    stats.text_chars += report.typed;
+                    stats.untyped_chars += report.untyped.len();
                 }
                 _ => {}
             }
@@ -74,11 +141,7 @@ impl Replayer {
         self.move_to(x, y)?;
         std::thread::sleep(Duration::from_millis(10));
 
-        let (down_flags, up_flags) = match button {
-            0 => (MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP),
-            1 => (MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP),
-            _ => (MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP),
-        };
+        let (down_flags, up_flags) = button_flags(button);
 
         for _ in 0..clicks.max(1) {
             let inputs = [
@@ -95,6 +158,21 @@ impl Replayer {
         Ok(())
     }
 
+    /// Press or release a button without the other half of a click
+    fn button(&self, button: u8, down: bool) -> Result<()> {
+        let (down_flags, up_flags) = button_flags(button);
+        let flags = if down { down_flags } else { up_flags };
+        send_inputs(&[make_mouse_input(flags, 0)])
+    }
+
+    fn glide(&self, from: (i32, i32), to: (i32, i32), profile: &MotionProfile) -> Result<()> {
+        for (x, y) in profile.path(from, to) {
+            self.move_to(x, y)?;
+            std::thread::sleep(Duration::from_millis(profile.step_ms));
+        }
+        Ok(())
+    }
+
     fn move_to(&self, x: i32, y: i32) -> Result<()> {
         unsafe {
             SetCursorPos(x, y)
@@ -103,33 +181,20 @@ impl Replayer {
         Ok(())
     }
 
-    fn scroll(&self, x: i32, y: i32, dy: i16) -> Result<()> {
-        self.move_to(x, y)?;
-        let inputs = [make_mouse_input(MOUSEEVENTF_WHEEL, dy as i32 * 120)];
-        send_inputs(&inputs)
-    }
-
-    fn key(&self, keycode: u16) -> Result<()> {
-        let inputs = [
-            make_key_input(keycode, false),
-            make_key_input(keycode, true),
-        ];
-        send_inputs(&inputs)?;
-        std::thread::sleep(Duration::from_millis(10));
+    fn scroll(&self, x: i32, y: i32, dx: i16, dy: i16) -> Result<()> {
+        // Recorded vertical deltas count up as positive, horizontal ones right
+        let scroll = Scroll::lines(dx as i32, -(dy as i32));
+        bigbrother_core::scroll_at(x, y, &scroll)?;
         Ok(())
     }
 
-    fn type_text(&self, text: &str) -> Result<()> {
-        let mut inputs = Vec::new();
-
-        for c in text.chars() {
-            let code = c as u16;
-            inputs.push(make_unicode_input(code, false));
-            inputs.push(make_unicode_input(code, true));
-        }
-
-        send_inputs(&inputs)?;
-        Ok(())
+    fn key(&self, keycode: u16, modifiers: Modifiers) -> Result<()> {
+        let held: Vec<u16> = modifiers.held().iter().map(|m| m.key().to_vk()).collect();
+        let mut inputs: Vec<INPUT> = held.iter().map(|&vk| make_key_input(vk, false)).collect();
+        inputs.push(make_key_input(keycode, false));
+        inputs.push(make_key_input(keycode, true));
+        inputs.extend(held.iter().rev().map(|&vk| make_key_input(vk, true)));
+        send_inputs(&inputs)
     }
 }
 
@@ -142,14 +207,27 @@ impl Default for Replayer {
 #[derive(Debug, Default)]
 pub struct ReplayStats {
     pub clicks: usize,
+    pub drags: usize,
     pub moves: usize,
     pub scrolls: usize,
     pub keys: usize,
+    /// Keys recorded on another platform with no logical name to translate
+    pub skipped_keys: usize,
     pub text_chars: usize,
+    /// Characters no text entry strategy could type
+    pub untyped_chars: usize,
 }
 
 // Helper functions
 
+fn button_flags(button: u8) -> (MOUSE_EVENT_FLAGS, MOUSE_EVENT_FLAGS) {
+    match button {
+        0 => (MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP),
+        1 => (MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP),
+        _ => (MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP),
+    }
+}
+
 fn make_mouse_input(flags: windows::Win32::UI::Input::KeyboardAndMouse::MOUSE_EVENT_FLAGS, data: i32) -> INPUT {
     INPUT {
         r#type: INPUT_MOUSE,
@@ -187,26 +265,6 @@ fn make_key_input(vk: u16, key_up: bool) -> INPUT {
     }
 }
 
-fn make_unicode_input(char_code: u16, key_up: bool) -> INPUT {
-    let mut flags = KEYEVENTF_UNICODE;
-    if key_up {
-        flags |= KEYEVENTF_KEYUP;
-    }
-
-    INPUT {
-        r#type: INPUT_KEYBOARD,
-        Anonymous: INPUT_0 {
-            ki: KEYBDINPUT {
-                wVk: VIRTUAL_KEY(0),
-                wScan: char_code,
-                dwFlags: flags,
-                time: 0,
-                dwExtraInfo: 0,
-            },
-        },
-    }
-}
-
 fn send_inputs(inputs: &[INPUT]) -> Result<()> {
     let sent = unsafe {
         SendInput(inputs, std::mem::size_of::<INPUT>() as i32)
@@ -2,24 +2,21 @@
 //!
 //! Optimized for minimal CPU/memory usage while capturing everything.
 
+use crate::drag::DragTracker;
 use crate::events::*;
 use anyhow::Result;
+use bigbrother_core::Key;
 pub use crossbeam_channel::{Receiver, Sender};
-use crossbeam_channel::bounded;
+use crossbeam_channel::{bounded, RecvTimeoutError};
 use parking_lot::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use cidre::{cf, cg, ns};
 use cidre::cg::event::access as cg_access;
 
-// Keycodes for clipboard operations
-const KEY_C: u16 = 8;
-const KEY_X: u16 = 7;
-const KEY_V: u16 = 9;
-
 /// Recorder configuration
 #[derive(Debug, Clone)]
 pub struct RecorderConfig {
@@ -221,12 +218,13 @@ impl WorkflowRecorder {
 
         let mut threads = Vec::new();
 
-        // Thread 1: CGEventTap for input events (includes clipboard via Cmd+C/X/V)
+        // Thread 1: CGEventTap for input events
+        let (clip_tx, clip_rx) = bounded::<ClipboardRead>(CLIPBOARD_QUEUE);
         let tx1 = tx.clone();
         let stop1 = stop.clone();
         let config1 = self.config.clone();
         threads.push(thread::spawn(move || {
-            run_event_tap(tx1, stop1, start_time, config1);
+            run_event_tap(tx1, clip_tx, stop1, start_time, config1);
         }));
 
         // Thread 2: App/window switch notifications
@@ -236,6 +234,13 @@ impl WorkflowRecorder {
             run_app_observer(tx2, stop2, start_time);
         }));
 
+        // Thread 3: clipboard reads for Cmd+C/X/V, too slow for the tap
+        let tx3 = tx.clone();
+        let stop3 = stop.clone();
+        threads.push(thread::spawn(move || {
+            run_clipboard_reader(clip_rx, tx3, stop3);
+        }));
+
         Ok(((threads, stop), rx))
     }
 }
@@ -252,9 +257,11 @@ impl Default for WorkflowRecorder {
 
 struct TapState {
     tx: Sender<Event>,
+    clipboard: Sender<ClipboardRead>,
     start: Instant,
     config: RecorderConfig,
     last_mouse: Mutex<(f64, f64)>,
+    drag: Mutex<DragTracker>,
     text_buf: Mutex<TextBuffer>,
 }
 
@@ -303,7 +310,13 @@ impl TextBuffer {
     }
 }
 
-fn run_event_tap(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, config: RecorderConfig) {
+fn run_event_tap(
+    tx: Sender<Event>,
+    clipboard: Sender<ClipboardRead>,
+    stop: Arc<AtomicBool>,
+    start: Instant,
+    config: RecorderConfig,
+) {
     // Build event mask - capture everything
     let mask = cg::EventType::LEFT_MOUSE_DOWN.mask()
         | cg::EventType::LEFT_MOUSE_UP.mask()
@@ -317,9 +330,11 @@ fn run_event_tap(tx: Sender<Event>, stop: Arc<AtomicBool>, start: Instant, confi
 
     let state = Box::leak(Box::new(TapState {
         tx,
+        clipboard,
         start,
         config: config.clone(),
         last_mouse: Mutex::new((0.0, 0.0)),
+        drag: Mutex::new(DragTracker::default()),
         text_buf: Mutex::new(TextBuffer::new(config.text_timeout_ms)),
     }));
 
@@ -388,6 +403,7 @@ extern "C" fn tap_callback(
         cg::EventType::LEFT_MOUSE_DOWN | cg::EventType::RIGHT_MOUSE_DOWN => {
             let btn = if event_type == cg::EventType::LEFT_MOUSE_DOWN { 0 } else { 1 };
             let clicks = event.field_i64(cg::EventField::MOUSE_EVENT_CLICK_STATE) as u8;
+            state.drag.lock().press(loc.x as i32, loc.y as i32, btn);
 
             let _ = state.tx.try_send(Event {
                 t,
@@ -427,13 +443,26 @@ extern "C" fn tap_callback(
 
             if dist >= state.config.mouse_move_threshold {
                 *last = (loc.x, loc.y);
-                let _ = state.tx.try_send(Event {
-                    t,
-                    data: EventData::Move {
-                        x: loc.x as i32,
-                        y: loc.y as i32,
-                    },
-                });
+                let (x, y) = (loc.x as i32, loc.y as i32);
+                match state.drag.lock().motion(x, y) {
+                    Some(events) => {
+                        for data in events {
+                            let _ = state.tx.try_send(Event { t, data });
+                        }
+                    }
+                    None => {
+                        let _ = state.tx.try_send(Event {
+                            t,
+                            data: EventData::Move { x, y },
+                        });
+                    }
+                }
+            }
+        }
+
+        cg::EventType::LEFT_MOUSE_UP | cg::EventType::RIGHT_MOUSE_UP => {
+            if let Some(data) = state.drag.lock().release(loc.x as i32, loc.y as i32) {
+                let _ = state.tx.try_send(Event { t, data });
             }
         }
 
@@ -455,66 +484,29 @@ extern "C" fn tap_callback(
 
         cg::EventType::KEY_DOWN => {
             let keycode = event.field_i64(cg::EventField::KEYBOARD_EVENT_KEYCODE) as u16;
+            let key = Key::from_mac(keycode);
 
             // Check for clipboard operations (Cmd+C, Cmd+X, Cmd+V)
             if mods.has_cmd() && !mods.has_ctrl() {
-                match keycode {
-                    KEY_C => {
-                        // Copy - capture clipboard after a short delay
-                        let tx = state.tx.clone();
-                        let start = state.start;
-                        std::thread::spawn(move || {
-                            // Wait for clipboard to be populated
-                            std::thread::sleep(std::time::Duration::from_millis(50));
-                            if let Some(content) = get_clipboard() {
-                                let _ = tx.try_send(Event {
-                                    t: start.elapsed().as_millis() as u64,
-                                    data: EventData::Paste { o: 'c', s: truncate(&content, 100) },
-                                });
-                            }
-                        });
-                        // Also record the key event
-                        let _ = state.tx.try_send(Event {
-                            t,
-                            data: EventData::Key { k: keycode, m: mods.0 },
-                        });
-                    }
-                    KEY_X => {
-                        // Cut - capture clipboard after a short delay
-                        let tx = state.tx.clone();
-                        let start = state.start;
-                        std::thread::spawn(move || {
-                            std::thread::sleep(std::time::Duration::from_millis(50));
-                            if let Some(content) = get_clipboard() {
-                                let _ = tx.try_send(Event {
-                                    t: start.elapsed().as_millis() as u64,
-                                    data: EventData::Paste { o: 'x', s: truncate(&content, 100) },
-                                });
-                            }
-                        });
-                        let _ = state.tx.try_send(Event {
-                            t,
-                            data: EventData::Key { k: keycode, m: mods.0 },
-                        });
-                    }
-                    KEY_V => {
-                        // Paste - capture what's being pasted
-                        if let Some(content) = get_clipboard() {
-                            let _ = state.tx.try_send(Event {
-                                t,
-                                data: EventData::Paste { o: 'v', s: truncate(&content, 100) },
-                            });
-                        }
+                match key {
+                    Some(Key::C | Key::X | Key::V) => {
+                        let o = match key {
+                            Some(Key::C) => 'c',
+                            Some(Key::X) => 'x',
+                            _ => 'v',
+                        };
+                        // Reading the clipboard is slow; the reader thread does it
+                        let _ = state.clipboard.try_send(ClipboardRead { t, o });
                         let _ = state.tx.try_send(Event {
                             t,
-                            data: EventData::Key { k: keycode, m: mods.0 },
+                            data: EventData::key(keycode, mods, key),
                         });
                     }
                     _ => {
                         // Other Cmd combo
                         let _ = state.tx.try_send(Event {
                             t,
-                            data: EventData::Key { k: keycode, m: mods.0 },
+                            data: EventData::key(keycode, mods, key),
                         });
                     }
                 }
@@ -522,7 +514,7 @@ extern "C" fn tap_callback(
                 // Other modifier combo
                 let _ = state.tx.try_send(Event {
                     t,
-                    data: EventData::Key { k: keycode, m: mods.0 },
+                    data: EventData::key(keycode, mods, key),
                 });
             } else if let Some(c) = keycode_to_char(keycode, mods) {
                 // Aggregate into text buffer
@@ -531,7 +523,7 @@ extern "C" fn tap_callback(
                 // Unknown key, record as key event
                 let _ = state.tx.try_send(Event {
                     t,
-                    data: EventData::Key { k: keycode, m: mods.0 },
+                    data: EventData::key(keycode, mods, key),
                 });
             }
         }
@@ -542,14 +534,50 @@ extern "C" fn tap_callback(
     Some(event)
 }
 
-/// Get clipboard content via pbpaste
+// ============================================================================
+// Clipboard Reader Thread
+// ============================================================================
+
+/// Copies, cuts and pastes waiting to be read
+const CLIPBOARD_QUEUE: usize = 64;
+
+/// Time for the app to put copied content on the clipboard
+const COPY_SETTLE_MS: u64 = 50;
+
+const CLIPBOARD_POLL_MS: u64 = 50;
+
+/// A clipboard operation the tap saw: when, and `c`, `x` or `v`
+struct ClipboardRead {
+    t: u64,
+    o: char,
+}
+
+/// Record what each copy, cut and paste put on or took off the clipboard
+///
+/// Reading the clipboard takes tens of milliseconds, and macOS disables an
+/// event tap whose callback is that slow, so the tap hands the reads over.
+fn run_clipboard_reader(reads: Receiver<ClipboardRead>, tx: Sender<Event>, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        let read = match reads.recv_timeout(Duration::from_millis(CLIPBOARD_POLL_MS)) {
+            Ok(read) => read,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if read.o != 'v' {
+            thread::sleep(Duration::from_millis(COPY_SETTLE_MS));
+        }
+        if let Some(content) = get_clipboard() {
+            let _ = tx.try_send(Event {
+                t: read.t,
+                data: EventData::Paste { o: read.o, s: truncate(&content, 100) },
+            });
+        }
+    }
+}
+
+/// Plain text on the clipboard, if any
 fn get_clipboard() -> Option<String> {
-    std::process::Command::new("pbpaste")
-        .output()
-        .ok()
-        .filter(|o| o.status.success())
-        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
-        .filter(|s| !s.is_empty())
+    bigbrother_core::clipboard::get_text().ok().flatten().filter(|s| !s.is_empty())
 }
 
 fn get_element_context(x: f64, y: f64) -> Option<EventData> {
@@ -669,49 +697,5 @@ fn get_focused_window_title(pid: i32) -> Option<String> {
 
 fn keycode_to_char(keycode: u16, mods: Modifiers) -> Option<char> {
     let shift = mods.0 & Modifiers::SHIFT != 0 || mods.0 & Modifiers::CAPS != 0;
-
-    let c = match keycode {
-        // Letters
-        0 => 'a', 1 => 's', 2 => 'd', 3 => 'f', 4 => 'h', 5 => 'g', 6 => 'z', 7 => 'x',
-        8 => 'c', 9 => 'v', 11 => 'b', 12 => 'q', 13 => 'w', 14 => 'e', 15 => 'r',
-        16 => 'y', 17 => 't', 31 => 'o', 32 => 'u', 34 => 'i', 35 => 'p', 37 => 'l',
-        38 => 'j', 40 => 'k', 45 => 'n', 46 => 'm',
-        // Numbers
-        18 => if shift { '!' } else { '1' },
-        19 => if shift { '@' } else { '2' },
-        20 => if shift { '#' } else { '3' },
-        21 => if shift { '$' } else { '4' },
-        22 => if shift { '^' } else { '6' },
-        23 => if shift { '%' } else { '5' },
-        24 => if shift { '+' } else { '=' },
-        25 => if shift { '(' } else { '9' },
-        26 => if shift { '&' } else { '7' },
-        27 => if shift { '_' } else { '-' },
-        28 => if shift { '*' } else { '8' },
-        29 => if shift { ')' } else { '0' },
-        // Punctuation
-        30 => if shift { '}' } else { ']' },
-        33 => if shift { '{' } else { '[' },
-        39 => if shift { '"' } else { '\'' },
-        41 => if shift { ':' } else { ';' },
-        42 => if shift { '|' } else { '\\' },
-        43 => if shift { '<' } else { ',' },
-        44 => if shift { '?' } else { '/' },
-        47 => if shift { '>' } else { '.' },
-        50 => if shift { '~' } else { '`' },
-        // Whitespace
-        36 => '\n',
-        48 => '\t',
-        49 => ' ',
-        // Backspace - special handling
-        51 => '\x08',
-        _ => return None,
-    };
-
-    // Handle shift for letters
-    if shift && c.is_ascii_lowercase() {
-        Some(c.to_ascii_uppercase())
-    } else {
-        Some(c)
-    }
+    Key::from_mac(keycode)?.to_char(shift)
 }
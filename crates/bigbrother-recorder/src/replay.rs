@@ -1,7 +1,11 @@
 //! Workflow replay using CGEvent injection
 
+use crate::drag::click_starts_drag;
 use crate::events::*;
 use anyhow::Result;
+use bigbrother_core::{
+    InputLease, Key, LeaseOptions, MotionProfile, Pacer, PacingPolicy, Scroll, TextEntryOptions,
+};
 use std::time::Duration;
 
 use cidre::cg;
@@ -24,11 +28,19 @@ const HID_EVENT_TAP: u32 = 0;
 /// Replay recorded workflows
 pub struct Replayer {
     speed: f64,
+    motion: MotionProfile,
+    pacer: Pacer,
+    lease: LeaseOptions,
 }
 
 impl Replayer {
     pub fn new() -> Self {
-        Self { speed: 1.0 }
+        Self {
+            speed: 1.0,
+            motion: MotionProfile::instant(),
+            pacer: Pacer::new(replay_pacing()),
+            lease: LeaseOptions::default(),
+        }
     }
 
     /// Set playback speed (1.0 = real-time, 2.0 = 2x speed)
@@ -37,42 +49,94 @@ impl Replayer {
         self
     }
 
+    /// Glide the pointer between recorded positions instead of jumping,
+    /// using the time until each event is due
+    pub fn motion(mut self, profile: MotionProfile) -> Self {
+        self.motion = profile;
+        self
+    }
+
+    /// Pace clicks, keys, scrolls and text; the recorded timing still
+    /// decides when each event is due, see `bigbrother_core::pacing`.
+    /// Nothing is read back during a replay, so the pace never adapts.
+    pub fn pacing(mut self, policy: PacingPolicy) -> Self {
+        self.pacer = Pacer::new(policy);
+        self
+    }
+
+    /// How playback waits for the input lease, see `bigbrother_core::lease`;
+    /// it is held for the whole workflow
+    pub fn input_lease(mut self, options: LeaseOptions) -> Self {
+        self.lease = options;
+        self
+    }
+
     /// Replay a workflow
     pub fn play(&self, workflow: &RecordedWorkflow) -> Result<ReplayStats> {
+        let _lease = InputLease::acquire(&self.lease)?;
         let mut stats = ReplayStats::default();
         let mut last_t = 0u64;
+        let mut pointer = bigbrother_core::input::mouse_position().ok();
 
-        for event in &workflow.events {
+        for (i, event) in workflow.events.iter().enumerate() {
             // Wait for the right time
-            if event.t > last_t {
-                let delay_ms = ((event.t - last_t) as f64 / self.speed) as u64;
-                if delay_ms > 0 {
-                    std::thread::sleep(Duration::from_millis(delay_ms));
-                }
-            }
+            let mut delay_ms = (event.t.saturating_sub(last_t) as f64 / self.speed) as u64;
             last_t = event.t;
 
+            // Spend the end of the wait gliding to where the event happens
+            let glide = match (pointer, event.data.glide_target()) {
+                (Some(from), Some(to)) if !self.motion.is_instant() => Some((from, to, self.motion.within(delay_ms))),
+                _ => None,
+            };
+            if let Some((_, _, profile)) = &glide {
+                delay_ms -= profile.duration_ms;
+            }
+            if delay_ms > 0 {
+                std::thread::sleep(Duration::from_millis(delay_ms));
+            }
+            if let Some((from, to, profile)) = glide {
+                self.glide(from, to, &profile)?;
+            }
+            pointer = event.data.position().or(pointer);
+
             // Replay the event
             match &event.data {
                 EventData::Click { x, y, b, n, .. } => {
-                    self.click(*x, *y, *b, *n)?;
-                    stats.clicks += 1;
+                    // The MouseDown that follows presses instead
+                    if !click_starts_drag(&workflow.events, i) {
+                        self.pacer.action(|| self.click(*x, *y, *b, *n))?;
+                        stats.clicks += 1;
+                    }
                 }
+                EventData::MouseDown { x, y, b } => {
+                    self.move_to(*x, *y)?;
+                    self.button(*x, *y, *b, true);
+                    stats.drags += 1;
+                }
+                EventData::Drag { x, y, b } => self.drag_move(*x, *y, *b),
+                EventData::MouseUp { x, y, b } => self.button(*x, *y, *b, false),
                 EventData::Move { x, y } => {
                     self.move_to(*x, *y)?;
                     stats.moves += 1;
                 }
                 EventData::Scroll { x, y, dx, dy } => {
-                    self.scroll(*x, *y, *dx, *dy)?;
+                    self.pacer.action(|| self.scroll(*x, *y, *dx, *dy))?;
                     stats.scrolls += 1;
                 }
-                EventData::Key { k, m } => {
-                    self.key(*k, *m)?;
-                    stats.keys += 1;
+                EventData::Key { k, m, n, o } => {
+                    match replay_key(*k, *m, n.as_ref(), o.as_deref(), Key::to_mac) {
+                        Some((code, mods)) => {
+                            self.pacer.action(|| self.key(code, mods.0))?;
+                            stats.keys += 1;
+                        }
+                        None => stats.skipped_keys += 1,
+                    }
                 }
                 EventData::Text { s } => {
-                    self.type_text(s)?;
-                    stats.text_chars += s.len();
+                    let options = self.pacer.text_entry(&TextEntryOptions::default());
+                    let report = self.pacer.action(|| bigbrother_core::input::enter_text(s, &options));
+                    stats.text_chars += report.typed;
+                    stats.untyped_chars += report.untyped.len();
                 }
                 // Context, App, Paste events are informational - skip during replay
                 _ => {}
@@ -84,11 +148,7 @@ impl Replayer {
 
     fn click(&self, x: i32, y: i32, button: u8, clicks: u8) -> Result<()> {
         let pos = cg::Point { x: x as f64, y: y as f64 };
-        let btn = match button {
-            0 => cg::MouseButton::Left,
-            1 => cg::MouseButton::Right,
-            _ => cg::MouseButton::Center,
-        };
+        let btn = mouse_button(button);
 
         let down_type = match button {
             0 => cg::EventType::LEFT_MOUSE_DOWN,
@@ -119,6 +179,43 @@ impl Replayer {
         Ok(())
     }
 
+    /// Press or release a button without the other half of a click
+    fn button(&self, x: i32, y: i32, button: u8, down: bool) {
+        let kind = match (button, down) {
+            (0, true) => cg::EventType::LEFT_MOUSE_DOWN,
+            (0, false) => cg::EventType::LEFT_MOUSE_UP,
+            (1, true) => cg::EventType::RIGHT_MOUSE_DOWN,
+            (1, false) => cg::EventType::RIGHT_MOUSE_UP,
+            (_, true) => cg::EventType::OHTER_MOUSE_DOWN,
+            (_, false) => cg::EventType::OHTER_MOUSE_UP,
+        };
+        let pos = cg::Point { x: x as f64, y: y as f64 };
+        if let Some(evt) = cg::Event::mouse(None, kind, pos, mouse_button(button)) {
+            post_event(&evt, HID_EVENT_TAP);
+        }
+    }
+
+    /// Move with a button held, which apps see as dragging rather than hovering
+    fn drag_move(&self, x: i32, y: i32, button: u8) {
+        let kind = match button {
+            0 => cg::EventType::LEFT_MOUSE_DRAGGED,
+            1 => cg::EventType::RIGHT_MOUSE_DRAGGED,
+            _ => cg::EventType::OTHER_MOUSE_DRAGGED,
+        };
+        let pos = cg::Point { x: x as f64, y: y as f64 };
+        if let Some(evt) = cg::Event::mouse(None, kind, pos, mouse_button(button)) {
+            post_event(&evt, HID_EVENT_TAP);
+        }
+    }
+
+    fn glide(&self, from: (i32, i32), to: (i32, i32), profile: &MotionProfile) -> Result<()> {
+        for (x, y) in profile.path(from, to) {
+            self.move_to(x, y)?;
+            std::thread::sleep(Duration::from_millis(profile.step_ms));
+        }
+        Ok(())
+    }
+
     fn move_to(&self, x: i32, y: i32) -> Result<()> {
         let pos = cg::Point { x: x as f64, y: y as f64 };
         if let Some(evt) = cg::Event::mouse(None, cg::EventType::MOUSE_MOVED, pos, cg::MouseButton::Left) {
@@ -128,18 +225,9 @@ impl Replayer {
     }
 
     fn scroll(&self, x: i32, y: i32, dx: i16, dy: i16) -> Result<()> {
-        // Move to position first
-        self.move_to(x, y)?;
-
-        // Create scroll event
-        if let Some(evt) = cg::Event::wheel_2(
-            None,
-            cg::ScrollEventUnit::Line,
-            dy.unsigned_abs() as u32,
-            dx.unsigned_abs() as u32,
-        ) {
-            post_event(&evt, HID_EVENT_TAP);
-        }
+        // Recorded deltas are the wheel's, which count up and left as positive
+        let scroll = Scroll::lines(-(dx as i32), -(dy as i32));
+        bigbrother_core::input::scroll_at(x, y, &scroll)?;
         Ok(())
     }
 
@@ -167,17 +255,6 @@ impl Replayer {
 
         Ok(())
     }
-
-    fn type_text(&self, text: &str) -> Result<()> {
-        for c in text.chars() {
-            if let Some((keycode, shift)) = char_to_keycode(c) {
-                let mods = if shift { Modifiers::SHIFT } else { 0 };
-                self.key(keycode, mods)?;
-                std::thread::sleep(Duration::from_millis(20));
-            }
-        }
-        Ok(())
-    }
 }
 
 impl Default for Replayer {
@@ -189,66 +266,21 @@ impl Default for Replayer {
 #[derive(Debug, Default)]
 pub struct ReplayStats {
     pub clicks: usize,
+    pub drags: usize,
     pub moves: usize,
     pub scrolls: usize,
     pub keys: usize,
+    /// Keys recorded on another platform with no logical name to translate
+    pub skipped_keys: usize,
     pub text_chars: usize,
+    /// Characters no text entry strategy could type
+    pub untyped_chars: usize,
 }
 
-/// Convert char to (keycode, needs_shift)
-fn char_to_keycode(c: char) -> Option<(u16, bool)> {
-    Some(match c {
-        'a' | 'A' => (0, c.is_uppercase()),
-        'b' | 'B' => (11, c.is_uppercase()),
-        'c' | 'C' => (8, c.is_uppercase()),
-        'd' | 'D' => (2, c.is_uppercase()),
-        'e' | 'E' => (14, c.is_uppercase()),
-        'f' | 'F' => (3, c.is_uppercase()),
-        'g' | 'G' => (5, c.is_uppercase()),
-        'h' | 'H' => (4, c.is_uppercase()),
-        'i' | 'I' => (34, c.is_uppercase()),
-        'j' | 'J' => (38, c.is_uppercase()),
-        'k' | 'K' => (40, c.is_uppercase()),
-        'l' | 'L' => (37, c.is_uppercase()),
-        'm' | 'M' => (46, c.is_uppercase()),
-        'n' | 'N' => (45, c.is_uppercase()),
-        'o' | 'O' => (31, c.is_uppercase()),
-        'p' | 'P' => (35, c.is_uppercase()),
-        'q' | 'Q' => (12, c.is_uppercase()),
-        'r' | 'R' => (15, c.is_uppercase()),
-        's' | 'S' => (1, c.is_uppercase()),
-        't' | 'T' => (17, c.is_uppercase()),
-        'u' | 'U' => (32, c.is_uppercase()),
-        'v' | 'V' => (9, c.is_uppercase()),
-        'w' | 'W' => (13, c.is_uppercase()),
-        'x' | 'X' => (7, c.is_uppercase()),
-        'y' | 'Y' => (16, c.is_uppercase()),
-        'z' | 'Z' => (6, c.is_uppercase()),
-        '0' | ')' => (29, c == ')'),
-        '1' | '!' => (18, c == '!'),
-        '2' | '@' => (19, c == '@'),
-        '3' | '#' => (20, c == '#'),
-        '4' | '$' => (21, c == '$'),
-        '5' | '%' => (23, c == '%'),
-        '6' | '^' => (22, c == '^'),
-        '7' | '&' => (26, c == '&'),
-        '8' | '*' => (28, c == '*'),
-        '9' | '(' => (25, c == '('),
-        ' ' => (49, false),
-        '\n' => (36, false),
-        '\t' => (48, false),
-        '\x08' => (51, false), // backspace
-        '-' | '_' => (27, c == '_'),
-        '=' | '+' => (24, c == '+'),
-        '[' | '{' => (33, c == '{'),
-        ']' | '}' => (30, c == '}'),
-        '\\' | '|' => (42, c == '|'),
-        ';' | ':' => (41, c == ':'),
-        '\'' | '"' => (39, c == '"'),
-        ',' | '<' => (43, c == '<'),
-        '.' | '>' => (47, c == '>'),
-        '/' | '?' => (44, c == '?'),
-        '`' | '~' => (50, c == '~'),
-        _ => return None,
-    })
+fn mouse_button(button: u8) -> cg::MouseButton {
+    match button {
+        0 => cg::MouseButton::Left,
+        1 => cg::MouseButton::Right,
+        _ => cg::MouseButton::Center,
+    }
 }